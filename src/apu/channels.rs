@@ -0,0 +1,519 @@
+/*!  The five sound channels of the APU and the units they share */
+
+/// Lengths loaded in the length counters, indexed by the 5 high bits of the 4th register of a channel
+const LENGTH_TABLE: [u8; 32] = [
+    10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14, 12, 16, 24, 18, 48, 20, 96, 22,
+    192, 24, 72, 26, 16, 28, 32, 30,
+];
+
+const DUTY_SEQUENCES: [[u8; 8]; 4] = [
+    [0, 1, 0, 0, 0, 0, 0, 0],
+    [0, 1, 1, 0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 1, 0, 0, 0],
+    [1, 0, 0, 1, 1, 1, 1, 1],
+];
+
+const TRIANGLE_SEQUENCE: [u8; 32] = [
+    15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12,
+    13, 14, 15,
+];
+
+/// Noise timer periods, in CPU cycles
+const NOISE_PERIODS: [u16; 16] = [
+    4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
+];
+
+/// DMC timer periods, in CPU cycles
+const DMC_PERIODS: [u16; 16] = [
+    428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
+];
+
+/// Silences a channel once it has played for the length written in its registers
+pub struct LengthCounter {
+    enabled: bool,
+    pub halt: bool,
+    counter: u8,
+}
+
+impl LengthCounter {
+    fn new() -> Self {
+        Self {
+            enabled: false,
+            halt: false,
+            counter: 0,
+        }
+    }
+
+    /// Disabling the channel through $4015 clears the counter
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.counter = 0;
+        }
+    }
+
+    fn load(&mut self, index: u8) {
+        if self.enabled {
+            self.counter = LENGTH_TABLE[(index >> 3) as usize];
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.counter > 0
+    }
+
+    /// Clocked by the frame counter on half frames
+    pub fn clock(&mut self) {
+        if !self.halt && self.counter > 0 {
+            self.counter -= 1;
+        }
+    }
+}
+
+/// Volume of the pulse and noise channels, either constant or a decaying sawtooth
+pub struct Envelope {
+    start: bool,
+    looping: bool,
+    constant_volume: bool,
+    volume: u8, // Constant volume or period of the decay
+    divider: u8,
+    decay: u8,
+}
+
+impl Envelope {
+    fn new() -> Self {
+        Self {
+            start: false,
+            looping: false,
+            constant_volume: false,
+            volume: 0,
+            divider: 0,
+            decay: 0,
+        }
+    }
+
+    /// Writes the low 6 bits of the first register of a channel
+    fn write(&mut self, value: u8) {
+        self.looping = value & 0x20 == 0x20;
+        self.constant_volume = value & 0x10 == 0x10;
+        self.volume = value & 0x0F;
+    }
+
+    /// Clocked by the frame counter on quarter frames
+    pub fn clock(&mut self) {
+        if self.start {
+            self.start = false;
+            self.decay = 15;
+            self.divider = self.volume;
+        } else if self.divider == 0 {
+            self.divider = self.volume;
+            if self.decay > 0 {
+                self.decay -= 1;
+            } else if self.looping {
+                self.decay = 15;
+            }
+        } else {
+            self.divider -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        if self.constant_volume {
+            self.volume
+        } else {
+            self.decay
+        }
+    }
+}
+
+/// Square wave channel, with a sweep unit bending its period
+pub struct Pulse {
+    first: bool, // Pulse 1 negates the sweep with one's complement, pulse 2 with two's complement
+    has_sweep: bool,
+    pub length: LengthCounter,
+    pub envelope: Envelope,
+    duty: u8,
+    step: u8,
+    period: u16,
+    timer: u16,
+    sweep_enabled: bool,
+    sweep_period: u8,
+    sweep_negate: bool,
+    sweep_shift: u8,
+    sweep_divider: u8,
+    sweep_reload: bool,
+}
+
+impl Pulse {
+    pub fn new(first: bool) -> Self {
+        Self {
+            first,
+            has_sweep: true,
+            length: LengthCounter::new(),
+            envelope: Envelope::new(),
+            duty: 0,
+            step: 0,
+            period: 0,
+            timer: 0,
+            sweep_enabled: false,
+            sweep_period: 0,
+            sweep_negate: false,
+            sweep_shift: 0,
+            sweep_divider: 0,
+            sweep_reload: false,
+        }
+    }
+
+    /// A pulse channel of the MMC5, it has no sweep unit and nothing mutes it
+    pub fn without_sweep() -> Self {
+        Self {
+            has_sweep: false,
+            ..Self::new(true)
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr & 0b11 {
+            0 => {
+                self.duty = value >> 6;
+                self.length.halt = value & 0x20 == 0x20;
+                self.envelope.write(value);
+            }
+            1 if !self.has_sweep => {}
+            1 => {
+                self.sweep_enabled = value & 0x80 == 0x80;
+                self.sweep_period = (value >> 4) & 0b111;
+                self.sweep_negate = value & 0x08 == 0x08;
+                self.sweep_shift = value & 0b111;
+                self.sweep_reload = true;
+            }
+            2 => self.period = (self.period & 0x700) | value as u16,
+            _ => {
+                self.period = (self.period & 0xFF) | ((value as u16 & 0b111) << 8);
+                self.length.load(value);
+                self.step = 0;
+                self.envelope.start = true;
+            }
+        }
+    }
+
+    /// Clocked every other CPU cycle
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            self.step = (self.step + 1) % 8;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn sweep_target(&self) -> u16 {
+        let change = self.period >> self.sweep_shift;
+        if self.sweep_negate {
+            let change = if self.first { change + 1 } else { change };
+            self.period.saturating_sub(change)
+        } else {
+            self.period + change
+        }
+    }
+
+    fn is_muted(&self) -> bool {
+        self.has_sweep && (self.period < 8 || self.sweep_target() > 0x7FF)
+    }
+
+    /// Clocked by the frame counter on half frames
+    pub fn clock_sweep(&mut self) {
+        if self.sweep_divider == 0 && self.sweep_enabled && self.sweep_shift > 0 && !self.is_muted()
+        {
+            self.period = self.sweep_target();
+        }
+
+        if self.sweep_divider == 0 || self.sweep_reload {
+            self.sweep_divider = self.sweep_period;
+            self.sweep_reload = false;
+        } else {
+            self.sweep_divider -= 1;
+        }
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.length.is_active()
+            || self.is_muted()
+            || DUTY_SEQUENCES[self.duty as usize][self.step as usize] == 0
+        {
+            0
+        } else {
+            self.envelope.output()
+        }
+    }
+}
+
+/// Triangle wave channel, gated by a linear counter on top of the length counter
+pub struct Triangle {
+    pub length: LengthCounter,
+    control: bool,
+    linear_reload_value: u8,
+    linear_counter: u8,
+    linear_reload: bool,
+    step: u8,
+    period: u16,
+    timer: u16,
+}
+
+impl Triangle {
+    pub fn new() -> Self {
+        Self {
+            length: LengthCounter::new(),
+            control: false,
+            linear_reload_value: 0,
+            linear_counter: 0,
+            linear_reload: false,
+            step: 0,
+            period: 0,
+            timer: 0,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr & 0b11 {
+            0 => {
+                self.control = value & 0x80 == 0x80;
+                self.length.halt = self.control;
+                self.linear_reload_value = value & 0x7F;
+            }
+            1 => {}
+            2 => self.period = (self.period & 0x700) | value as u16,
+            _ => {
+                self.period = (self.period & 0xFF) | ((value as u16 & 0b111) << 8);
+                self.length.load(value);
+                self.linear_reload = true;
+            }
+        }
+    }
+
+    /// Clocked every CPU cycle
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            if self.length.is_active() && self.linear_counter > 0 {
+                self.step = (self.step + 1) % 32;
+            }
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    /// Clocked by the frame counter on quarter frames
+    pub fn clock_linear_counter(&mut self) {
+        if self.linear_reload {
+            self.linear_counter = self.linear_reload_value;
+        } else if self.linear_counter > 0 {
+            self.linear_counter -= 1;
+        }
+
+        if !self.control {
+            self.linear_reload = false;
+        }
+    }
+
+    pub fn output(&self) -> u8 {
+        // Ultrasonic periods hold the middle level instead of popping, like most emulators do
+        if self.period < 2 {
+            7
+        } else {
+            TRIANGLE_SEQUENCE[self.step as usize]
+        }
+    }
+}
+
+/// Pseudo-random noise from a 15-bit linear feedback shift register
+pub struct Noise {
+    pub length: LengthCounter,
+    pub envelope: Envelope,
+    short_mode: bool,
+    period: u16,
+    timer: u16,
+    shift_register: u16,
+}
+
+impl Noise {
+    pub fn new() -> Self {
+        Self {
+            length: LengthCounter::new(),
+            envelope: Envelope::new(),
+            short_mode: false,
+            period: NOISE_PERIODS[0],
+            timer: 0,
+            shift_register: 1,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr & 0b11 {
+            0 => {
+                self.length.halt = value & 0x20 == 0x20;
+                self.envelope.write(value);
+            }
+            1 => {}
+            2 => {
+                self.short_mode = value & 0x80 == 0x80;
+                self.period = NOISE_PERIODS[(value & 0x0F) as usize];
+            }
+            _ => {
+                self.length.load(value);
+                self.envelope.start = true;
+            }
+        }
+    }
+
+    /// Clocked every CPU cycle
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period - 1;
+            let tap = if self.short_mode { 6 } else { 1 };
+            let feedback = (self.shift_register ^ (self.shift_register >> tap)) & 1;
+            self.shift_register = (self.shift_register >> 1) | (feedback << 14);
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.length.is_active() || self.shift_register & 1 == 1 {
+            0
+        } else {
+            self.envelope.output()
+        }
+    }
+}
+
+/// Delta modulation channel, playing 1-bit delta samples read from CPU memory
+pub struct Dmc {
+    pub irq: bool,
+    irq_enabled: bool,
+    looping: bool,
+    period: u16,
+    timer: u16,
+    output_level: u8,
+    sample_address: u16,
+    sample_length: u16,
+    current_address: u16,
+    bytes_remaining: u16,
+    sample_buffer: Option<u8>,
+    shift_register: u8,
+    bits_remaining: u8,
+    silence: bool,
+}
+
+impl Dmc {
+    pub fn new() -> Self {
+        Self {
+            irq: false,
+            irq_enabled: false,
+            looping: false,
+            period: DMC_PERIODS[0],
+            timer: 0,
+            output_level: 0,
+            sample_address: 0xC000,
+            sample_length: 1,
+            current_address: 0xC000,
+            bytes_remaining: 0,
+            sample_buffer: None,
+            shift_register: 0,
+            bits_remaining: 8,
+            silence: true,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr & 0b11 {
+            0 => {
+                self.irq_enabled = value & 0x80 == 0x80;
+                if !self.irq_enabled {
+                    self.irq = false;
+                }
+                self.looping = value & 0x40 == 0x40;
+                self.period = DMC_PERIODS[(value & 0x0F) as usize];
+            }
+            1 => self.output_level = value & 0x7F,
+            2 => self.sample_address = 0xC000 | (value as u16) << 6,
+            _ => self.sample_length = (value as u16) << 4 | 1,
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if !enabled {
+            self.bytes_remaining = 0;
+        } else if self.bytes_remaining == 0 {
+            self.restart();
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.bytes_remaining > 0
+    }
+
+    fn restart(&mut self) {
+        self.current_address = self.sample_address;
+        self.bytes_remaining = self.sample_length;
+    }
+
+    pub fn read_request(&self) -> Option<u16> {
+        if self.sample_buffer.is_none() && self.bytes_remaining > 0 {
+            Some(self.current_address)
+        } else {
+            None
+        }
+    }
+
+    pub fn fill(&mut self, value: u8) {
+        self.sample_buffer = Some(value);
+        // The address wraps to $8000, not $0000
+        self.current_address = self.current_address.checked_add(1).unwrap_or(0x8000);
+        self.bytes_remaining -= 1;
+
+        if self.bytes_remaining == 0 {
+            if self.looping {
+                self.restart();
+            } else if self.irq_enabled {
+                self.irq = true;
+            }
+        }
+    }
+
+    /// Clocked every CPU cycle
+    pub fn clock_timer(&mut self) {
+        if self.timer > 0 {
+            self.timer -= 1;
+            return;
+        }
+        self.timer = self.period - 1;
+
+        if !self.silence {
+            if self.shift_register & 1 == 1 {
+                if self.output_level <= 125 {
+                    self.output_level += 2;
+                }
+            } else if self.output_level >= 2 {
+                self.output_level -= 2;
+            }
+        }
+        self.shift_register >>= 1;
+
+        self.bits_remaining -= 1;
+        if self.bits_remaining == 0 {
+            self.bits_remaining = 8;
+            match self.sample_buffer.take() {
+                Some(sample) => {
+                    self.silence = false;
+                    self.shift_register = sample;
+                }
+                None => self.silence = true,
+            }
+        }
+    }
+
+    pub fn output(&self) -> u8 {
+        self.output_level
+    }
+}
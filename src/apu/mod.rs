@@ -0,0 +1,337 @@
+/*!  Emulate the audio processing unit of the Ricoh 2A03 */
+
+mod channels;
+
+pub use self::channels::Pulse;
+
+use self::channels::{Dmc, Noise, Triangle};
+
+/// Frequency of the NTSC CPU, the APU is clocked from it
+pub const CPU_FREQUENCY: u32 = 1_789_773;
+/// Rate of the samples produced by the APU
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// CPU cycles at which the frame counter clocks the envelopes and length counters, in 4-step mode
+const FOUR_STEP_SEQUENCE: [u32; 4] = [7457, 14913, 22371, 29829];
+/// Same in 5-step mode, the fourth step does nothing
+const FIVE_STEP_SEQUENCE: [u32; 5] = [7457, 14913, 22371, 29829, 37281];
+
+/**
+The APU, as seen from the CPU through its memory mapped registers
+
+Pulse 1    $4000 - $4003
+Pulse 2    $4004 - $4007
+Triangle    $4008 - $400B
+Noise    $400C - $400F
+DMC    $4010 - $4013
+Status    $4015 (read/write)
+Frame counter    $4017 (write)
+*/
+pub struct Apu {
+    pulse1: Pulse,
+    pulse2: Pulse,
+    triangle: Triangle,
+    noise: Noise,
+    dmc: Dmc,
+    cycle: u32, // CPU cycles since the frame counter was reset
+    five_step_mode: bool,
+    irq_inhibit: bool,
+    frame_irq: bool,
+    frame_counter_reset: Option<u8>, // Cycles left before a $4017 write resets the frame counter
+    odd_cycle: bool,
+    expansion: f32,    // Output of the audio chip on the cartridge
+    sample_clock: u32, // Fraction of a sample elapsed, in units of 1 / CPU_FREQUENCY
+    sample_sum: f32,
+    sample_count: u32,
+    samples: Vec<f32>,
+}
+
+impl Default for Apu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Apu {
+    pub fn new() -> Self {
+        Self {
+            pulse1: Pulse::new(true),
+            pulse2: Pulse::new(false),
+            triangle: Triangle::new(),
+            noise: Noise::new(),
+            dmc: Dmc::new(),
+            cycle: 0,
+            five_step_mode: false,
+            irq_inhibit: false,
+            frame_irq: false,
+            frame_counter_reset: None,
+            odd_cycle: false,
+            expansion: 0.0,
+            sample_clock: 0,
+            sample_sum: 0.0,
+            sample_count: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    /// CPU read of $4015, clears the frame interrupt flag
+    pub fn read_status(&mut self) -> u8 {
+        let value = self.peek_status();
+        self.frame_irq = false;
+        value
+    }
+
+    /// Reads $4015 without clearing the frame interrupt flag, bit 5 is open bus and left clear
+    pub fn peek_status(&self) -> u8 {
+        (self.dmc.irq as u8) << 7
+            | (self.frame_irq as u8) << 6
+            | (self.dmc.is_active() as u8) << 4
+            | (self.noise.length.is_active() as u8) << 3
+            | (self.triangle.length.is_active() as u8) << 2
+            | (self.pulse2.length.is_active() as u8) << 1
+            | self.pulse1.length.is_active() as u8
+    }
+
+    /// CPU write to an APU register, `addr` is in $4000-$4017
+    pub fn write_register(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x4000..=0x4003 => self.pulse1.write(addr, value),
+            0x4004..=0x4007 => self.pulse2.write(addr, value),
+            0x4008..=0x400B => self.triangle.write(addr, value),
+            0x400C..=0x400F => self.noise.write(addr, value),
+            0x4010..=0x4013 => self.dmc.write(addr, value),
+            0x4015 => {
+                self.pulse1.length.set_enabled(value & 0b1 == 0b1);
+                self.pulse2.length.set_enabled(value & 0b10 == 0b10);
+                self.triangle.length.set_enabled(value & 0b100 == 0b100);
+                self.noise.length.set_enabled(value & 0b1000 == 0b1000);
+                self.dmc.set_enabled(value & 0b10000 == 0b10000);
+                self.dmc.irq = false;
+            }
+            0x4017 => {
+                self.five_step_mode = value & 0x80 == 0x80;
+                self.irq_inhibit = value & 0x40 == 0x40;
+                if self.irq_inhibit {
+                    self.frame_irq = false;
+                }
+                // The reset happens 3 or 4 cycles later depending on the CPU cycle parity
+                self.frame_counter_reset = Some(if self.odd_cycle { 4 } else { 3 });
+            }
+            _ => {}
+        }
+    }
+
+    /// State of the IRQ line, driven by the frame counter and the DMC
+    pub fn irq(&self) -> bool {
+        self.frame_irq || self.dmc.irq
+    }
+
+    /// Address the DMC wants to read its next sample byte from, if its buffer is empty
+    pub fn dmc_read_request(&self) -> Option<u16> {
+        self.dmc.read_request()
+    }
+
+    /// Gives the DMC the byte it asked for with `dmc_read_request`
+    pub fn dmc_fill(&mut self, value: u8) {
+        self.dmc.fill(value);
+    }
+
+    /// Samples produced since the last call to `clear_samples`
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// Nothing else empties the buffer, it grows by `SAMPLE_RATE` samples per emulated second until then
+    pub fn clear_samples(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Sets the level of the cartridge audio, mixed with the APU channels on the next cycles
+    pub fn set_expansion_output(&mut self, output: f32) {
+        self.expansion = output;
+    }
+
+    /// Advances the APU by one CPU cycle
+    pub fn tick(&mut self) {
+        self.step_frame_counter();
+
+        self.triangle.clock_timer();
+        self.noise.clock_timer();
+        self.dmc.clock_timer();
+        // Pulse timers run at half the CPU clock
+        if self.odd_cycle {
+            self.pulse1.clock_timer();
+            self.pulse2.clock_timer();
+        }
+        self.odd_cycle = !self.odd_cycle;
+
+        self.sample_sum += self.output();
+        self.sample_count += 1;
+        self.sample_clock += SAMPLE_RATE;
+        if self.sample_clock >= CPU_FREQUENCY {
+            // Averaging the cycles of a sample is a cheap low-pass filter
+            self.sample_clock -= CPU_FREQUENCY;
+            self.samples
+                .push(self.sample_sum / self.sample_count as f32);
+            self.sample_sum = 0.0;
+            self.sample_count = 0;
+        }
+    }
+
+    fn step_frame_counter(&mut self) {
+        if let Some(delay) = self.frame_counter_reset {
+            if delay == 0 {
+                self.frame_counter_reset = None;
+                self.cycle = 0;
+                if self.five_step_mode {
+                    self.clock_quarter_frame();
+                    self.clock_half_frame();
+                }
+            } else {
+                self.frame_counter_reset = Some(delay - 1);
+            }
+        }
+
+        self.cycle += 1;
+        if self.five_step_mode {
+            match self.cycle {
+                c if c == FIVE_STEP_SEQUENCE[0] || c == FIVE_STEP_SEQUENCE[2] => {
+                    self.clock_quarter_frame()
+                }
+                c if c == FIVE_STEP_SEQUENCE[1] || c == FIVE_STEP_SEQUENCE[4] => {
+                    self.clock_quarter_frame();
+                    self.clock_half_frame();
+                }
+                c if c > FIVE_STEP_SEQUENCE[4] => self.cycle = 0,
+                _ => {}
+            }
+        } else {
+            let last = FOUR_STEP_SEQUENCE[3];
+            match self.cycle {
+                c if c == FOUR_STEP_SEQUENCE[0] || c == FOUR_STEP_SEQUENCE[2] => {
+                    self.clock_quarter_frame()
+                }
+                c if c == FOUR_STEP_SEQUENCE[1] => {
+                    self.clock_quarter_frame();
+                    self.clock_half_frame();
+                }
+                // The interrupt flag is raised for three cycles around the last step
+                c if c == last - 1 => self.raise_frame_irq(),
+                c if c == last => {
+                    self.raise_frame_irq();
+                    self.clock_quarter_frame();
+                    self.clock_half_frame();
+                }
+                c if c == last + 1 => {
+                    self.raise_frame_irq();
+                    self.cycle = 0;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn raise_frame_irq(&mut self) {
+        if !self.irq_inhibit {
+            self.frame_irq = true;
+        }
+    }
+
+    /// Envelopes and the triangle linear counter
+    fn clock_quarter_frame(&mut self) {
+        self.pulse1.envelope.clock();
+        self.pulse2.envelope.clock();
+        self.noise.envelope.clock();
+        self.triangle.clock_linear_counter();
+    }
+
+    /// Length counters and sweep units
+    fn clock_half_frame(&mut self) {
+        self.pulse1.length.clock();
+        self.pulse2.length.clock();
+        self.triangle.length.clock();
+        self.noise.length.clock();
+        self.pulse1.clock_sweep();
+        self.pulse2.clock_sweep();
+    }
+
+    /// Mixes the channels with the non-linear DAC of the NES, between 0.0 and 1.0
+    fn output(&self) -> f32 {
+        let pulse = (self.pulse1.output() + self.pulse2.output()) as f32;
+        let pulse_out = if pulse == 0.0 {
+            0.0
+        } else {
+            95.88 / (8128.0 / pulse + 100.0)
+        };
+
+        let tnd = self.triangle.output() as f32 / 8227.0
+            + self.noise.output() as f32 / 12241.0
+            + self.dmc.output() as f32 / 22638.0;
+        let tnd_out = if tnd == 0.0 {
+            0.0
+        } else {
+            159.79 / (1.0 / tnd + 100.0)
+        };
+
+        pulse_out + tnd_out + self.expansion
+    }
+}
+
+#[test]
+fn frame_irq_test() {
+    let mut apu = Apu::new();
+
+    for _ in 0..FOUR_STEP_SEQUENCE[3] - 2 {
+        apu.tick();
+    }
+    assert!(!apu.irq());
+    apu.tick();
+    assert!(apu.irq());
+
+    // Reading $4015 acknowledges the interrupt
+    assert_eq!(apu.read_status() & 0x40, 0x40);
+    assert_eq!(apu.read_status() & 0x40, 0x00);
+
+    // No interrupt in 5-step mode
+    apu.write_register(0x4017, 0x80);
+    for _ in 0..FIVE_STEP_SEQUENCE[4] * 2 {
+        apu.tick();
+        assert!(!apu.irq());
+    }
+}
+
+#[test]
+fn length_counter_test() {
+    let mut apu = Apu::new();
+
+    // Writing the length of a disabled channel does nothing
+    apu.write_register(0x4003, 0x08);
+    assert_eq!(apu.read_status() & 0b1, 0);
+
+    // Length index 1 is 254 half frames
+    apu.write_register(0x4015, 0b1);
+    apu.write_register(0x4003, 0x08);
+    assert_eq!(apu.read_status() & 0b1, 1);
+    for _ in 0..254 {
+        apu.clock_half_frame();
+    }
+    assert_eq!(apu.read_status() & 0b1, 0);
+
+    // Disabling a channel clears its length counter
+    apu.write_register(0x400F, 0x08);
+    apu.write_register(0x4015, 0b1000);
+    apu.write_register(0x400F, 0x08);
+    assert_eq!(apu.read_status() & 0b1000, 0b1000);
+    apu.write_register(0x4015, 0);
+    assert_eq!(apu.read_status() & 0b1000, 0);
+}
+
+#[test]
+fn sample_rate_test() {
+    let mut apu = Apu::new();
+
+    for _ in 0..CPU_FREQUENCY {
+        apu.tick();
+    }
+    assert_eq!(apu.samples().len(), SAMPLE_RATE as usize);
+}
@@ -0,0 +1,88 @@
+/*!  Run blargg's test ROMs headless and read their result
+
+The ROMs report through the cartridge RAM:
+
+Status    $6000    $80 while running, $81 to ask for the reset button, $00-$7F result code once done
+Signature    $6001 - $6003    $DE $B0 $61 once the values at $6000 can be trusted
+Text    $6004    Zero terminated, what the ROM prints on screen
+
+*/
+
+use crate::{apu::CPU_FREQUENCY, bus::Bus, error::EmulatorError, nes_rom::RomFile, nessy::Nessy};
+
+const STATUS_ADDRESS: u16 = 0x6000;
+const SIGNATURE_ADDRESS: u16 = 0x6001;
+const TEXT_ADDRESS: u16 = 0x6004;
+const SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+
+const STATUS_RUNNING: u8 = 0x80;
+const STATUS_RESET_REQUESTED: u8 = 0x81;
+
+/// The ROMs want the reset button pressed at least 100ms after asking for it
+const RESET_DELAY: usize = CPU_FREQUENCY as usize / 10;
+/// The status is checked about once per frame
+const CHECK_INTERVAL: usize = 30_000;
+
+/// How a test ROM ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Passed,
+    /// Result code reported by the ROM, the text explains it
+    Failed(u8),
+    /// No result after the cycle budget was spent
+    TimedOut,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestResult {
+    pub status: Status,
+    /// Text printed by the ROM
+    pub text: String,
+}
+
+/// Runs a test ROM until it reports a result or after `max_cycles` CPU cycles
+pub fn run(rom: &[u8], max_cycles: usize) -> Result<TestResult, EmulatorError> {
+    let nesfile = RomFile::new(rom)?;
+    let mut nessy = Nessy::new();
+    nessy.load(&nesfile)?;
+
+    let mut reset_at = None;
+    while nessy.cycle < max_cycles {
+        nessy.run_cycles(CHECK_INTERVAL)?;
+
+        if !has_signature(&nessy) {
+            continue;
+        }
+
+        match nessy.bus.peek(STATUS_ADDRESS) {
+            STATUS_RUNNING => {}
+            STATUS_RESET_REQUESTED => match reset_at {
+                None => reset_at = Some(nessy.cycle + RESET_DELAY),
+                Some(cycle) if nessy.cycle >= cycle => {
+                    reset_at = None;
+                    nessy.reset();
+                }
+                Some(_) => {}
+            },
+            0 => return Ok(result(&nessy, Status::Passed)),
+            code if code < STATUS_RUNNING => return Ok(result(&nessy, Status::Failed(code))),
+            _ => {}
+        }
+    }
+
+    Ok(result(&nessy, Status::TimedOut))
+}
+
+fn has_signature(nessy: &Nessy) -> bool {
+    (0..SIGNATURE.len()).all(|i| nessy.bus.peek(SIGNATURE_ADDRESS + i as u16) == SIGNATURE[i])
+}
+
+fn result(nessy: &Nessy, status: Status) -> TestResult {
+    let text = (TEXT_ADDRESS..0x8000)
+        .map(|addr| nessy.bus.peek(addr))
+        .take_while(|&c| c != 0)
+        .map(|c| c as char)
+        .collect();
+
+    TestResult { status, text }
+}
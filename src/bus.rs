@@ -0,0 +1,210 @@
+/*!  CPU address space of the NES */
+
+use crate::{apu::Apu, cartridge::Cartridge, input::Controller, ppu::Ppu};
+
+/**
+Something the CPU can read from and write to
+
+`read` and `write` are real bus accesses and trigger the side effects tied to the address
+(clearing the vblank flag when reading $2002, shifting the controllers when reading $4016, ...).
+`peek` returns what a read would return without any side effect, for debuggers and trace logs.
+`nmi` and `irq` report the state of the interrupt lines driven by the devices on the bus.
+`take_nmi` acknowledges an NMI latched by the CPU, the interrupt sequences check it right before
+fetching their vector so an NMI can hijack them.
+`tick` lets the devices on the bus run for one CPU cycle.
+*/
+pub trait Bus {
+    fn read(&mut self, addr: u16) -> u8;
+    fn write(&mut self, addr: u16, value: u8);
+    fn peek(&self, addr: u16) -> u8;
+
+    fn tick(&mut self) {}
+
+    fn nmi(&self) -> bool {
+        false
+    }
+
+    fn irq(&self) -> bool {
+        false
+    }
+
+    fn take_nmi(&mut self) -> bool {
+        false
+    }
+}
+
+/**
+The NES CPU memory map
+
+Internal RAM (2KB, mirrored every $0800)    $0000 - $1FFF
+PPU registers (mirrored every 8 bytes)    $2000 - $3FFF
+APU and I/O registers    $4000 - $401F
+Cartridge space (PRG RAM, PRG ROM, mapper registers)    $4020 - $FFFF
+
+*/
+pub struct NesBus {
+    pub ram: Vec<u8>,
+    pub ppu: Ppu,
+    pub controllers: [Controller; 2],
+    pub apu: Apu,
+    pub cartridge: Cartridge,
+    open_bus: u8,        // Last value seen on the data bus
+    oam_dma: Option<u8>, // Page written to $4014, copied once the CPU is halted
+}
+
+impl Default for NesBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NesBus {
+    pub fn new() -> Self {
+        let mut ram = Vec::new();
+        ram.resize_with(0x800, || 0);
+
+        Self {
+            ram,
+            ppu: Ppu::new(),
+            controllers: [Controller::new(), Controller::new()],
+            apu: Apu::new(),
+            cartridge: Cartridge::default(),
+            open_bus: 0,
+            oam_dma: None,
+        }
+    }
+
+    /// Returns the page to copy to OAM if a DMA was requested since the last call
+    pub fn take_oam_dma(&mut self) -> Option<u8> {
+        self.oam_dma.take()
+    }
+
+    fn read_io(&mut self, addr: u16) -> u8 {
+        match addr {
+            // Only the low bits are driven, the rest is open bus
+            0x4016 => (self.open_bus & 0b11100000) | self.controllers[0].read(),
+            0x4017 => (self.open_bus & 0b11100000) | self.controllers[1].read(),
+            // APU status, bit 5 is open bus
+            0x4015 => (self.open_bus & 0b00100000) | self.apu.read_status(),
+            // Every other APU register is write-only
+            _ => self.open_bus,
+        }
+    }
+
+    fn write_io(&mut self, addr: u16, value: u8) {
+        match addr {
+            // OAM DMA, the copy halts the CPU so it is run by the CPU itself
+            0x4014 => self.oam_dma = Some(value),
+            0x4016 => {
+                self.controllers[0].write(value);
+                self.controllers[1].write(value);
+            }
+            0x4000..=0x4017 => self.apu.write_register(addr, value),
+            _ => {}
+        }
+    }
+}
+
+impl Bus for NesBus {
+    fn read(&mut self, addr: u16) -> u8 {
+        let value = match addr {
+            0x0000..=0x1FFF => self.ram[(addr % 0x0800) as usize],
+            0x2000..=0x3FFF => self
+                .ppu
+                .read_register(&mut self.cartridge, 0x2000 + addr % 8),
+            0x4000..=0x401F => self.read_io(addr),
+            _ => self.cartridge.cpu_read(addr).unwrap_or(self.open_bus),
+        };
+
+        self.open_bus = value;
+        value
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        self.open_bus = value;
+
+        match addr {
+            0x0000..=0x1FFF => self.ram[(addr % 0x0800) as usize] = value,
+            0x2000..=0x3FFF => {
+                self.ppu
+                    .write_register(&mut self.cartridge, 0x2000 + addr % 8, value);
+                self.cartridge.ppu_register_write(0x2000 + addr % 8, value);
+            }
+            0x4000..=0x401F => self.write_io(addr, value),
+            _ => self.cartridge.cpu_write(addr, value),
+        }
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => self.ram[(addr % 0x0800) as usize],
+            0x2000..=0x3FFF => self.ppu.peek_register(0x2000 + addr % 8),
+            // NOTE: APU and I/O registers are write-only or shift on read, reported as $FF like Nintendulator does
+            0x4000..=0x401F => 0xFF,
+            _ => self.cartridge.cpu_peek(addr).unwrap_or(self.open_bus),
+        }
+    }
+
+    fn tick(&mut self) {
+        // The PPU runs three dots per CPU cycle
+        for _ in 0..3 {
+            self.ppu.tick(&mut self.cartridge);
+        }
+        self.cartridge.tick();
+        self.apu.set_expansion_output(self.cartridge.audio());
+        self.apu.tick();
+
+        // NOTE: The DMC reads its samples without stalling the CPU
+        if let Some(addr) = self.apu.dmc_read_request() {
+            let value = self.peek(addr);
+            self.apu.dmc_fill(value);
+        }
+    }
+
+    fn nmi(&self) -> bool {
+        self.ppu.nmi_output()
+    }
+
+    fn irq(&self) -> bool {
+        self.apu.irq() || self.cartridge.irq()
+    }
+}
+
+#[test]
+fn ram_mirroring_test() {
+    let mut bus = NesBus::new();
+
+    bus.write(0x0042, 0x12);
+    assert_eq!(bus.read(0x0842), 0x12);
+    assert_eq!(bus.read(0x1042), 0x12);
+    assert_eq!(bus.read(0x1842), 0x12);
+
+    bus.write(0x1FFF, 0x34);
+    assert_eq!(bus.peek(0x07FF), 0x34);
+}
+
+#[test]
+fn ppu_register_mirroring_test() {
+    let mut bus = NesBus::new();
+
+    // $3456 mirrors $2006 (PPUADDR), $200F mirrors $2007 (PPUDATA)
+    bus.write(0x3456, 0x20);
+    bus.write(0x2006, 0x00);
+    bus.write(0x200F, 0x42);
+    assert_eq!(bus.ppu.memory.nametables[0x000], 0x42);
+
+    bus.ppu.registers.status = crate::ppu::Status::new_from(0x80);
+    assert_eq!(bus.read(0x3FFA) & 0x80, 0x80);
+    assert_eq!(bus.read(0x2002) & 0x80, 0x00);
+}
+
+#[test]
+fn controller_port_test() {
+    let mut bus = NesBus::new();
+    bus.controllers[0].set_button(crate::input::Button::A, true);
+
+    bus.write(0x4016, 1);
+    bus.write(0x4016, 0);
+    assert_eq!(bus.read(0x4016) & 0x1, 1);
+    assert_eq!(bus.read(0x4016) & 0x1, 0);
+}
@@ -0,0 +1,87 @@
+/*!  AxROM (mapper 7), ANROM, AMROM and AOROM boards
+
+Bank select    $8000 - $FFFF    ...N.PPP: nametable, 32KB PRG ROM bank
+
+PRG ROM, switchable 32KB    $8000 - $FFFF
+CHR RAM    $0000 - $1FFF
+
+The nametable bit selects which of the two nametables fills the whole PPU nametable space.
+AMROM and some ANROM boards have bus conflicts, the register gets the value written ANDed
+with the ROM byte at that address.
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+pub struct Axrom {
+    bus_conflicts: bool,
+    bank: u8,
+}
+
+impl Axrom {
+    pub fn new(bus_conflicts: bool) -> Self {
+        Self {
+            bus_conflicts,
+            bank: 0,
+        }
+    }
+}
+
+impl Mapper for Axrom {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x8000..=0xFFFF => Some(memory.read_prg_rom(0x8000, (self.bank & 0x07) as usize, addr)),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        if addr >= 0x8000 {
+            self.bank = if self.bus_conflicts {
+                value & memory.read_prg_rom(0x8000, (self.bank & 0x07) as usize, addr)
+            } else {
+                value
+            };
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x2000, 0, addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x2000, 0, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        if self.bank & 0x10 == 0 {
+            Mirroring::SingleScreenA
+        } else {
+            Mirroring::SingleScreenB
+        }
+    }
+}
+
+#[test]
+fn axrom_test() {
+    let mut memory = Memory {
+        chr_is_ram: true,
+        ..test_memory(0x40000, 0x2000)
+    };
+    let mut axrom = Axrom::new(false);
+
+    assert_eq!(axrom.cpu_peek(&memory, 0x8000), Some(0));
+    assert_eq!(axrom.mirroring(), Mirroring::SingleScreenA);
+
+    axrom.cpu_write(&mut memory, 0x8000, 0x15);
+    assert_eq!(axrom.cpu_peek(&memory, 0x8000), Some(20));
+    assert_eq!(axrom.cpu_peek(&memory, 0xFFFF), Some(23));
+    assert_eq!(axrom.mirroring(), Mirroring::SingleScreenB);
+
+    let mut axrom = Axrom::new(true);
+    // Bank 0 holds 0 everywhere, nothing goes through
+    axrom.cpu_write(&mut memory, 0x8000, 0x15);
+    assert_eq!(axrom.cpu_peek(&memory, 0x8000), Some(0));
+    assert_eq!(axrom.mirroring(), Mirroring::SingleScreenA);
+}
@@ -0,0 +1,154 @@
+/*!  Mapper 34, two unrelated boards sharing the number
+
+BNROM:
+
+Bank select    $8000 - $FFFF    32KB PRG ROM bank
+
+PRG ROM, switchable 32KB    $8000 - $FFFF
+CHR RAM    $0000 - $1FFF
+
+NINA-001, the registers are also written to PRG RAM:
+
+PRG bank    $7FFD    32KB PRG ROM bank
+CHR bank 0    $7FFE    4KB CHR ROM bank at $0000
+CHR bank 1    $7FFF    4KB CHR ROM bank at $1000
+
+PRG RAM    $6000 - $7FFF
+PRG ROM, switchable 32KB    $8000 - $FFFF
+CHR ROM, two switchable 4KB    $0000 - $1FFF
+
+BNROM has bus conflicts, the register gets the value written ANDed with the ROM byte at that address.
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+pub struct Bnrom {
+    mirroring: Mirroring,
+    bank: u8,
+}
+
+impl Bnrom {
+    pub fn new(mirroring: Mirroring) -> Self {
+        Self { mirroring, bank: 0 }
+    }
+}
+
+impl Mapper for Bnrom {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x8000..=0xFFFF => Some(memory.read_prg_rom(0x8000, self.bank as usize, addr)),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        if addr >= 0x8000 {
+            self.bank = value & memory.read_prg_rom(0x8000, self.bank as usize, addr);
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x2000, 0, addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x2000, 0, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+pub struct Nina001 {
+    mirroring: Mirroring,
+    prg_bank: u8,
+    chr_banks: [u8; 2],
+}
+
+impl Nina001 {
+    pub fn new(mirroring: Mirroring) -> Self {
+        Self {
+            mirroring,
+            prg_bank: 0,
+            chr_banks: [0; 2],
+        }
+    }
+
+    fn chr_bank(&self, addr: u16) -> usize {
+        self.chr_banks[(addr >> 12) as usize & 1] as usize
+    }
+}
+
+impl Mapper for Nina001 {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF => memory.read_prg_ram(addr),
+            0x8000..=0xFFFF => Some(memory.read_prg_rom(0x8000, self.prg_bank as usize, addr)),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        match addr {
+            0x7FFD => self.prg_bank = value & 0x01,
+            0x7FFE => self.chr_banks[0] = value & 0x0F,
+            0x7FFF => self.chr_banks[1] = value & 0x0F,
+            _ => {}
+        }
+
+        if let 0x6000..=0x7FFF = addr {
+            memory.write_prg_ram(addr, value);
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x1000, self.chr_bank(addr), addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x1000, self.chr_bank(addr), addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[test]
+fn bnrom_test() {
+    let mut memory = Memory {
+        chr_is_ram: true,
+        ..test_memory(0x20000, 0x2000)
+    };
+    let mut bnrom = Bnrom::new(Mirroring::Vertical);
+
+    // $E000 holds 3, 2 goes through
+    bnrom.cpu_write(&mut memory, 0xE000, 2);
+    assert_eq!(bnrom.cpu_peek(&memory, 0x8000), Some(8));
+
+    // $8000 holds 8 in bank 2, 1 & 8 selects bank 0
+    bnrom.cpu_write(&mut memory, 0x8000, 1);
+    assert_eq!(bnrom.cpu_peek(&memory, 0x8000), Some(0));
+
+    bnrom.ppu_write(&mut memory, 0x0042, 0x42);
+    assert_eq!(bnrom.ppu_read(&memory, 0x0042), 0x42);
+}
+
+#[test]
+fn nina001_test() {
+    let mut memory = test_memory(0x10000, 0x10000);
+    let mut nina001 = Nina001::new(Mirroring::Horizontal);
+
+    nina001.cpu_write(&mut memory, 0x7FFD, 1);
+    nina001.cpu_write(&mut memory, 0x7FFE, 5);
+    nina001.cpu_write(&mut memory, 0x7FFF, 9);
+    assert_eq!(nina001.cpu_peek(&memory, 0x8000), Some(4));
+    assert_eq!(nina001.ppu_read(&memory, 0x0000), 20);
+    assert_eq!(nina001.ppu_read(&memory, 0x1000), 36);
+
+    // The registers are RAM too
+    assert_eq!(nina001.cpu_peek(&memory, 0x7FFE), Some(5));
+}
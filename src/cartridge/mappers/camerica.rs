@@ -0,0 +1,106 @@
+/*!  Camerica/Codemasters (mapper 71), BF9093 and BF9097 chips
+
+Mirroring    $9000 - $9FFF    ...M....: nametable, only on the BF9097 of Fire Hawk
+PRG bank    $C000 - $FFFF    16KB PRG ROM bank at $8000
+
+PRG ROM, switchable 16KB    $8000 - $BFFF
+PRG ROM, last 16KB    $C000 - $FFFF
+CHR RAM    $0000 - $1FFF
+
+NES 2.0 headers mark the Fire Hawk board with submapper 1, which starts with single-screen
+mirroring. Other headers can't tell it apart, the first write to $9000 then switches to
+single-screen mirroring. No other game writes there.
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+pub struct Camerica {
+    mirroring: Mirroring,
+    bank: u8,
+}
+
+impl Camerica {
+    pub fn new(mirroring: Mirroring) -> Self {
+        Self { mirroring, bank: 0 }
+    }
+
+    /// The board of Fire Hawk, with the mirroring register
+    pub fn fire_hawk() -> Self {
+        Self::new(Mirroring::SingleScreenA)
+    }
+
+    fn prg_rom_bank(&self, memory: &Memory, addr: u16) -> usize {
+        if addr < 0xC000 {
+            self.bank as usize
+        } else {
+            memory.prg_rom_banks(0x4000) - 1
+        }
+    }
+}
+
+impl Mapper for Camerica {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x8000..=0xFFFF => {
+                Some(memory.read_prg_rom(0x4000, self.prg_rom_bank(memory, addr), addr))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, _memory: &mut Memory, addr: u16, value: u8) {
+        match addr {
+            0x9000..=0x9FFF => {
+                self.mirroring = if value & 0x10 == 0 {
+                    Mirroring::SingleScreenA
+                } else {
+                    Mirroring::SingleScreenB
+                };
+            }
+            0xC000..=0xFFFF => self.bank = value & 0x0F,
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x2000, 0, addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x2000, 0, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[test]
+fn camerica_test() {
+    let mut memory = Memory {
+        chr_is_ram: true,
+        ..test_memory(0x40000, 0x2000)
+    };
+    let mut camerica = Camerica::new(Mirroring::Vertical);
+
+    assert_eq!(camerica.cpu_peek(&memory, 0xC000), Some(30));
+    camerica.cpu_write(&mut memory, 0xC000, 6);
+    assert_eq!(camerica.cpu_peek(&memory, 0x8000), Some(12));
+    assert_eq!(camerica.cpu_peek(&memory, 0xC000), Some(30));
+
+    // $8000 - $8FFF does nothing
+    camerica.cpu_write(&mut memory, 0x8000, 0x10);
+    assert_eq!(camerica.mirroring(), Mirroring::Vertical);
+
+    // Fire Hawk from an iNES header
+    camerica.cpu_write(&mut memory, 0x9000, 0x10);
+    assert_eq!(camerica.mirroring(), Mirroring::SingleScreenB);
+    camerica.cpu_write(&mut memory, 0x9000, 0x00);
+    assert_eq!(camerica.mirroring(), Mirroring::SingleScreenA);
+
+    // Known from the header, single-screen before any write
+    let camerica = Camerica::fire_hawk();
+    assert_eq!(camerica.mirroring(), Mirroring::SingleScreenA);
+}
@@ -0,0 +1,145 @@
+/*!  CNROM (mapper 3), and its copy protected variant (mapper 185)
+
+Bank select    $8000 - $FFFF    8KB CHR ROM bank
+
+PRG ROM    $8000 - $FFFF (16KB mirrored, or 32KB)
+CHR ROM, switchable 8KB    $0000 - $1FFF
+
+With bus conflicts the register gets the value written ANDed with the ROM byte at that address.
+The mapper 185 boards only have 8KB of CHR ROM, diodes on the register lines enable it for a
+single value and the game checks that the pattern tables read as garbage otherwise.
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+/// Which values of the register enable CHR ROM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChrProtection {
+    /// Plain CNROM, the register selects the CHR bank
+    None,
+    /// Mapper 185 without a known submapper, enabled by most values games use
+    Heuristic,
+    /// Mapper 185 submappers 4-7, enabled by a single value of the low 2 bits
+    EnabledBy(u8),
+}
+
+pub struct Cnrom {
+    mirroring: Mirroring,
+    bus_conflicts: bool,
+    protection: ChrProtection,
+    bank: u8,
+}
+
+impl Cnrom {
+    pub fn new(mirroring: Mirroring, bus_conflicts: bool, protection: ChrProtection) -> Self {
+        Self {
+            mirroring,
+            bus_conflicts,
+            protection,
+            bank: 0,
+        }
+    }
+
+    fn is_chr_enabled(&self) -> bool {
+        match self.protection {
+            ChrProtection::None => true,
+            ChrProtection::Heuristic => self.bank & 0x03 != 0 && self.bank != 0x13,
+            ChrProtection::EnabledBy(value) => self.bank & 0x03 == value,
+        }
+    }
+
+    fn chr_bank(&self) -> usize {
+        match self.protection {
+            ChrProtection::None => self.bank as usize,
+            _ => 0,
+        }
+    }
+}
+
+impl Mapper for Cnrom {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x8000..=0xFFFF => Some(memory.read_prg_rom(0x8000, 0, addr)),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        if addr >= 0x8000 {
+            self.bank = if self.bus_conflicts {
+                value & memory.read_prg_rom(0x8000, 0, addr)
+            } else {
+                value
+            };
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        if self.is_chr_enabled() {
+            memory.read_chr(0x2000, self.chr_bank(), addr)
+        } else {
+            0xFF
+        }
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x2000, self.chr_bank(), addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[test]
+fn cnrom_test() {
+    let mut memory = test_memory(0x4000, 0x8000);
+    let mut cnrom = Cnrom::new(Mirroring::Horizontal, false, ChrProtection::None);
+
+    assert_eq!(cnrom.ppu_read(&memory, 0x1000), 4);
+    cnrom.cpu_write(&mut memory, 0x8000, 2);
+    assert_eq!(cnrom.ppu_read(&memory, 0x0000), 16);
+    assert_eq!(cnrom.ppu_read(&memory, 0x1FFF), 23);
+    // Only 2 bits are wired on a 32KB board
+    cnrom.cpu_write(&mut memory, 0x8000, 7);
+    assert_eq!(cnrom.ppu_read(&memory, 0x0000), 24);
+
+    // CHR ROM can't be written
+    cnrom.ppu_write(&mut memory, 0x0000, 0x42);
+    assert_eq!(cnrom.ppu_read(&memory, 0x0000), 24);
+}
+
+#[test]
+fn cnrom_bus_conflicts_test() {
+    let mut memory = test_memory(0x8000, 0x8000);
+    let mut cnrom = Cnrom::new(Mirroring::Horizontal, true, ChrProtection::None);
+
+    // The ROM holds $01 at $A000
+    cnrom.cpu_write(&mut memory, 0xA000, 3);
+    assert_eq!(cnrom.ppu_read(&memory, 0x0000), 8);
+}
+
+#[test]
+fn cnrom_chr_protection_test() {
+    // PRG ROM full of $FF lets every write through the bus conflicts
+    let mut memory = Memory {
+        prg_rom: vec![0xFF; 0x8000],
+        ..test_memory(0x8000, 0x2000)
+    };
+    let mut cnrom = Cnrom::new(Mirroring::Horizontal, true, ChrProtection::EnabledBy(1));
+
+    assert_eq!(cnrom.ppu_read(&memory, 0x0000), 0xFF);
+    cnrom.cpu_write(&mut memory, 0x8000, 0x21);
+    assert_eq!(cnrom.ppu_read(&memory, 0x0000), 0);
+    cnrom.cpu_write(&mut memory, 0x8000, 0x02);
+    assert_eq!(cnrom.ppu_read(&memory, 0x0000), 0xFF);
+
+    let mut cnrom = Cnrom::new(Mirroring::Horizontal, true, ChrProtection::Heuristic);
+    assert_eq!(cnrom.ppu_read(&memory, 0x0000), 0xFF);
+    cnrom.cpu_write(&mut memory, 0x8000, 0x03);
+    assert_eq!(cnrom.ppu_read(&memory, 0x0000), 0);
+    cnrom.cpu_write(&mut memory, 0x8000, 0x13);
+    assert_eq!(cnrom.ppu_read(&memory, 0x0000), 0xFF);
+}
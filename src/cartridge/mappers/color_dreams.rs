@@ -0,0 +1,82 @@
+/*!  Color Dreams (mapper 11)
+
+Bank select    $8000 - $FFFF    CCCC..PP: 8KB CHR ROM bank, 32KB PRG ROM bank
+
+PRG ROM, switchable 32KB    $8000 - $FFFF
+CHR ROM, switchable 8KB    $0000 - $1FFF
+
+With bus conflicts the register gets the value written ANDed with the ROM byte at that address.
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+pub struct ColorDreams {
+    mirroring: Mirroring,
+    bus_conflicts: bool,
+    bank: u8,
+}
+
+impl ColorDreams {
+    pub fn new(mirroring: Mirroring, bus_conflicts: bool) -> Self {
+        Self {
+            mirroring,
+            bus_conflicts,
+            bank: 0,
+        }
+    }
+
+    fn prg_rom_bank(&self) -> usize {
+        (self.bank & 0x03) as usize
+    }
+}
+
+impl Mapper for ColorDreams {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x8000..=0xFFFF => Some(memory.read_prg_rom(0x8000, self.prg_rom_bank(), addr)),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        if addr >= 0x8000 {
+            self.bank = if self.bus_conflicts {
+                value & memory.read_prg_rom(0x8000, self.prg_rom_bank(), addr)
+            } else {
+                value
+            };
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x2000, (self.bank >> 4) as usize, addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x2000, (self.bank >> 4) as usize, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[test]
+fn color_dreams_test() {
+    let mut memory = test_memory(0x20000, 0x20000);
+    let mut color_dreams = ColorDreams::new(Mirroring::Vertical, false);
+
+    color_dreams.cpu_write(&mut memory, 0x8000, 0xA2);
+    assert_eq!(color_dreams.cpu_peek(&memory, 0x8000), Some(8));
+    assert_eq!(color_dreams.cpu_peek(&memory, 0xFFFF), Some(11));
+    assert_eq!(color_dreams.ppu_read(&memory, 0x0000), 80);
+    assert_eq!(color_dreams.ppu_read(&memory, 0x1FFF), 87);
+
+    let mut color_dreams = ColorDreams::new(Mirroring::Vertical, true);
+    // $8000 holds 0, nothing goes through
+    color_dreams.cpu_write(&mut memory, 0x8000, 0xA2);
+    assert_eq!(color_dreams.cpu_peek(&memory, 0x8000), Some(0));
+    assert_eq!(color_dreams.ppu_read(&memory, 0x0000), 0);
+}
@@ -0,0 +1,456 @@
+/*!  Sunsoft FME-7 and 5B (mapper 69)
+
+Command    $8000 - $9FFF    ....CCCC: register written by the next parameter
+Parameter    $A000 - $BFFF    Value of the selected register
+Audio register    $C000 - $DFFF    ....RRRR: 5B audio register written next, ignored with the high bits set
+Audio data    $E000 - $FFFF    Value of the selected audio register
+
+Registers:
+CHR banks    $0 - $7    1KB banks
+PRG bank 0    $8    ERBBBBBB: RAM enable, RAM or ROM, 8KB bank at $6000
+PRG banks 1-3    $9 - $B    ..BBBBBB: 8KB banks at $8000, $A000 and $C000
+Mirroring    $C    ......MM: vertical, horizontal, single screen A, single screen B
+IRQ control    $D    C......I: counter enable, IRQ enable. Also acknowledges the IRQ
+IRQ counter    $E - $F    Low and high bytes, decremented every CPU cycle
+
+PRG ROM    $8000 - $FFFF, $E000 is the last 8KB bank
+CHR ROM    $0000 - $1FFF
+
+The 5B is an FME-7 with a YM2149 sound chip, there is no way to tell them apart from the
+header. Writes to the audio registers of an FME-7 don't do anything on the real boards.
+
+NOTE: The PRG RAM isn't banked, no game has more than 8KB.
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+/// Output of a channel at full volume, about an APU pulse at full volume
+const CHANNEL_LEVEL: f32 = 0.15;
+/// The sound chip counts its periods in units of 16 CPU cycles
+const AUDIO_PRESCALER: u8 = 16;
+
+/**
+The YM2149 of the 5B, three square waves mixed with noise, and an envelope
+
+Tone periods    $0 - $5    Low byte then high nibble for channels A, B and C
+Noise period    $6    ...PPPPP
+Mixer    $7    ..CBAcba: noise disable, tone disable, for each channel
+Volumes    $8 - $A    ...EVVVV: envelope, volume
+Envelope period    $B - $C    Low and high bytes
+Envelope shape    $D    ....CAAH: continue, attack, alternate, hold. Restarts the envelope
+*/
+struct Sunsoft5b {
+    registers: [u8; 16],
+    prescaler: u8,
+    tone_timers: [u16; 3],
+    tone_outputs: [bool; 3],
+    noise_timer: u16,
+    noise: u32, // 17 bits LFSR
+    envelope_timer: u16,
+    envelope_step: u8,
+    envelope_attack: bool,
+    envelope_holding: bool,
+}
+
+impl Sunsoft5b {
+    fn new() -> Self {
+        Self {
+            registers: [0; 16],
+            prescaler: 0,
+            tone_timers: [0; 3],
+            tone_outputs: [false; 3],
+            noise_timer: 0,
+            noise: 1,
+            envelope_timer: 0,
+            envelope_step: 0,
+            envelope_attack: false,
+            envelope_holding: false,
+        }
+    }
+
+    fn write(&mut self, register: u8, value: u8) {
+        self.registers[register as usize] = value;
+        if register == 0x0D {
+            self.envelope_step = 0;
+            self.envelope_timer = 0;
+            self.envelope_attack = value & 0x04 != 0;
+            self.envelope_holding = false;
+        }
+    }
+
+    fn tone_period(&self, channel: usize) -> u16 {
+        let period = self.registers[channel * 2] as u16
+            | (self.registers[channel * 2 + 1] as u16 & 0x0F) << 8;
+        period.max(1)
+    }
+
+    /// Called on every CPU cycle
+    fn tick(&mut self) {
+        self.prescaler += 1;
+        if self.prescaler < AUDIO_PRESCALER {
+            return;
+        }
+        self.prescaler = 0;
+
+        for channel in 0..3 {
+            self.tone_timers[channel] += 1;
+            if self.tone_timers[channel] >= self.tone_period(channel) {
+                self.tone_timers[channel] = 0;
+                self.tone_outputs[channel] = !self.tone_outputs[channel];
+            }
+        }
+
+        // The noise is clocked at half the rate of the tones
+        self.noise_timer += 1;
+        if self.noise_timer >= (self.registers[6] as u16 & 0x1F).max(1) * 2 {
+            self.noise_timer = 0;
+            let feedback = (self.noise ^ (self.noise >> 3)) & 1;
+            self.noise = (self.noise >> 1) | feedback << 16;
+        }
+
+        self.envelope_timer += 1;
+        let envelope_period =
+            (self.registers[0x0B] as u16 | (self.registers[0x0C] as u16) << 8).max(1);
+        if self.envelope_timer >= envelope_period {
+            self.envelope_timer = 0;
+            self.clock_envelope();
+        }
+    }
+
+    fn clock_envelope(&mut self) {
+        if self.envelope_holding {
+            return;
+        }
+        if self.envelope_step < 31 {
+            self.envelope_step += 1;
+            return;
+        }
+
+        let shape = self.registers[0x0D];
+        let (continues, alternate, hold) =
+            (shape & 0x08 != 0, shape & 0x02 != 0, shape & 0x01 != 0);
+        if !continues {
+            // Shapes $0-$7 fall to zero and stay there
+            self.envelope_holding = true;
+            self.envelope_attack = false;
+        } else if hold {
+            self.envelope_holding = true;
+            self.envelope_attack ^= alternate;
+        } else {
+            self.envelope_attack ^= alternate;
+            self.envelope_step = 0;
+        }
+    }
+
+    /// Volume of the envelope, 0-31
+    fn envelope_level(&self) -> u8 {
+        if self.envelope_attack {
+            self.envelope_step
+        } else {
+            31 - self.envelope_step
+        }
+    }
+
+    fn output(&self) -> f32 {
+        let mixer = self.registers[7];
+        let noise = self.noise & 1 != 0;
+
+        (0..3)
+            .map(|channel| {
+                // Disabled tones and noise leave the channel at its volume
+                let tone = self.tone_outputs[channel] || mixer & (1 << channel) != 0;
+                let noise = noise || mixer & (8 << channel) != 0;
+                if !(tone && noise) {
+                    return 0.0;
+                }
+
+                let volume = self.registers[8 + channel];
+                let level = if volume & 0x10 != 0 {
+                    self.envelope_level()
+                } else if volume & 0x0F == 0 {
+                    0
+                } else {
+                    (volume & 0x0F) * 2 + 1
+                };
+                Sunsoft5b::amplitude(level)
+            })
+            .sum::<f32>()
+    }
+
+    /// Amplitude of a 5 bits volume, the steps are 1.5dB apart
+    fn amplitude(level: u8) -> f32 {
+        if level == 0 {
+            0.0
+        } else {
+            CHANNEL_LEVEL * 10f32.powf((level as f32 - 31.0) * 1.5 / 20.0)
+        }
+    }
+}
+
+pub struct Fme7 {
+    command: u8,
+    chr_banks: [u8; 8],
+    prg_banks: [u8; 4],
+    mirroring: Mirroring,
+    irq_enabled: bool,
+    irq_counter_enabled: bool,
+    irq_counter: u16,
+    irq: bool,
+    audio_register: u8,
+    audio: Sunsoft5b,
+}
+
+impl Default for Fme7 {
+    fn default() -> Self {
+        Self {
+            command: 0,
+            chr_banks: [0; 8],
+            prg_banks: [0; 4],
+            mirroring: Mirroring::Vertical,
+            irq_enabled: false,
+            irq_counter_enabled: false,
+            irq_counter: 0,
+            irq: false,
+            audio_register: 0,
+            audio: Sunsoft5b::new(),
+        }
+    }
+}
+
+impl Fme7 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn write_parameter(&mut self, value: u8) {
+        match self.command {
+            0x0..=0x7 => self.chr_banks[self.command as usize] = value,
+            0x8..=0xB => self.prg_banks[self.command as usize - 8] = value,
+            0xC => {
+                self.mirroring = match value & 0x03 {
+                    0 => Mirroring::Vertical,
+                    1 => Mirroring::Horizontal,
+                    2 => Mirroring::SingleScreenA,
+                    _ => Mirroring::SingleScreenB,
+                };
+            }
+            0xD => {
+                self.irq_enabled = value & 0x01 != 0;
+                self.irq_counter_enabled = value & 0x80 != 0;
+                self.irq = false;
+            }
+            0xE => self.irq_counter = (self.irq_counter & 0xFF00) | value as u16,
+            _ => self.irq_counter = (self.irq_counter & 0x00FF) | (value as u16) << 8,
+        }
+    }
+
+    fn is_prg_ram_selected(&self) -> bool {
+        self.prg_banks[0] & 0x40 != 0
+    }
+
+    fn is_prg_ram_enabled(&self) -> bool {
+        self.is_prg_ram_selected() && self.prg_banks[0] & 0x80 != 0
+    }
+}
+
+impl Mapper for Fme7 {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.is_prg_ram_enabled() => memory.read_prg_ram(addr),
+            // Open bus with the RAM disabled
+            0x6000..=0x7FFF if self.is_prg_ram_selected() => None,
+            0x6000..=0xDFFF => {
+                let bank = self.prg_banks[(addr as usize - 0x6000) / 0x2000] & 0x3F;
+                Some(memory.read_prg_rom(0x2000, bank as usize, addr))
+            }
+            0xE000..=0xFFFF => {
+                let bank = memory.prg_rom_banks(0x2000) - 1;
+                Some(memory.read_prg_rom(0x2000, bank, addr))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF if self.is_prg_ram_enabled() => memory.write_prg_ram(addr, value),
+            0x8000..=0x9FFF => self.command = value & 0x0F,
+            0xA000..=0xBFFF => self.write_parameter(value),
+            0xC000..=0xDFFF => self.audio_register = value,
+            0xE000..=0xFFFF if self.audio_register & 0xF0 == 0 => {
+                self.audio.write(self.audio_register, value)
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x400, self.chr_banks[addr as usize >> 10] as usize, addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(
+            0x400,
+            self.chr_banks[addr as usize >> 10] as usize,
+            addr,
+            value,
+        );
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn irq(&self) -> bool {
+        self.irq
+    }
+
+    fn tick(&mut self) {
+        if self.irq_counter_enabled {
+            self.irq_counter = self.irq_counter.wrapping_sub(1);
+            if self.irq_counter == 0xFFFF && self.irq_enabled {
+                self.irq = true;
+            }
+        }
+
+        self.audio.tick();
+    }
+
+    fn audio(&self) -> f32 {
+        self.audio.output()
+    }
+}
+
+#[cfg(test)]
+fn write_register(fme7: &mut Fme7, memory: &mut Memory, register: u8, value: u8) {
+    fme7.cpu_write(memory, 0x8000, register);
+    fme7.cpu_write(memory, 0xA000, value);
+}
+
+#[test]
+fn fme7_banks_test() {
+    let mut memory = test_memory(0x40000, 0x40000);
+    let mut fme7 = Fme7::new();
+
+    for bank in 0..8 {
+        write_register(&mut fme7, &mut memory, bank, 0x20 + bank);
+    }
+    assert_eq!(fme7.ppu_read(&memory, 0x0000), 0x20);
+    assert_eq!(fme7.ppu_read(&memory, 0x1C00), 0x27);
+
+    write_register(&mut fme7, &mut memory, 0x9, 3);
+    write_register(&mut fme7, &mut memory, 0xA, 4);
+    write_register(&mut fme7, &mut memory, 0xB, 5);
+    assert_eq!(fme7.cpu_peek(&memory, 0x8000), Some(3));
+    assert_eq!(fme7.cpu_peek(&memory, 0xA000), Some(4));
+    assert_eq!(fme7.cpu_peek(&memory, 0xC000), Some(5));
+    assert_eq!(fme7.cpu_peek(&memory, 0xE000), Some(31));
+
+    // ROM, disabled RAM and RAM at $6000
+    write_register(&mut fme7, &mut memory, 0x8, 6);
+    assert_eq!(fme7.cpu_peek(&memory, 0x6000), Some(6));
+    write_register(&mut fme7, &mut memory, 0x8, 0x40);
+    fme7.cpu_write(&mut memory, 0x6000, 0x42);
+    assert_eq!(fme7.cpu_peek(&memory, 0x6000), None);
+    write_register(&mut fme7, &mut memory, 0x8, 0xC0);
+    fme7.cpu_write(&mut memory, 0x6000, 0x42);
+    assert_eq!(fme7.cpu_peek(&memory, 0x6000), Some(0x42));
+
+    write_register(&mut fme7, &mut memory, 0xC, 3);
+    assert_eq!(fme7.mirroring(), Mirroring::SingleScreenB);
+}
+
+#[test]
+fn fme7_irq_test() {
+    let mut memory = test_memory(0x8000, 0x2000);
+    let mut fme7 = Fme7::new();
+
+    // The IRQ is raised when the counter wraps from $0000 to $FFFF
+    write_register(&mut fme7, &mut memory, 0xE, 0x02);
+    write_register(&mut fme7, &mut memory, 0xF, 0x00);
+    write_register(&mut fme7, &mut memory, 0xD, 0x81);
+    fme7.tick();
+    fme7.tick();
+    assert!(!fme7.irq());
+    fme7.tick();
+    assert!(fme7.irq());
+
+    // Writing the control acknowledges it
+    write_register(&mut fme7, &mut memory, 0xD, 0x81);
+    assert!(!fme7.irq());
+
+    // Without the IRQ enabled the counter still runs
+    write_register(&mut fme7, &mut memory, 0xD, 0x80);
+    for _ in 0..0x10000 {
+        fme7.tick();
+    }
+    assert!(!fme7.irq());
+    assert_eq!(fme7.irq_counter, 0xFFFF);
+
+    // Stopped counter
+    write_register(&mut fme7, &mut memory, 0xD, 0x01);
+    fme7.tick();
+    assert_eq!(fme7.irq_counter, 0xFFFF);
+}
+
+#[test]
+fn sunsoft_5b_test() {
+    let mut memory = test_memory(0x8000, 0x2000);
+    let mut fme7 = Fme7::new();
+    let mut write_audio = |fme7: &mut Fme7, register: u8, value: u8| {
+        fme7.cpu_write(&mut memory, 0xC000, register);
+        fme7.cpu_write(&mut memory, 0xE000, value);
+    };
+
+    // Tone A at full volume, with a period of 2: it toggles every 32 CPU cycles
+    write_audio(&mut fme7, 0x0, 0x02);
+    write_audio(&mut fme7, 0x7, 0b111_110);
+    write_audio(&mut fme7, 0x8, 0x0F);
+    let mut outputs = vec![];
+    for _ in 0..4 {
+        for _ in 0..32 {
+            fme7.tick();
+        }
+        outputs.push(fme7.audio());
+    }
+    assert_eq!(outputs[0], Sunsoft5b::amplitude(31));
+    assert_eq!(outputs[1], 0.0);
+    assert_eq!(outputs[2], outputs[0]);
+    assert_eq!(outputs[3], 0.0);
+
+    // Each volume step is 3dB
+    let ratio = Sunsoft5b::amplitude(29) / Sunsoft5b::amplitude(31);
+    assert!((ratio - 10f32.powf(-3.0 / 20.0)).abs() < 1e-6);
+
+    // Ignored register, with high bits set
+    write_audio(&mut fme7, 0x18, 0x00);
+    assert_eq!(fme7.audio.registers[8], 0x0F);
+
+    // Envelope shape $D rises and holds, the envelope steps every 16 CPU cycles with a period of 1
+    write_audio(&mut fme7, 0x7, 0b111_111);
+    write_audio(&mut fme7, 0x8, 0x10);
+    write_audio(&mut fme7, 0xB, 0x01);
+    write_audio(&mut fme7, 0xD, 0x0D);
+    assert_eq!(fme7.audio(), 0.0);
+    for _ in 0..16 {
+        fme7.tick();
+    }
+    assert_eq!(fme7.audio(), Sunsoft5b::amplitude(1));
+    for _ in 0..16 * 40 {
+        fme7.tick();
+    }
+    assert_eq!(fme7.audio(), Sunsoft5b::amplitude(31));
+
+    // Shape $A goes down and up
+    write_audio(&mut fme7, 0xD, 0x0A);
+    for _ in 0..16 * 31 {
+        fme7.tick();
+    }
+    assert_eq!(fme7.audio(), 0.0);
+    for _ in 0..16 * 2 {
+        fme7.tick();
+    }
+    assert_eq!(fme7.audio(), Sunsoft5b::amplitude(1));
+}
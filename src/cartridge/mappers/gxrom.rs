@@ -0,0 +1,79 @@
+/*!  GxROM (mapper 66), GNROM and MHROM boards
+
+Bank select    $8000 - $FFFF    ..PP..CC: 32KB PRG ROM bank, 8KB CHR ROM bank
+
+PRG ROM, switchable 32KB    $8000 - $FFFF
+CHR ROM, switchable 8KB    $0000 - $1FFF
+
+The register gets the value written ANDed with the ROM byte at that address (bus conflicts).
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+pub struct Gxrom {
+    mirroring: Mirroring,
+    bank: u8,
+}
+
+impl Gxrom {
+    pub fn new(mirroring: Mirroring) -> Self {
+        Self { mirroring, bank: 0 }
+    }
+
+    fn prg_rom_bank(&self) -> usize {
+        ((self.bank >> 4) & 0x03) as usize
+    }
+
+    fn chr_bank(&self) -> usize {
+        (self.bank & 0x03) as usize
+    }
+}
+
+impl Mapper for Gxrom {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x8000..=0xFFFF => Some(memory.read_prg_rom(0x8000, self.prg_rom_bank(), addr)),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        if addr >= 0x8000 {
+            self.bank = value & memory.read_prg_rom(0x8000, self.prg_rom_bank(), addr);
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x2000, self.chr_bank(), addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x2000, self.chr_bank(), addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[test]
+fn gxrom_test() {
+    // PRG ROM full of $FF lets every write through the bus conflicts
+    let mut memory = Memory {
+        prg_rom: vec![0xFF; 0x20000],
+        ..test_memory(0x20000, 0x8000)
+    };
+    memory.prg_rom[0x10000] = 0x42;
+    let mut gxrom = Gxrom::new(Mirroring::Vertical);
+
+    gxrom.cpu_write(&mut memory, 0x8000, 0x23);
+    assert_eq!(gxrom.cpu_peek(&memory, 0x8000), Some(0x42));
+    assert_eq!(gxrom.ppu_read(&memory, 0x0000), 24);
+
+    // $8000 in bank 2 holds $42, $21 & $42 keeps nothing
+    gxrom.cpu_write(&mut memory, 0x8000, 0x21);
+    assert_eq!(gxrom.cpu_peek(&memory, 0x8001), Some(0xFF));
+    assert_eq!(gxrom.ppu_read(&memory, 0x0000), 0);
+}
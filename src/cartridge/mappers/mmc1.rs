@@ -0,0 +1,315 @@
+/*!  MMC1 (mapper 1), SxROM boards
+
+Registers are written one bit at a time through a 5-bit shift register, bit 7 of any write
+resets it. The fifth write loads the register selected by address bits 13-14:
+
+Control    $8000 - $9FFF    CPPMM: mirroring, PRG bank mode, CHR bank mode
+CHR bank 0    $A000 - $BFFF    4KB bank at $0000, or 8KB bank at $0000 in 8KB mode
+CHR bank 1    $C000 - $DFFF    4KB bank at $1000, ignored in 8KB mode
+PRG bank    $E000 - $FFFF    RPPPP: PRG RAM disable, 16KB bank
+
+PRG RAM    $6000 - $7FFF
+PRG ROM    $8000 - $FFFF
+
+The boards with big chips wire the unused CHR bank bits to them:
+SNROM uses bit 4 to disable PRG RAM, SOROM bit 3 and SXROM bits 2-3 to select an 8KB PRG RAM
+bank, SUROM/SXROM bit 4 to select a 256KB PRG ROM half.
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring, CHR_RAM_SIZE, PRG_RAM_SIZE};
+
+/// PRG ROM size handled by the PRG bank register, bigger boards use a CHR bank bit for the rest
+const PRG_ROM_HALF_SIZE: usize = 0x40000;
+
+pub struct Mmc1 {
+    shift: u8,
+    writes: u8, // Bits in the shift register
+    control: u8,
+    chr_banks: [u8; 2],
+    prg_bank: u8,
+    chr_bank_high: bool, // Last pattern table read by the PPU, selecting which CHR bank drives the extra lines
+    cycle: u64,
+    last_write: u64,
+}
+
+impl Default for Mmc1 {
+    fn default() -> Self {
+        Self {
+            shift: 0,
+            writes: 0,
+            control: 0x0C, // The last bank is fixed at $C000 on power up
+            chr_banks: [0; 2],
+            prg_bank: 0,
+            chr_bank_high: false,
+            cycle: 0,
+            // Anything but the cycle before the first one
+            last_write: u64::MAX - 1,
+        }
+    }
+}
+
+impl Mmc1 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn write_register(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x8000..=0x9FFF => self.control = value,
+            0xA000..=0xBFFF => self.chr_banks[0] = value,
+            0xC000..=0xDFFF => self.chr_banks[1] = value,
+            _ => self.prg_bank = value,
+        }
+    }
+
+    fn is_chr_4kb_mode(&self) -> bool {
+        self.control & 0x10 != 0
+    }
+
+    /// CHR bank register driving the extra lines of the big boards
+    fn outer_bank_register(&self) -> u8 {
+        if self.is_chr_4kb_mode() && self.chr_bank_high {
+            self.chr_banks[1]
+        } else {
+            self.chr_banks[0]
+        }
+    }
+
+    fn is_prg_ram_enabled(&self, memory: &Memory) -> bool {
+        // SNROM has a single 8KB CHR chip, bit 4 is wired to the PRG RAM chip enable
+        let snrom = memory.chr.len() == CHR_RAM_SIZE && memory.prg_rom.len() <= PRG_ROM_HALF_SIZE;
+        self.prg_bank & 0x10 == 0 && !(snrom && self.outer_bank_register() & 0x10 != 0)
+    }
+
+    /// Offset of `addr` in PRG RAM, SOROM has 16KB and SXROM 32KB
+    fn prg_ram_addr(&self, memory: &Memory, addr: u16) -> u16 {
+        let register = self.outer_bank_register();
+        let bank = match memory.prg_ram.len() / PRG_RAM_SIZE {
+            0 | 1 => 0,
+            2 => (register >> 3) & 0x01,
+            _ => (register >> 2) & 0x03,
+        };
+        (bank as usize * PRG_RAM_SIZE) as u16 | (addr & 0x1FFF)
+    }
+
+    /// 16KB PRG ROM bank at `addr`
+    fn prg_rom_bank(&self, memory: &Memory, addr: u16) -> usize {
+        let bank = (self.prg_bank & 0x0F) as usize;
+        let bank = match (self.control >> 2) & 0x03 {
+            0 | 1 => (bank & !1) | ((addr as usize >> 14) & 1),
+            2 if addr < 0xC000 => 0,
+            2 => bank,
+            _ if addr < 0xC000 => bank,
+            _ => 0x0F,
+        };
+
+        if memory.prg_rom.len() > PRG_ROM_HALF_SIZE {
+            let half = (self.outer_bank_register() & 0x10) as usize;
+            half | bank
+        } else {
+            bank
+        }
+    }
+
+    /// 4KB CHR bank at `addr`
+    fn chr_bank(&self, addr: u16) -> usize {
+        let high = addr >= 0x1000;
+        if self.is_chr_4kb_mode() {
+            self.chr_banks[high as usize] as usize
+        } else {
+            (self.chr_banks[0] & !1) as usize | high as usize
+        }
+    }
+}
+
+impl Mapper for Mmc1 {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.is_prg_ram_enabled(memory) => {
+                memory.read_prg_ram(self.prg_ram_addr(memory, addr))
+            }
+            0x8000..=0xFFFF => {
+                Some(memory.read_prg_rom(0x4000, self.prg_rom_bank(memory, addr), addr))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF if self.is_prg_ram_enabled(memory) => {
+                memory.write_prg_ram(self.prg_ram_addr(memory, addr), value);
+            }
+            0x8000..=0xFFFF => {
+                // Only the first write of read-modify-write instructions is seen
+                let consecutive = self.cycle == self.last_write.wrapping_add(1);
+                self.last_write = self.cycle;
+                if consecutive {
+                    return;
+                }
+
+                if value & 0x80 != 0 {
+                    self.shift = 0;
+                    self.writes = 0;
+                    self.control |= 0x0C;
+                    return;
+                }
+
+                self.shift |= (value & 1) << self.writes;
+                self.writes += 1;
+                if self.writes == 5 {
+                    self.write_register(addr, self.shift);
+                    self.shift = 0;
+                    self.writes = 0;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        self.chr_bank_high = addr >= 0x1000;
+        memory.read_chr(0x1000, self.chr_bank(addr), addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x1000, self.chr_bank(addr), addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0x03 {
+            0 => Mirroring::SingleScreenA,
+            1 => Mirroring::SingleScreenB,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        }
+    }
+
+    fn tick(&mut self) {
+        self.cycle += 1;
+    }
+}
+
+#[cfg(test)]
+fn write_serial(mmc1: &mut Mmc1, memory: &mut Memory, addr: u16, value: u8) {
+    for bit in 0..5 {
+        mmc1.cpu_write(memory, addr, value >> bit);
+        mmc1.tick();
+        mmc1.tick();
+    }
+}
+
+#[test]
+fn mmc1_test() {
+    let mut memory = test_memory(0x40000, 0x20000);
+    let mut mmc1 = Mmc1::new();
+
+    // Power up: last bank fixed at $C000
+    assert_eq!(mmc1.cpu_peek(&memory, 0x8000), Some(0));
+    assert_eq!(mmc1.cpu_peek(&memory, 0xC000), Some(30));
+
+    write_serial(&mut mmc1, &mut memory, 0xE000, 0x05);
+    assert_eq!(mmc1.cpu_peek(&memory, 0x8000), Some(10));
+    assert_eq!(mmc1.cpu_peek(&memory, 0xC000), Some(30));
+
+    // 32KB mode ignores the low bit
+    write_serial(&mut mmc1, &mut memory, 0x8000, 0x02);
+    assert_eq!(mmc1.mirroring(), Mirroring::Vertical);
+    assert_eq!(mmc1.cpu_peek(&memory, 0x8000), Some(8));
+    assert_eq!(mmc1.cpu_peek(&memory, 0xC000), Some(10));
+
+    // First bank fixed at $8000
+    write_serial(&mut mmc1, &mut memory, 0x8000, 0x08);
+    assert_eq!(mmc1.mirroring(), Mirroring::SingleScreenA);
+    assert_eq!(mmc1.cpu_peek(&memory, 0x8000), Some(0));
+    assert_eq!(mmc1.cpu_peek(&memory, 0xC000), Some(10));
+
+    // Bit 7 resets the shift register and goes back to mode 3
+    mmc1.cpu_write(&mut memory, 0x8000, 0x01);
+    mmc1.tick();
+    mmc1.tick();
+    mmc1.cpu_write(&mut memory, 0x8000, 0x80);
+    mmc1.tick();
+    mmc1.tick();
+    assert_eq!(mmc1.cpu_peek(&memory, 0xC000), Some(30));
+    write_serial(&mut mmc1, &mut memory, 0xE000, 0x03);
+    assert_eq!(mmc1.cpu_peek(&memory, 0x8000), Some(6));
+
+    // CHR banks, 8KB mode then 4KB mode
+    write_serial(&mut mmc1, &mut memory, 0xA000, 0x07);
+    write_serial(&mut mmc1, &mut memory, 0xC000, 0x09);
+    assert_eq!(mmc1.ppu_read(&memory, 0x0000), 24);
+    assert_eq!(mmc1.ppu_read(&memory, 0x1000), 28);
+    write_serial(&mut mmc1, &mut memory, 0x8000, 0x1C);
+    assert_eq!(mmc1.ppu_read(&memory, 0x0000), 28);
+    assert_eq!(mmc1.ppu_read(&memory, 0x1000), 36);
+
+    // PRG RAM enable
+    mmc1.cpu_write(&mut memory, 0x6000, 0x42);
+    assert_eq!(mmc1.cpu_peek(&memory, 0x6000), Some(0x42));
+    write_serial(&mut mmc1, &mut memory, 0xE000, 0x10);
+    assert_eq!(mmc1.cpu_peek(&memory, 0x6000), None);
+}
+
+#[test]
+fn mmc1_consecutive_writes_test() {
+    let mut memory = test_memory(0x40000, 0x2000);
+    let mut mmc1 = Mmc1::new();
+
+    // The dummy write and the write of an INC on consecutive cycles only count once
+    for _ in 0..5 {
+        mmc1.cpu_write(&mut memory, 0xE000, 0x01);
+        mmc1.tick();
+        mmc1.cpu_write(&mut memory, 0xE000, 0x00);
+        mmc1.tick();
+        mmc1.tick();
+    }
+    assert_eq!(mmc1.cpu_peek(&memory, 0x8000), Some(30));
+}
+
+#[test]
+fn mmc1_surom_test() {
+    let mut memory = test_memory(0x80000, 0x2000);
+    let mut mmc1 = Mmc1::new();
+
+    assert_eq!(mmc1.cpu_peek(&memory, 0xC000), Some(30));
+    write_serial(&mut mmc1, &mut memory, 0xA000, 0x10);
+    assert_eq!(mmc1.cpu_peek(&memory, 0x8000), Some(32));
+    assert_eq!(mmc1.cpu_peek(&memory, 0xC000), Some(62));
+    // SUROM doesn't use bit 4 to disable PRG RAM like SNROM
+    assert_eq!(mmc1.cpu_peek(&memory, 0x6000), Some(0));
+}
+
+#[test]
+fn mmc1_snrom_test() {
+    let mut memory = test_memory(0x40000, 0x2000);
+    let mut mmc1 = Mmc1::new();
+
+    mmc1.cpu_write(&mut memory, 0x6000, 0x42);
+    write_serial(&mut mmc1, &mut memory, 0xA000, 0x10);
+    assert_eq!(mmc1.cpu_peek(&memory, 0x6000), None);
+    write_serial(&mut mmc1, &mut memory, 0xA000, 0x00);
+    assert_eq!(mmc1.cpu_peek(&memory, 0x6000), Some(0x42));
+}
+
+#[test]
+fn mmc1_sorom_test() {
+    let mut memory = Memory {
+        prg_ram: vec![0; 0x4000],
+        ..test_memory(0x40000, 0x2000)
+    };
+    let mut mmc1 = Mmc1::new();
+
+    // Bit 3 selects the second 8KB bank, bit 2 isn't wired
+    mmc1.cpu_write(&mut memory, 0x6000, 0x11);
+    write_serial(&mut mmc1, &mut memory, 0xA000, 0x04);
+    assert_eq!(mmc1.cpu_peek(&memory, 0x6000), Some(0x11));
+    write_serial(&mut mmc1, &mut memory, 0xA000, 0x08);
+    assert_eq!(mmc1.cpu_peek(&memory, 0x6000), Some(0x00));
+    mmc1.cpu_write(&mut memory, 0x6000, 0x22);
+    assert_eq!(memory.prg_ram[0x2000], 0x22);
+    write_serial(&mut mmc1, &mut memory, 0xA000, 0x00);
+    assert_eq!(mmc1.cpu_peek(&memory, 0x6000), Some(0x11));
+}
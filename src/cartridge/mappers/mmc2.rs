@@ -0,0 +1,203 @@
+/*!  MMC2 (mapper 9, PxROM) and MMC4 (mapper 10, FxROM)
+
+PRG bank    $A000 - $AFFF    8KB bank at $8000 for MMC2, 16KB bank at $8000 for MMC4
+CHR bank 0/$FD    $B000 - $BFFF    4KB bank at $0000 when latch 0 holds $FD
+CHR bank 0/$FE    $C000 - $CFFF    4KB bank at $0000 when latch 0 holds $FE
+CHR bank 1/$FD    $D000 - $DFFF    4KB bank at $1000 when latch 1 holds $FD
+CHR bank 1/$FE    $E000 - $EFFF    4KB bank at $1000 when latch 1 holds $FE
+Mirroring    $F000 - $FFFF    0: vertical, 1: horizontal
+
+PRG RAM    $6000 - $7FFF (MMC4 only)
+PRG ROM    $8000 - $FFFF, the rest of the space is fixed to the last banks
+
+Each pattern table has a latch, set to $FD or $FE after the PPU fetches the high plane of
+tile $FD or $FE, which switches the CHR bank for the following fetches.
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+pub struct Mmc2 {
+    mmc4: bool,
+    mirroring: Mirroring,
+    prg_bank: u8,
+    chr_banks: [[u8; 2]; 2], // By pattern table, then for latch $FD and $FE
+    latches: [usize; 2],     // 0 for $FD, 1 for $FE
+}
+
+impl Mmc2 {
+    pub fn new(mirroring: Mirroring) -> Self {
+        Self {
+            mmc4: false,
+            mirroring,
+            prg_bank: 0,
+            chr_banks: [[0; 2]; 2],
+            latches: [1; 2],
+        }
+    }
+
+    pub fn mmc4(mirroring: Mirroring) -> Self {
+        Self {
+            mmc4: true,
+            ..Self::new(mirroring)
+        }
+    }
+
+    /// PRG ROM bank at `addr`, and the bank size
+    fn prg_rom_bank(&self, memory: &Memory, addr: u16) -> (usize, usize) {
+        if self.mmc4 {
+            if addr < 0xC000 {
+                (0x4000, self.prg_bank as usize)
+            } else {
+                (0x4000, memory.prg_rom_banks(0x4000) - 1)
+            }
+        } else if addr < 0xA000 {
+            (0x2000, self.prg_bank as usize)
+        } else {
+            let slot = (addr as usize - 0x8000) >> 13;
+            // Wraps around below 32KB, `Memory` then wraps the bank number
+            (
+                0x2000,
+                (memory.prg_rom_banks(0x2000) + slot).wrapping_sub(4),
+            )
+        }
+    }
+
+    fn chr_bank(&self, addr: u16) -> usize {
+        let table = (addr >> 12) as usize & 1;
+        self.chr_banks[table][self.latches[table]] as usize
+    }
+
+    fn update_latch(&mut self, addr: u16) {
+        // MMC2 only watches the last row of the tiles of the first pattern table
+        let (fd, fe) = if addr < 0x1000 && !self.mmc4 {
+            (addr == 0x0FD8, addr == 0x0FE8)
+        } else {
+            (addr & 0x0FF8 == 0x0FD8, addr & 0x0FF8 == 0x0FE8)
+        };
+
+        let table = (addr >> 12) as usize & 1;
+        if fd {
+            self.latches[table] = 0;
+        } else if fe {
+            self.latches[table] = 1;
+        }
+    }
+}
+
+impl Mapper for Mmc2 {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.mmc4 => memory.read_prg_ram(addr),
+            0x8000..=0xFFFF => {
+                let (bank_size, bank) = self.prg_rom_bank(memory, addr);
+                Some(memory.read_prg_rom(bank_size, bank, addr))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF if self.mmc4 => memory.write_prg_ram(addr, value),
+            0xA000..=0xAFFF => self.prg_bank = value & 0x0F,
+            0xB000..=0xBFFF => self.chr_banks[0][0] = value & 0x1F,
+            0xC000..=0xCFFF => self.chr_banks[0][1] = value & 0x1F,
+            0xD000..=0xDFFF => self.chr_banks[1][0] = value & 0x1F,
+            0xE000..=0xEFFF => self.chr_banks[1][1] = value & 0x1F,
+            0xF000..=0xFFFF => {
+                self.mirroring = if value & 1 == 0 {
+                    Mirroring::Vertical
+                } else {
+                    Mirroring::Horizontal
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        let value = memory.read_chr(0x1000, self.chr_bank(addr), addr);
+        self.update_latch(addr);
+        value
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x1000, self.chr_bank(addr), addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[test]
+fn mmc2_test() {
+    let mut memory = test_memory(0x20000, 0x20000);
+    let mut mmc2 = Mmc2::new(Mirroring::Vertical);
+
+    mmc2.cpu_write(&mut memory, 0xA000, 3);
+    assert_eq!(mmc2.cpu_peek(&memory, 0x8000), Some(3));
+    assert_eq!(mmc2.cpu_peek(&memory, 0xA000), Some(13));
+    assert_eq!(mmc2.cpu_peek(&memory, 0xE000), Some(15));
+    assert_eq!(mmc2.cpu_peek(&memory, 0x6000), None);
+
+    for (i, addr) in [0xB000, 0xC000, 0xD000, 0xE000].iter().enumerate() {
+        mmc2.cpu_write(&mut memory, *addr, 4 + i as u8);
+    }
+
+    // Both latches start on $FE, the 4KB banks are 4 of the 1KB banks CHR ROM is numbered in
+    assert_eq!(mmc2.ppu_read(&memory, 0x0000), 4 * 5);
+    assert_eq!(mmc2.ppu_read(&memory, 0x1000), 4 * 7);
+
+    // The fetch setting the latch still uses the previous bank, $0FD8 is in its 4th KB
+    assert_eq!(mmc2.ppu_read(&memory, 0x0FD8), 4 * 5 + 3);
+    assert_eq!(mmc2.ppu_read(&memory, 0x0000), 4 * 4);
+    assert_eq!(mmc2.ppu_read(&memory, 0x1000), 4 * 7);
+
+    // MMC2 only watches $0FD8 and $0FE8 in the first pattern table
+    mmc2.ppu_read(&memory, 0x0FE9);
+    assert_eq!(mmc2.ppu_read(&memory, 0x0000), 4 * 4);
+    mmc2.ppu_read(&memory, 0x0FE8);
+    assert_eq!(mmc2.ppu_read(&memory, 0x0000), 4 * 5);
+
+    mmc2.ppu_read(&memory, 0x1FDB);
+    assert_eq!(mmc2.ppu_read(&memory, 0x1000), 4 * 6);
+
+    mmc2.cpu_write(&mut memory, 0xF000, 1);
+    assert_eq!(mmc2.mirroring(), Mirroring::Horizontal);
+}
+
+#[test]
+fn mmc2_small_prg_rom_test() {
+    // 16KB of PRG ROM, the three fixed banks are its last 24KB wrapped around
+    let memory = test_memory(0x4000, 0x2000);
+    let mmc2 = Mmc2::new(Mirroring::Vertical);
+
+    assert_eq!(mmc2.cpu_peek(&memory, 0xA000), Some(1));
+    assert_eq!(mmc2.cpu_peek(&memory, 0xC000), Some(0));
+    assert_eq!(mmc2.cpu_peek(&memory, 0xE000), Some(1));
+}
+
+#[test]
+fn mmc4_test() {
+    let mut memory = test_memory(0x20000, 0x20000);
+    let mut mmc4 = Mmc2::mmc4(Mirroring::Vertical);
+
+    mmc4.cpu_write(&mut memory, 0xA000, 3);
+    assert_eq!(mmc4.cpu_peek(&memory, 0x8000), Some(6));
+    assert_eq!(mmc4.cpu_peek(&memory, 0xA000), Some(7));
+    assert_eq!(mmc4.cpu_peek(&memory, 0xC000), Some(14));
+
+    mmc4.cpu_write(&mut memory, 0x6000, 0x42);
+    assert_eq!(mmc4.cpu_peek(&memory, 0x6000), Some(0x42));
+
+    mmc4.cpu_write(&mut memory, 0xB000, 8);
+    mmc4.cpu_write(&mut memory, 0xC000, 9);
+    // MMC4 watches the whole row in both pattern tables
+    mmc4.ppu_read(&memory, 0x0FDD);
+    assert_eq!(mmc4.ppu_read(&memory, 0x0000), 4 * 8);
+    mmc4.ppu_read(&memory, 0x0FEF);
+    assert_eq!(mmc4.ppu_read(&memory, 0x0000), 4 * 9);
+}
@@ -0,0 +1,426 @@
+/*!  MMC3 (mapper 4), TxROM boards
+
+Registers are selected by the address range and bit 0 of the address:
+
+Bank select    $8000 - $9FFE, even    CP...RRR: CHR inversion, PRG mode, bank register to update
+Bank data    $8001 - $9FFF, odd    New value of the selected bank register
+Mirroring    $A000 - $BFFE, even    0: vertical, 1: horizontal, ignored by four-screen boards
+PRG RAM protect    $A001 - $BFFF, odd    EW......: chip enable, write protect
+IRQ latch    $C000 - $DFFE, even    Value reloaded in the counter
+IRQ reload    $C001 - $DFFF, odd    Reloads the counter on the next clock
+IRQ disable    $E000 - $FFFE, even    Also acknowledges a pending IRQ
+IRQ enable    $E001 - $FFFF, odd
+
+PRG RAM    $6000 - $7FFF
+PRG ROM    $8000 - $FFFF, four 8KB banks, two of them are fixed to the second to last and last banks
+CHR    $0000 - $1FFF, two 2KB banks and four 1KB banks, swapped with inversion
+
+The IRQ counter is clocked by rises of PPU A12 after it stayed low for a few CPU cycles, which
+happens once per scanline when the background and sprites use different pattern tables.
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+/// CPU cycles A12 has to stay low before a rise clocks the counter
+const A12_FILTER_CYCLES: u64 = 3;
+
+/// The chip revisions differ in how a counter at zero triggers the IRQ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mmc3Revision {
+    /// MMC3B and MMC3C, the IRQ is triggered on every clock leaving the counter at zero
+    Sharp,
+    /// MMC3A, the IRQ is only triggered when the counter decrements or is reloaded to zero
+    Nec,
+}
+
+pub struct Mmc3 {
+    revision: Mmc3Revision,
+    mirroring: Mirroring,
+    bank_select: u8,
+    banks: [u8; 8], // R0-R5 CHR banks, R6-R7 PRG banks
+    prg_ram_enabled: bool,
+    prg_ram_protected: bool,
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq: bool,
+    a12: bool,
+    a12_low_since: u64,
+    cycle: u64,
+}
+
+impl Mmc3 {
+    pub fn new(mirroring: Mirroring, revision: Mmc3Revision) -> Self {
+        Self {
+            revision,
+            mirroring,
+            bank_select: 0,
+            banks: [0, 2, 4, 5, 6, 7, 0, 1],
+            // Many games never write $A001, the RAM is usable at power up
+            prg_ram_enabled: true,
+            prg_ram_protected: false,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq: false,
+            a12: false,
+            a12_low_since: 0,
+            cycle: 0,
+        }
+    }
+
+    /// 8KB PRG ROM bank at `addr`
+    fn prg_rom_bank(&self, memory: &Memory, addr: u16) -> usize {
+        // Wraps around with a single bank, `Memory` then wraps the bank number
+        let second_last = memory.prg_rom_banks(0x2000).wrapping_sub(2);
+        let swapped = self.bank_select & 0x40 != 0;
+        match (addr >> 13) & 0x03 {
+            0 if swapped => second_last,
+            0 => self.banks[6] as usize,
+            1 => self.banks[7] as usize,
+            2 if swapped => self.banks[6] as usize,
+            2 => second_last,
+            _ => second_last.wrapping_add(1),
+        }
+    }
+
+    /// 1KB CHR bank at `addr`
+    fn chr_bank(&self, addr: u16) -> usize {
+        // With inversion the 2KB banks are at $1000 and the 1KB ones at $0000
+        let addr = if self.bank_select & 0x80 != 0 {
+            addr ^ 0x1000
+        } else {
+            addr
+        };
+
+        let slot = (addr >> 10) as usize;
+        match slot {
+            0..=3 => (self.banks[slot / 2] & 0xFE) as usize | (slot & 1),
+            _ => self.banks[slot - 2] as usize,
+        }
+    }
+
+    fn clock_irq_counter(&mut self) {
+        let previous = self.irq_counter;
+        let reloaded = self.irq_reload;
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+        } else {
+            self.irq_counter -= 1;
+        }
+        self.irq_reload = false;
+
+        let triggered = match self.revision {
+            Mmc3Revision::Sharp => self.irq_counter == 0,
+            Mmc3Revision::Nec => self.irq_counter == 0 && (previous != 0 || reloaded),
+        };
+        if triggered && self.irq_enabled {
+            self.irq = true;
+        }
+    }
+}
+
+impl Mapper for Mmc3 {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled => memory.read_prg_ram(addr),
+            0x8000..=0xFFFF => {
+                Some(memory.read_prg_rom(0x2000, self.prg_rom_bank(memory, addr), addr))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        match (addr, addr & 1 == 0) {
+            (0x6000..=0x7FFF, _) if self.prg_ram_enabled && !self.prg_ram_protected => {
+                memory.write_prg_ram(addr, value);
+            }
+            (0x8000..=0x9FFF, true) => self.bank_select = value,
+            (0x8000..=0x9FFF, false) => self.banks[(self.bank_select & 0x07) as usize] = value,
+            // Four-screen boards don't use the mirroring output
+            (0xA000..=0xBFFF, true) if self.mirroring != Mirroring::FourScreen => {
+                self.mirroring = if value & 1 == 0 {
+                    Mirroring::Vertical
+                } else {
+                    Mirroring::Horizontal
+                };
+            }
+            (0xA000..=0xBFFF, false) => {
+                self.prg_ram_enabled = value & 0x80 != 0;
+                self.prg_ram_protected = value & 0x40 != 0;
+            }
+            (0xC000..=0xDFFF, true) => self.irq_latch = value,
+            (0xC000..=0xDFFF, false) => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            }
+            (0xE000..=0xFFFF, true) => {
+                self.irq_enabled = false;
+                self.irq = false;
+            }
+            (0xE000..=0xFFFF, false) => self.irq_enabled = true,
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x400, self.chr_bank(addr), addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x400, self.chr_bank(addr), addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn irq(&self) -> bool {
+        self.irq
+    }
+
+    fn tick(&mut self) {
+        self.cycle += 1;
+    }
+
+    fn ppu_address(&mut self, addr: u16) {
+        let a12 = addr & 0x1000 != 0;
+        if a12 && !self.a12 && self.cycle - self.a12_low_since >= A12_FILTER_CYCLES {
+            self.clock_irq_counter();
+        } else if !a12 && self.a12 {
+            self.a12_low_since = self.cycle;
+        }
+        self.a12 = a12;
+    }
+}
+
+/// Puts a rise of A12 on the PPU bus, after it stayed low long enough
+#[cfg(test)]
+fn clock_a12(mmc3: &mut Mmc3) {
+    mmc3.ppu_address(0x0000);
+    for _ in 0..A12_FILTER_CYCLES {
+        mmc3.tick();
+    }
+    mmc3.ppu_address(0x1000);
+}
+
+#[test]
+fn mmc3_banks_test() {
+    let mut memory = test_memory(0x20000, 0x40000);
+    let mut mmc3 = Mmc3::new(Mirroring::Vertical, Mmc3Revision::Sharp);
+
+    for (register, bank) in [8, 11, 20, 21, 22, 23, 3, 5].iter().enumerate() {
+        mmc3.cpu_write(&mut memory, 0x8000, register as u8);
+        mmc3.cpu_write(&mut memory, 0x8001, *bank);
+    }
+
+    assert_eq!(mmc3.cpu_peek(&memory, 0x8000), Some(3));
+    assert_eq!(mmc3.cpu_peek(&memory, 0xA000), Some(5));
+    assert_eq!(mmc3.cpu_peek(&memory, 0xC000), Some(14));
+    assert_eq!(mmc3.cpu_peek(&memory, 0xE000), Some(15));
+    // 2KB banks ignore the low bit
+    assert_eq!(mmc3.ppu_read(&memory, 0x0000), 8);
+    assert_eq!(mmc3.ppu_read(&memory, 0x0400), 9);
+    assert_eq!(mmc3.ppu_read(&memory, 0x0800), 10);
+    assert_eq!(mmc3.ppu_read(&memory, 0x0C00), 11);
+    assert_eq!(mmc3.ppu_read(&memory, 0x1000), 20);
+    assert_eq!(mmc3.ppu_read(&memory, 0x1C00), 23);
+
+    // PRG mode 1 and CHR inversion
+    mmc3.cpu_write(&mut memory, 0x8000, 0xC0);
+    assert_eq!(mmc3.cpu_peek(&memory, 0x8000), Some(14));
+    assert_eq!(mmc3.cpu_peek(&memory, 0xA000), Some(5));
+    assert_eq!(mmc3.cpu_peek(&memory, 0xC000), Some(3));
+    assert_eq!(mmc3.cpu_peek(&memory, 0xE000), Some(15));
+    assert_eq!(mmc3.ppu_read(&memory, 0x0000), 20);
+    assert_eq!(mmc3.ppu_read(&memory, 0x1400), 9);
+
+    mmc3.cpu_write(&mut memory, 0xA000, 1);
+    assert_eq!(mmc3.mirroring(), Mirroring::Horizontal);
+
+    let mut mmc3 = Mmc3::new(Mirroring::FourScreen, Mmc3Revision::Sharp);
+    mmc3.cpu_write(&mut memory, 0xA000, 1);
+    assert_eq!(mmc3.mirroring(), Mirroring::FourScreen);
+}
+
+#[test]
+fn mmc3_small_prg_rom_test() {
+    // 8KB of PRG ROM, the fixed banks wrap around to its only bank
+    let mut memory = test_memory(0x2000, 0x2000);
+    memory.prg_rom[0x1FFF] = 0x42;
+    let mmc3 = Mmc3::new(Mirroring::Vertical, Mmc3Revision::Sharp);
+
+    assert_eq!(mmc3.cpu_peek(&memory, 0xDFFF), Some(0x42));
+    assert_eq!(mmc3.cpu_peek(&memory, 0xFFFF), Some(0x42));
+}
+
+#[test]
+fn mmc3_prg_ram_protect_test() {
+    let mut memory = test_memory(0x20000, 0x40000);
+    let mut mmc3 = Mmc3::new(Mirroring::Vertical, Mmc3Revision::Sharp);
+
+    mmc3.cpu_write(&mut memory, 0x6000, 0x42);
+    mmc3.cpu_write(&mut memory, 0xA001, 0xC0);
+    mmc3.cpu_write(&mut memory, 0x6000, 0x43);
+    assert_eq!(mmc3.cpu_peek(&memory, 0x6000), Some(0x42));
+    mmc3.cpu_write(&mut memory, 0xA001, 0x00);
+    assert_eq!(mmc3.cpu_peek(&memory, 0x6000), None);
+}
+
+#[test]
+fn mmc3_irq_test() {
+    let mut memory = test_memory(0x20000, 0x40000);
+    let mut mmc3 = Mmc3::new(Mirroring::Vertical, Mmc3Revision::Sharp);
+
+    mmc3.cpu_write(&mut memory, 0xC000, 2);
+    mmc3.cpu_write(&mut memory, 0xC001, 0);
+    mmc3.cpu_write(&mut memory, 0xE001, 0);
+
+    clock_a12(&mut mmc3); // Reload to 2
+    clock_a12(&mut mmc3); // 1
+    assert!(!mmc3.irq());
+    clock_a12(&mut mmc3); // 0
+    assert!(mmc3.irq());
+
+    mmc3.cpu_write(&mut memory, 0xE000, 0);
+    assert!(!mmc3.irq());
+
+    // A12 toggling faster than the filter doesn't clock the counter
+    mmc3.cpu_write(&mut memory, 0xE001, 0);
+    for _ in 0..8 {
+        mmc3.ppu_address(0x0000);
+        mmc3.tick();
+        mmc3.ppu_address(0x1000);
+    }
+    clock_a12(&mut mmc3); // Reload to 2
+    clock_a12(&mut mmc3); // 1
+    assert!(!mmc3.irq());
+}
+
+#[test]
+fn mmc3_revisions_test() {
+    let mut memory = test_memory(0x20000, 0x40000);
+
+    // With a latch of 0, the Sharp chips trigger on every clock
+    let mut sharp = Mmc3::new(Mirroring::Vertical, Mmc3Revision::Sharp);
+    sharp.cpu_write(&mut memory, 0xC000, 0);
+    sharp.cpu_write(&mut memory, 0xE001, 0);
+    clock_a12(&mut sharp);
+    sharp.cpu_write(&mut memory, 0xE000, 0);
+    sharp.cpu_write(&mut memory, 0xE001, 0);
+    clock_a12(&mut sharp);
+    assert!(sharp.irq());
+
+    // The NEC chips only do after writing $C001
+    let mut nec = Mmc3::new(Mirroring::Vertical, Mmc3Revision::Nec);
+    nec.cpu_write(&mut memory, 0xC000, 0);
+    nec.cpu_write(&mut memory, 0xE001, 0);
+    clock_a12(&mut nec);
+    assert!(!nec.irq());
+    nec.cpu_write(&mut memory, 0xC001, 0);
+    clock_a12(&mut nec);
+    assert!(nec.irq());
+}
+
+#[test]
+fn mmc3_irq_reload_test() {
+    let mut memory = test_memory(0x20000, 0x40000);
+    let mut mmc3 = Mmc3::new(Mirroring::Vertical, Mmc3Revision::Sharp);
+
+    mmc3.cpu_write(&mut memory, 0xC000, 3);
+    mmc3.cpu_write(&mut memory, 0xC001, 0);
+    mmc3.cpu_write(&mut memory, 0xE001, 0);
+    clock_a12(&mut mmc3); // Reload to 3
+
+    // A new latch is only used on the next reload
+    mmc3.cpu_write(&mut memory, 0xC000, 10);
+    clock_a12(&mut mmc3); // 2
+    clock_a12(&mut mmc3); // 1
+    clock_a12(&mut mmc3); // 0
+    assert!(mmc3.irq());
+    mmc3.cpu_write(&mut memory, 0xE000, 0);
+    mmc3.cpu_write(&mut memory, 0xE001, 0);
+    for _ in 0..10 {
+        clock_a12(&mut mmc3); // Reload to 10, then down to 1
+    }
+    assert!(!mmc3.irq());
+
+    // $C001 reloads on the next clock, whatever the counter is at
+    mmc3.cpu_write(&mut memory, 0xC000, 1);
+    mmc3.cpu_write(&mut memory, 0xC001, 0);
+    clock_a12(&mut mmc3); // Reload to 1
+    assert!(!mmc3.irq());
+    clock_a12(&mut mmc3); // 0
+    assert!(mmc3.irq());
+
+    // Disabling the IRQ doesn't stop the counter
+    mmc3.cpu_write(&mut memory, 0xE000, 0);
+    clock_a12(&mut mmc3); // Reload to 1
+    clock_a12(&mut mmc3); // 0
+    assert!(!mmc3.irq());
+    mmc3.cpu_write(&mut memory, 0xE001, 0);
+    clock_a12(&mut mmc3); // Reload to 1
+    clock_a12(&mut mmc3); // 0
+    assert!(mmc3.irq());
+
+    // A latch of 255 takes the reload and 255 more clocks
+    mmc3.cpu_write(&mut memory, 0xE000, 0);
+    mmc3.cpu_write(&mut memory, 0xE001, 0);
+    mmc3.cpu_write(&mut memory, 0xC000, 255);
+    mmc3.cpu_write(&mut memory, 0xC001, 0);
+    for _ in 0..255 {
+        clock_a12(&mut mmc3);
+    }
+    assert!(!mmc3.irq());
+    clock_a12(&mut mmc3);
+    assert!(mmc3.irq());
+}
+
+#[test]
+fn mmc3_scanline_irq_test() {
+    use crate::{
+        bus::{Bus, NesBus},
+        cartridge::Cartridge,
+        nes_rom::RomFile,
+    };
+
+    // 32KB of PRG ROM and 8KB of CHR ROM on mapper 4
+    let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 0x02, 0x01, 0x40, 0x00];
+    rom.resize(16 + 0x8000 + 0x2000, 0);
+    let mut bus = NesBus::new();
+    bus.cartridge = Cartridge::new(&RomFile::new(&rom).unwrap()).unwrap();
+
+    // Background at $0000 and sprites at $1000, A12 rises once per rendered scanline
+    bus.write(0x2000, 0x08);
+    bus.write(0x2001, 0x18);
+    while bus.ppu.scanline != 241 {
+        bus.tick();
+    }
+    bus.write(0xC000, 9);
+    bus.write(0xC001, 0);
+    bus.write(0xE001, 0);
+
+    // Reloaded on the pre-render scanline, then triggers every 10 scanlines
+    let mut irq_scanlines = Vec::new();
+    while bus.ppu.scanline != 240 {
+        bus.tick();
+        if bus.cartridge.irq() {
+            irq_scanlines.push(bus.ppu.scanline);
+            bus.write(0xE000, 0);
+            bus.write(0xE001, 0);
+        }
+    }
+    assert_eq!(irq_scanlines, (8..240).step_by(10).collect::<Vec<u16>>());
+
+    // Nothing clocks the counter with rendering disabled
+    bus.write(0x2001, 0x00);
+    while bus.ppu.scanline != 239 {
+        bus.tick();
+        assert!(!bus.cartridge.irq());
+    }
+}
@@ -0,0 +1,830 @@
+/*!  MMC5 (mapper 5), ExROM boards
+
+Pulse 1    $5000 - $5003    Same as the APU pulse 1, without the sweep register
+Pulse 2    $5004 - $5007    Same as the APU pulse 2, without the sweep register
+PCM control    $5010    I......M: IRQ enable, read mode. Reads give the IRQ flag in bit 7
+PCM data    $5011    Level of the PCM channel in write mode, $00 is ignored
+Audio status    $5015    ......21: pulse length counters enable and status, like $4015
+PRG mode    $5100    ......PP: 32KB, 16KB, 16KB + 8KB + 8KB, 8KB banks
+CHR mode    $5101    ......CC: 8KB, 4KB, 2KB, 1KB banks
+PRG RAM protect    $5102 - $5103    Writes need $02 in $5102 and $01 in $5103
+ExRAM mode    $5104    ......XX: nametable, extended attributes, CPU RAM, CPU ROM
+Nametable mapping    $5105    DDCCBBAA: nametable RAM page A/B, ExRAM or fill mode, for each nametable
+Fill mode tile    $5106
+Fill mode attribute    $5107    ......AA
+PRG RAM bank    $5113    .....BBB: 8KB bank at $6000
+PRG banks    $5114 - $5117    RBBBBBBB: ROM/RAM, 8KB bank. $5117 is always ROM
+CHR banks    $5120 - $512B    Sprite banks, then background banks used for 8x16 sprites
+CHR upper bits    $5130    ......UU: bits 8-9 of the next CHR bank writes
+Vertical split    $5200    ES.TTTTT: enable, right side, tile column
+Split scroll    $5201    Vertical scroll of the split region
+Split bank    $5202    4KB CHR bank of the split region
+IRQ scanline    $5203    Scanline raising the IRQ
+IRQ status    $5204    Write E.......: IRQ enable. Reads PF......: IRQ pending, in frame
+Multiplier    $5205 - $5206    Factors, reads give the low and high bytes of the product
+ExRAM    $5C00 - $5FFF    1KB
+
+PRG RAM    $6000 - $7FFF, and in $8000 - $DFFF with the ROM/RAM bit clear
+PRG ROM    $8000 - $FFFF, laid out by the PRG mode
+CHR ROM    $0000 - $1FFF, laid out by the CHR mode
+
+The MMC5 only sees the PPU buses, it follows the rendering by counting the fetches. A scanline
+starts on the third fetch in a row of the same nametable address, which the PPU does at the
+end of each scanline. The frame ends when the PPU stops fetching, when rendering is disabled
+through $2001 and when the CPU reads the NMI vector. The first two tiles of a frame are
+fetched before the MMC5 sees the frame start, they don't get the extended attributes or the split.
+
+NOTE: The PRG RAM banks wrap around the size of the PRG RAM, without the chip select of the
+boards with two RAM chips.
+*/
+
+use crate::apu::Pulse;
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+/// CPU cycles without PPU fetches ending the frame
+const IDLE_CYCLES: u8 = 3;
+/// CPU cycles between the clocks of the envelopes and length counters, 240Hz
+const FRAME_PERIOD: u16 = 7457;
+/// Output level of a step of the pulse channels, the same as a step of the APU pulses
+const PULSE_LEVEL: f32 = 0.00752;
+/// Output level of a step of the PCM channel, the same range as the DMC
+const PCM_LEVEL: f32 = 0.0017;
+
+/// What the PPU is fetching, from the fetches since the start of the scanline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fetch {
+    /// Nametable byte of the background tile at a column, the first two are the prefetched ones
+    Nametable(u16),
+    Attribute,
+    Pattern,
+    Sprite,
+    /// Outside of the frame, or the unused nametable fetches
+    Other,
+}
+
+pub struct Mmc5 {
+    prg_mode: u8,
+    prg_banks: [u8; 4], // $5114-$5117
+    prg_ram_bank: u8,
+    prg_ram_protect: [u8; 2],
+    chr_mode: u8,
+    chr_banks: [u16; 12], // $5120-$512B with the upper bits
+    chr_upper: u8,
+    background_banks_written: bool, // The last CHR bank write was to $5128-$512B
+    exram: Vec<u8>,
+    exram_mode: u8,
+    nametable_mapping: u8,
+    fill_tile: u8,
+    fill_attribute: u8,
+    split_control: u8,
+    split_scroll: u8,
+    split_bank: u8,
+    split_y: u8, // Split scroll at the start of the frame
+    irq_scanline: u8,
+    irq_enabled: bool,
+    irq_pending: bool,
+    multiplicand: u8,
+    multiplier: u8,
+    sprites_8x16: bool, // Snooped from the CPU writes to $2000
+    // Tracking of the PPU fetches
+    in_frame: bool,
+    scanline: u8,
+    fetches: u16,
+    idle_cycles: u8,
+    last_nametable_addr: Option<u16>,
+    nametable_repeats: u8,
+    ext_attribute: u8, // ExRAM byte of the background tile being fetched
+    split_tile: Option<(u16, u16)>, // Row and column of the background tile fetched in the split
+    // Audio
+    pulse1: Pulse,
+    pulse2: Pulse,
+    frame_timer: u16,
+    odd_cycle: bool,
+    pcm: u8,
+    pcm_read_mode: bool,
+    pcm_irq_enabled: bool,
+    pcm_irq: bool,
+}
+
+impl Default for Mmc5 {
+    fn default() -> Self {
+        Self {
+            prg_mode: 3,
+            prg_banks: [0xFF; 4],
+            prg_ram_bank: 0,
+            prg_ram_protect: [0; 2],
+            chr_mode: 0,
+            chr_banks: [0; 12],
+            chr_upper: 0,
+            background_banks_written: false,
+            exram: vec![0; 0x400],
+            exram_mode: 0,
+            nametable_mapping: 0,
+            fill_tile: 0,
+            fill_attribute: 0,
+            split_control: 0,
+            split_scroll: 0,
+            split_bank: 0,
+            split_y: 0,
+            irq_scanline: 0,
+            irq_enabled: false,
+            irq_pending: false,
+            multiplicand: 0xFF,
+            multiplier: 0xFF,
+            sprites_8x16: false,
+            in_frame: false,
+            scanline: 0,
+            fetches: 0,
+            idle_cycles: 0,
+            last_nametable_addr: None,
+            nametable_repeats: 0,
+            ext_attribute: 0,
+            split_tile: None,
+            pulse1: Pulse::without_sweep(),
+            pulse2: Pulse::without_sweep(),
+            frame_timer: 0,
+            odd_cycle: false,
+            pcm: 0,
+            pcm_read_mode: false,
+            pcm_irq_enabled: false,
+            pcm_irq: false,
+        }
+    }
+}
+
+impl Mmc5 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bank register and 8KB bank at `addr` in $8000-$FFFF
+    fn prg_bank(&self, addr: u16) -> (u8, usize) {
+        let slot = (addr >> 13) as usize & 0x03;
+        // Index of the register and mask of the 8KB banks in the bank size
+        let (register, mask) = match (self.prg_mode, slot) {
+            (0, _) => (3, 0x03),
+            (1, 0..=1) | (2, 0..=1) => (1, 0x01),
+            (1, _) => (3, 0x01),
+            (_, slot) => (slot, 0x00),
+        };
+
+        let value = self.prg_banks[register];
+        // $5117 always maps ROM
+        let value = if register == 3 { value | 0x80 } else { value };
+        (value, (value as usize & 0x7F & !mask) | (slot & mask))
+    }
+
+    /// Offset in PRG RAM of `addr` in an 8KB bank
+    fn prg_ram_addr(bank: u8, addr: u16) -> u16 {
+        (bank as u16 & 0x07) << 13 | (addr & 0x1FFF)
+    }
+
+    fn is_prg_ram_writable(&self) -> bool {
+        self.prg_ram_protect == [0x02, 0x01]
+    }
+
+    /// Bank size and bank at `addr`, in the background banks or the sprite banks
+    fn chr_bank(&self, addr: u16, background: bool) -> (usize, usize) {
+        let (size, register) = match self.chr_mode {
+            0 => (0x2000, 7),
+            1 => (0x1000, (addr >> 12) * 4 + 3),
+            2 => (0x800, (addr >> 11) * 2 + 1),
+            _ => (0x400, addr >> 10),
+        };
+
+        // The background banks are the same for both pattern tables
+        let register = if background {
+            8 + (register as usize & 0x03)
+        } else {
+            register as usize
+        };
+        (size, self.chr_banks[register] as usize)
+    }
+
+    /// The banks used outside of the fetches of 8x16 sprites are the last ones written
+    fn uses_background_banks(&self, fetch: Fetch) -> bool {
+        if self.sprites_8x16 && fetch != Fetch::Other {
+            fetch != Fetch::Sprite
+        } else {
+            self.background_banks_written
+        }
+    }
+
+    fn fetch(&self) -> Fetch {
+        if !self.in_frame {
+            return Fetch::Other;
+        }
+
+        let (column, step) = match self.fetches {
+            0..=127 => (self.fetches / 4 + 2, self.fetches % 4),
+            128..=159 => return Fetch::Sprite,
+            160..=167 => ((self.fetches - 160) / 4, self.fetches % 4),
+            _ => return Fetch::Other,
+        };
+        match step {
+            0 => Fetch::Nametable(column),
+            1 => Fetch::Attribute,
+            _ => Fetch::Pattern,
+        }
+    }
+
+    /// Counts a PPU read, `nametable_addr` is the address of nametable fetches
+    fn count_fetch(&mut self, nametable_addr: Option<u16>) {
+        self.idle_cycles = 0;
+        self.fetches = self.fetches.saturating_add(1);
+
+        match nametable_addr {
+            Some(_) if nametable_addr == self.last_nametable_addr => {
+                self.nametable_repeats += 1;
+                if self.nametable_repeats == 2 {
+                    self.start_scanline();
+                }
+            }
+            _ => self.nametable_repeats = 0,
+        }
+        self.last_nametable_addr = nametable_addr;
+    }
+
+    fn start_scanline(&mut self) {
+        self.fetches = 0;
+        if self.in_frame {
+            self.scanline = self.scanline.wrapping_add(1);
+            if self.scanline == self.irq_scanline {
+                self.irq_pending = true;
+            }
+        } else {
+            self.in_frame = true;
+            self.scanline = 0;
+            self.irq_pending = false;
+            self.split_y = self.split_scroll;
+        }
+    }
+
+    fn end_frame(&mut self) {
+        self.in_frame = false;
+        self.last_nametable_addr = None;
+        self.nametable_repeats = 0;
+    }
+
+    fn is_split_column(&self, column: u16) -> bool {
+        let threshold = (self.split_control & 0x1F) as u16;
+        let right_side = self.split_control & 0x40 != 0;
+        self.split_control & 0x80 != 0
+            && self.exram_mode <= 1
+            && (column >= threshold) == right_side
+    }
+
+    /// Row of the split region for a tile of the current scanline
+    fn split_row(&self, column: u16) -> u16 {
+        // The first two tiles are fetched on the previous scanline
+        let scanline = if column < 2 {
+            self.scanline as u16 + 1
+        } else {
+            self.scanline as u16
+        };
+        (self.split_y as u16 + scanline) % 240
+    }
+
+    /// Nametable byte at `addr` in the memory mapped by $5105, `None` for the nametable RAM
+    fn mapped_nametable(&self, addr: u16) -> Option<u8> {
+        let offset = addr as usize & 0x3FF;
+        match (self.nametable_mapping >> ((addr >> 9) & 0x06)) & 0x03 {
+            0 | 1 => None,
+            2 if self.exram_mode <= 1 => Some(self.exram[offset]),
+            2 => Some(0),
+            _ if offset < 0x3C0 => Some(self.fill_tile),
+            _ => Some(self.fill_attribute * 0x55),
+        }
+    }
+
+    fn clock_audio(&mut self) {
+        self.odd_cycle = !self.odd_cycle;
+        if self.odd_cycle {
+            self.pulse1.clock_timer();
+            self.pulse2.clock_timer();
+        }
+
+        self.frame_timer += 1;
+        if self.frame_timer == FRAME_PERIOD {
+            self.frame_timer = 0;
+            for pulse in [&mut self.pulse1, &mut self.pulse2].iter_mut() {
+                pulse.envelope.clock();
+                pulse.length.clock();
+            }
+        }
+    }
+}
+
+impl Mapper for Mmc5 {
+    fn cpu_read(&mut self, memory: &Memory, addr: u16) -> Option<u8> {
+        let value = self.cpu_peek(memory, addr);
+        match addr {
+            0x5010 => self.pcm_irq = false,
+            0x5204 => self.irq_pending = false,
+            0x8000..=0xBFFF if self.pcm_read_mode => match value {
+                Some(0) => self.pcm_irq = self.pcm_irq_enabled,
+                Some(value) => self.pcm = value,
+                None => {}
+            },
+            0xFFFA | 0xFFFB => self.end_frame(),
+            _ => {}
+        }
+        value
+    }
+
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x5010 => Some((self.pcm_irq as u8) << 7 | self.pcm_read_mode as u8),
+            0x5015 => Some(
+                (self.pulse2.length.is_active() as u8) << 1 | self.pulse1.length.is_active() as u8,
+            ),
+            0x5204 => Some((self.irq_pending as u8) << 7 | (self.in_frame as u8) << 6),
+            0x5205 => Some((self.multiplicand as u16 * self.multiplier as u16) as u8),
+            0x5206 => Some(((self.multiplicand as u16 * self.multiplier as u16) >> 8) as u8),
+            0x5C00..=0x5FFF if self.exram_mode >= 2 => Some(self.exram[addr as usize & 0x3FF]),
+            0x6000..=0x7FFF => memory.read_prg_ram(Mmc5::prg_ram_addr(self.prg_ram_bank, addr)),
+            0x8000..=0xFFFF => match self.prg_bank(addr) {
+                (value, bank) if value & 0x80 != 0 => Some(memory.read_prg_rom(0x2000, bank, addr)),
+                (_, bank) => memory.read_prg_ram(Mmc5::prg_ram_addr(bank as u8, addr)),
+            },
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        match addr {
+            0x5000..=0x5003 => self.pulse1.write(addr, value),
+            0x5004..=0x5007 => self.pulse2.write(addr, value),
+            0x5010 => {
+                self.pcm_irq_enabled = value & 0x80 != 0;
+                self.pcm_read_mode = value & 0x01 != 0;
+            }
+            0x5011 if !self.pcm_read_mode && value != 0 => self.pcm = value,
+            0x5015 => {
+                self.pulse1.length.set_enabled(value & 0x01 != 0);
+                self.pulse2.length.set_enabled(value & 0x02 != 0);
+            }
+            0x5100 => self.prg_mode = value & 0x03,
+            0x5101 => self.chr_mode = value & 0x03,
+            0x5102 => self.prg_ram_protect[0] = value & 0x03,
+            0x5103 => self.prg_ram_protect[1] = value & 0x03,
+            0x5104 => self.exram_mode = value & 0x03,
+            0x5105 => self.nametable_mapping = value,
+            0x5106 => self.fill_tile = value,
+            0x5107 => self.fill_attribute = value & 0x03,
+            0x5113 => self.prg_ram_bank = value,
+            0x5114..=0x5117 => self.prg_banks[addr as usize - 0x5114] = value,
+            0x5120..=0x512B => {
+                let register = addr as usize - 0x5120;
+                self.chr_banks[register] = (self.chr_upper as u16) << 8 | value as u16;
+                self.background_banks_written = register >= 8;
+            }
+            0x5130 => self.chr_upper = value & 0x03,
+            0x5200 => self.split_control = value,
+            0x5201 => self.split_scroll = value,
+            0x5202 => self.split_bank = value,
+            0x5203 => self.irq_scanline = value,
+            0x5204 => self.irq_enabled = value & 0x80 != 0,
+            0x5205 => self.multiplicand = value,
+            0x5206 => self.multiplier = value,
+            0x5C00..=0x5FFF => match self.exram_mode {
+                // As nametables ExRAM can only be written during rendering
+                0 | 1 => self.exram[addr as usize & 0x3FF] = if self.in_frame { value } else { 0 },
+                2 => self.exram[addr as usize & 0x3FF] = value,
+                _ => {}
+            },
+            0x6000..=0x7FFF if self.is_prg_ram_writable() => {
+                memory.write_prg_ram(Mmc5::prg_ram_addr(self.prg_ram_bank, addr), value);
+            }
+            0x8000..=0xDFFF if self.is_prg_ram_writable() => {
+                let (register, bank) = self.prg_bank(addr);
+                if register & 0x80 == 0 {
+                    memory.write_prg_ram(Mmc5::prg_ram_addr(bank as u8, addr), value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        self.count_fetch(None);
+
+        let fetch = self.fetch();
+        if fetch == Fetch::Pattern {
+            if let Some((row, _)) = self.split_tile {
+                let addr = (addr & 0x0FF8) | (row & 0x07);
+                return memory.read_chr(0x1000, self.split_bank as usize, addr);
+            }
+            if self.exram_mode == 1 {
+                let bank = (self.chr_upper as usize) << 6 | (self.ext_attribute & 0x3F) as usize;
+                return memory.read_chr(0x1000, bank, addr);
+            }
+        }
+
+        let (size, bank) = self.chr_bank(addr, self.uses_background_banks(fetch));
+        memory.read_chr(size, bank, addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        let (size, bank) = self.chr_bank(addr, self.background_banks_written);
+        memory.write_chr(size, bank, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        // ExRAM and fill mode don't use the nametable RAM
+        let page = |nametable: u8| ((self.nametable_mapping >> (nametable * 2)) & 0x03 == 1) as u8;
+        Mirroring::Mapped([page(0), page(1), page(2), page(3)])
+    }
+
+    fn nametable_read(&mut self, _memory: &Memory, addr: u16) -> Option<u8> {
+        self.count_fetch(Some(addr & 0x2FFF));
+
+        match self.fetch() {
+            Fetch::Nametable(column) => {
+                self.ext_attribute = self.exram[addr as usize & 0x3FF];
+                self.split_tile = if self.is_split_column(column) {
+                    Some((self.split_row(column), column))
+                } else {
+                    None
+                };
+
+                if let Some((row, column)) = self.split_tile {
+                    return Some(self.exram[((row / 8) * 32 + column % 32) as usize]);
+                }
+            }
+            Fetch::Attribute => {
+                if let Some((row, column)) = self.split_tile {
+                    let attribute =
+                        self.exram[(0x3C0 + (row / 32) * 8 + (column % 32) / 4) as usize];
+                    let shift = (row & 0x10) >> 2 | (column & 0x02);
+                    return Some(((attribute >> shift) & 0x03) * 0x55);
+                }
+                if self.exram_mode == 1 {
+                    return Some((self.ext_attribute >> 6) * 0x55);
+                }
+            }
+            _ => {}
+        }
+
+        self.mapped_nametable(addr)
+    }
+
+    fn nametable_write(&mut self, _memory: &mut Memory, addr: u16, value: u8) -> bool {
+        match (self.nametable_mapping >> ((addr >> 9) & 0x06)) & 0x03 {
+            0 | 1 => false,
+            2 => {
+                if self.exram_mode <= 1 {
+                    self.exram[addr as usize & 0x3FF] = value;
+                }
+                true
+            }
+            _ => true,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        (self.irq_pending && self.irq_enabled) || self.pcm_irq
+    }
+
+    fn tick(&mut self) {
+        if self.in_frame {
+            self.idle_cycles += 1;
+            if self.idle_cycles >= IDLE_CYCLES {
+                self.end_frame();
+            }
+        }
+
+        self.clock_audio();
+    }
+
+    fn ppu_register_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x2000 => self.sprites_8x16 = value & 0x20 != 0,
+            0x2001 if value & 0x18 == 0 => self.end_frame(),
+            _ => {}
+        }
+    }
+
+    fn audio(&self) -> f32 {
+        let pulse = (self.pulse1.output() + self.pulse2.output()) as f32;
+        pulse * PULSE_LEVEL + self.pcm as f32 * PCM_LEVEL
+    }
+}
+
+/// Does the fetches of a rendered scanline like the PPU, returns the bytes read
+///
+/// The background tiles are in the first row of the first nametable, with the pattern
+/// tables at $0000. The sprite fetches are for empty slots, tile $FF of 8x16 sprites.
+#[cfg(test)]
+fn fetch_scanline(mmc5: &mut Mmc5, memory: &Memory) -> Vec<u8> {
+    let mut values = vec![];
+    let fetch_tile = |mmc5: &mut Mmc5, values: &mut Vec<u8>, column: u16| {
+        let addr = 0x2000 | (column % 32);
+        let tile = mmc5.nametable_read(memory, addr).unwrap_or(0);
+        let attribute = mmc5.nametable_read(memory, 0x23C0 | ((column % 32) / 4));
+        values.push(tile);
+        values.push(attribute.unwrap_or(0));
+        values.push(mmc5.ppu_read(memory, (tile as u16) << 4));
+        values.push(mmc5.ppu_read(memory, (tile as u16) << 4 | 8));
+        // 8 dots
+        mmc5.tick();
+        mmc5.tick();
+    };
+
+    for column in 2..34 {
+        fetch_tile(mmc5, &mut values, column);
+    }
+    for _ in 0..8 {
+        values.push(mmc5.nametable_read(memory, 0x2002).unwrap_or(0));
+        values.push(mmc5.nametable_read(memory, 0x2002).unwrap_or(0));
+        values.push(mmc5.ppu_read(memory, 0x1FF0));
+        values.push(mmc5.ppu_read(memory, 0x1FF8));
+        mmc5.tick();
+        mmc5.tick();
+    }
+    for column in 0..2 {
+        fetch_tile(mmc5, &mut values, column);
+    }
+    for _ in 0..2 {
+        values.push(mmc5.nametable_read(memory, 0x2002).unwrap_or(0));
+    }
+    mmc5.tick();
+    values
+}
+
+#[test]
+fn mmc5_prg_test() {
+    let mut memory = test_memory(0x40000, 0x100000);
+    // 64KB of PRG RAM, as on the largest MMC5 boards
+    memory.prg_ram = vec![0; 0x10000];
+    let mut mmc5 = Mmc5::new();
+
+    // Mode 3 at power up, with the last bank everywhere
+    assert_eq!(mmc5.cpu_peek(&memory, 0x8000), Some(31));
+    assert_eq!(mmc5.cpu_peek(&memory, 0xE000), Some(31));
+
+    mmc5.cpu_write(&mut memory, 0x5114, 0x84);
+    mmc5.cpu_write(&mut memory, 0x5115, 0x85);
+    mmc5.cpu_write(&mut memory, 0x5116, 0x86);
+    mmc5.cpu_write(&mut memory, 0x5117, 0x07);
+    assert_eq!(mmc5.cpu_peek(&memory, 0x8000), Some(4));
+    assert_eq!(mmc5.cpu_peek(&memory, 0xA000), Some(5));
+    assert_eq!(mmc5.cpu_peek(&memory, 0xC000), Some(6));
+    assert_eq!(mmc5.cpu_peek(&memory, 0xE000), Some(7));
+
+    // 16KB + 8KB + 8KB
+    mmc5.cpu_write(&mut memory, 0x5100, 2);
+    assert_eq!(mmc5.cpu_peek(&memory, 0x8000), Some(4));
+    assert_eq!(mmc5.cpu_peek(&memory, 0xA000), Some(5));
+    assert_eq!(mmc5.cpu_peek(&memory, 0xC000), Some(6));
+
+    // 16KB banks
+    mmc5.cpu_write(&mut memory, 0x5100, 1);
+    assert_eq!(mmc5.cpu_peek(&memory, 0xA000), Some(5));
+    assert_eq!(mmc5.cpu_peek(&memory, 0xC000), Some(6));
+    assert_eq!(mmc5.cpu_peek(&memory, 0xE000), Some(7));
+
+    // 32KB bank
+    mmc5.cpu_write(&mut memory, 0x5100, 0);
+    assert_eq!(mmc5.cpu_peek(&memory, 0x8000), Some(4));
+    assert_eq!(mmc5.cpu_peek(&memory, 0xE000), Some(7));
+
+    // PRG RAM, only writable with both protect registers set
+    mmc5.cpu_write(&mut memory, 0x5113, 2);
+    mmc5.cpu_write(&mut memory, 0x6000, 0x42);
+    assert_eq!(mmc5.cpu_peek(&memory, 0x6000), Some(0));
+    mmc5.cpu_write(&mut memory, 0x5102, 0x02);
+    mmc5.cpu_write(&mut memory, 0x5103, 0x01);
+    mmc5.cpu_write(&mut memory, 0x6000, 0x42);
+    assert_eq!(mmc5.cpu_peek(&memory, 0x6000), Some(0x42));
+
+    // RAM in $8000-$DFFF
+    mmc5.cpu_write(&mut memory, 0x5100, 3);
+    mmc5.cpu_write(&mut memory, 0x5115, 0x02);
+    assert_eq!(mmc5.cpu_peek(&memory, 0xA000), Some(0x42));
+    mmc5.cpu_write(&mut memory, 0xA001, 0x43);
+    assert_eq!(mmc5.cpu_peek(&memory, 0x6001), Some(0x43));
+}
+
+#[test]
+fn mmc5_chr_test() {
+    let mut memory = test_memory(0x40000, 0x100000);
+    let mut mmc5 = Mmc5::new();
+
+    for register in 0..12 {
+        mmc5.cpu_write(&mut memory, 0x5120 + register, 0x10 + register as u8);
+    }
+
+    // 1KB banks, the last written banks are the background ones
+    mmc5.cpu_write(&mut memory, 0x5101, 3);
+    assert_eq!(mmc5.ppu_read(&memory, 0x0400), 0x19);
+    assert_eq!(mmc5.ppu_read(&memory, 0x1400), 0x19);
+    mmc5.cpu_write(&mut memory, 0x5125, 0x25);
+    assert_eq!(mmc5.ppu_read(&memory, 0x1400), 0x25);
+
+    // 2KB, 4KB and 8KB banks
+    mmc5.cpu_write(&mut memory, 0x5101, 2);
+    assert_eq!(mmc5.ppu_read(&memory, 0x0C00), 0x13 * 2 + 1);
+    mmc5.cpu_write(&mut memory, 0x5101, 1);
+    assert_eq!(mmc5.ppu_read(&memory, 0x1000), 0x17 * 4);
+    mmc5.cpu_write(&mut memory, 0x5101, 0);
+    assert_eq!(mmc5.ppu_read(&memory, 0x1C00), 0x17 * 8 + 7);
+
+    // Upper bits
+    mmc5.cpu_write(&mut memory, 0x5101, 3);
+    mmc5.cpu_write(&mut memory, 0x5130, 0x02);
+    mmc5.cpu_write(&mut memory, 0x5120, 0x01);
+    assert_eq!(memory.chr[0x201 * 0x400], 0x01);
+    assert_eq!(mmc5.chr_bank(0x0000, false), (0x400, 0x201));
+}
+
+#[test]
+fn mmc5_8x16_sprites_test() {
+    let mut memory = test_memory(0x40000, 0x100000);
+    let mut mmc5 = Mmc5::new();
+
+    mmc5.cpu_write(&mut memory, 0x5101, 3);
+    for register in 0..12 {
+        mmc5.cpu_write(&mut memory, 0x5120 + register, 0x10 + register as u8);
+    }
+    mmc5.ppu_register_write(0x2000, 0x20);
+    mmc5.ppu_register_write(0x2001, 0x18);
+
+    fetch_scanline(&mut mmc5, &memory);
+    let values = fetch_scanline(&mut mmc5, &memory);
+    // Background patterns from $5128, sprite patterns at $1C00 from $5127
+    assert_eq!(values[2], 0x18);
+    assert_eq!(values[128 + 2], 0x17);
+    assert_eq!(values[160 + 2], 0x18);
+}
+
+#[test]
+fn mmc5_irq_test() {
+    let mut memory = test_memory(0x40000, 0x100000);
+    let mut mmc5 = Mmc5::new();
+
+    mmc5.cpu_write(&mut memory, 0x5203, 2);
+    mmc5.cpu_write(&mut memory, 0x5204, 0x80);
+    mmc5.ppu_register_write(0x2001, 0x18);
+
+    // The pre-render scanline, the frame starts on the next one
+    fetch_scanline(&mut mmc5, &memory);
+    assert_eq!(mmc5.cpu_peek(&memory, 0x5204), Some(0x00));
+    fetch_scanline(&mut mmc5, &memory);
+    assert_eq!(mmc5.cpu_peek(&memory, 0x5204), Some(0x40));
+    fetch_scanline(&mut mmc5, &memory);
+    assert!(!mmc5.irq());
+    fetch_scanline(&mut mmc5, &memory);
+    assert!(mmc5.irq());
+
+    // Reading $5204 acknowledges
+    assert_eq!(mmc5.cpu_read(&memory, 0x5204), Some(0xC0));
+    assert!(!mmc5.irq());
+
+    // The frame ends when the PPU stops fetching
+    for _ in 0..IDLE_CYCLES {
+        mmc5.tick();
+    }
+    assert_eq!(mmc5.cpu_peek(&memory, 0x5204), Some(0x00));
+
+    // And when the CPU reads the NMI vector
+    fetch_scanline(&mut mmc5, &memory);
+    fetch_scanline(&mut mmc5, &memory);
+    assert!(mmc5.in_frame);
+    mmc5.cpu_read(&memory, 0xFFFA);
+    assert!(!mmc5.in_frame);
+}
+
+#[test]
+fn mmc5_nametables_test() {
+    let mut memory = test_memory(0x40000, 0x100000);
+    let mut mmc5 = Mmc5::new();
+
+    // Nametable RAM page B, page A, ExRAM, fill mode
+    mmc5.cpu_write(&mut memory, 0x5105, 0b11_10_00_01);
+    assert_eq!(mmc5.mirroring(), Mirroring::Mapped([1, 0, 0, 0]));
+    assert_eq!(mmc5.nametable_read(&memory, 0x2000), None);
+    assert!(!mmc5.nametable_write(&mut memory, 0x2400, 0x42));
+
+    // ExRAM, only writable by the CPU during rendering in modes 0 and 1
+    mmc5.cpu_write(&mut memory, 0x5C01, 0x42);
+    assert_eq!(mmc5.nametable_read(&memory, 0x2801), Some(0x00));
+    assert!(mmc5.nametable_write(&mut memory, 0x2801, 0x42));
+    assert_eq!(mmc5.nametable_read(&memory, 0x2801), Some(0x42));
+    assert_eq!(mmc5.cpu_peek(&memory, 0x5C01), None);
+    mmc5.cpu_write(&mut memory, 0x5104, 2);
+    assert_eq!(mmc5.cpu_peek(&memory, 0x5C01), Some(0x42));
+    assert_eq!(mmc5.nametable_read(&memory, 0x2801), Some(0x00));
+
+    // Fill mode
+    mmc5.cpu_write(&mut memory, 0x5106, 0x24);
+    mmc5.cpu_write(&mut memory, 0x5107, 0x02);
+    assert_eq!(mmc5.nametable_read(&memory, 0x2C10), Some(0x24));
+    assert_eq!(mmc5.nametable_read(&memory, 0x2FC0), Some(0xAA));
+}
+
+#[test]
+fn mmc5_extended_attributes_test() {
+    let mut memory = test_memory(0x40000, 0x100000);
+    let mut mmc5 = Mmc5::new();
+
+    // Tile column 3 uses the 4KB bank 5 with palette 2
+    mmc5.cpu_write(&mut memory, 0x5104, 2);
+    mmc5.cpu_write(&mut memory, 0x5C03, 0x85);
+    mmc5.cpu_write(&mut memory, 0x5104, 1);
+    mmc5.ppu_register_write(0x2001, 0x18);
+
+    fetch_scanline(&mut mmc5, &memory);
+    let values = fetch_scanline(&mut mmc5, &memory);
+    assert_eq!(values[4 + 1], 0xAA);
+    assert_eq!(values[4 + 2], 5 * 4);
+    assert_eq!(values[1], 0x00);
+    assert_eq!(values[2], 0);
+}
+
+#[test]
+fn mmc5_split_test() {
+    let mut memory = test_memory(0x40000, 0x100000);
+    let mut mmc5 = Mmc5::new();
+
+    // Split on the left of column 4, with tiles from ExRAM and the 4KB bank 3
+    mmc5.cpu_write(&mut memory, 0x5104, 2);
+    mmc5.cpu_write(&mut memory, 0x5C22, 0x40);
+    mmc5.cpu_write(&mut memory, 0x5C42, 0x41);
+    mmc5.cpu_write(&mut memory, 0x5FC0, 0b01_00_00_00);
+    mmc5.cpu_write(&mut memory, 0x5104, 0);
+    mmc5.cpu_write(&mut memory, 0x5200, 0x84);
+    mmc5.cpu_write(&mut memory, 0x5201, 14);
+    mmc5.cpu_write(&mut memory, 0x5202, 3);
+    mmc5.ppu_register_write(0x2001, 0x18);
+
+    // Scanline 0 is row 14 of the split, in the second row of tiles
+    fetch_scanline(&mut mmc5, &memory);
+    let values = fetch_scanline(&mut mmc5, &memory);
+    assert_eq!(values[0], 0x40);
+    assert_eq!(values[2], 3 * 4 + 1);
+    assert_eq!(values[8], 0);
+
+    // Scanline 1 fetched its first two tiles at the end of scanline 0, from row 15
+    assert_eq!(values[160 + 1], 0x00);
+    fetch_scanline(&mut mmc5, &memory);
+    let values = fetch_scanline(&mut mmc5, &memory);
+    // Scanline 2 is row 16, in the third row of tiles and the bottom half of the attributes
+    assert_eq!(values[0], 0x41);
+    assert_eq!(values[1], 0x55);
+}
+
+#[test]
+fn mmc5_multiplier_test() {
+    let mut memory = test_memory(0x40000, 0x100000);
+    let mut mmc5 = Mmc5::new();
+
+    mmc5.cpu_write(&mut memory, 0x5205, 200);
+    mmc5.cpu_write(&mut memory, 0x5206, 150);
+    assert_eq!(mmc5.cpu_peek(&memory, 0x5205), Some((30000 & 0xFF) as u8));
+    assert_eq!(mmc5.cpu_peek(&memory, 0x5206), Some((30000 >> 8) as u8));
+}
+
+#[test]
+fn mmc5_audio_test() {
+    let mut memory = test_memory(0x40000, 0x100000);
+    let mut mmc5 = Mmc5::new();
+
+    // A pulse at constant volume 15 and 50% duty, no muting at low periods
+    mmc5.cpu_write(&mut memory, 0x5015, 0x01);
+    mmc5.cpu_write(&mut memory, 0x5000, 0xBF);
+    mmc5.cpu_write(&mut memory, 0x5002, 0x02);
+    mmc5.cpu_write(&mut memory, 0x5003, 0x08);
+    assert_eq!(mmc5.cpu_peek(&memory, 0x5015), Some(0x01));
+    let mut high = 0;
+    for _ in 0..48 {
+        mmc5.tick();
+        if mmc5.audio() > 0.0 {
+            high += 1;
+        }
+    }
+    assert_eq!(high, 24);
+
+    // The length counter runs at 240Hz
+    mmc5.cpu_write(&mut memory, 0x5000, 0x9F);
+    mmc5.cpu_write(&mut memory, 0x5003, 0x18);
+    for _ in 0..FRAME_PERIOD as u32 * 2 {
+        mmc5.tick();
+    }
+    assert_eq!(mmc5.cpu_peek(&memory, 0x5015), Some(0x00));
+
+    // PCM, written or read from $8000-$BFFF, reading $00 raises the IRQ
+    mmc5.cpu_write(&mut memory, 0x5011, 0x80);
+    assert_eq!(mmc5.audio(), 0x80 as f32 * PCM_LEVEL);
+    mmc5.cpu_write(&mut memory, 0x5010, 0x81);
+    mmc5.cpu_write(&mut memory, 0x5114, 0x80);
+    mmc5.cpu_read(&memory, 0x8000);
+    assert!(mmc5.irq());
+    assert_eq!(mmc5.cpu_read(&memory, 0x5010), Some(0x81));
+    assert!(!mmc5.irq());
+    mmc5.cpu_write(&mut memory, 0x5114, 0x83);
+    mmc5.cpu_read(&memory, 0x8000);
+    assert_eq!(mmc5.audio(), 3.0 * PCM_LEVEL);
+}
@@ -0,0 +1,97 @@
+/*!  The mappers, by iNES mapper number */
+
+mod axrom;
+mod bnrom;
+mod camerica;
+mod cnrom;
+mod color_dreams;
+mod fme7;
+mod gxrom;
+mod mmc1;
+mod mmc2;
+mod mmc3;
+mod mmc5;
+mod namco108;
+mod namco163;
+mod nina003;
+mod nrom;
+mod uxrom;
+mod vrc4;
+mod vrc6;
+mod vrc7;
+mod vrc_irq;
+
+pub use self::{
+    axrom::Axrom,
+    bnrom::{Bnrom, Nina001},
+    camerica::Camerica,
+    cnrom::{ChrProtection, Cnrom},
+    color_dreams::ColorDreams,
+    fme7::Fme7,
+    gxrom::Gxrom,
+    mmc1::Mmc1,
+    mmc2::Mmc2,
+    mmc3::{Mmc3, Mmc3Revision},
+    mmc5::Mmc5,
+    namco108::Namco108,
+    namco163::Namco163,
+    nina003::Nina003,
+    nrom::Nrom,
+    uxrom::Uxrom,
+    vrc4::{Vrc4, VrcChip, VrcPins},
+    vrc6::Vrc6,
+    vrc7::Vrc7,
+};
+
+use super::Mapper;
+use crate::{error::EmulatorError, nes_rom::RomFile};
+
+/// Creates the mapper of the cartridge described by the header of `nesfile`
+pub fn new(nesfile: &RomFile) -> Result<Box<dyn Mapper>, EmulatorError> {
+    let mirroring = nesfile.mirroring();
+    let has_bus_conflict = nesfile.has_bus_conflict();
+    let submapper = nesfile.submapper();
+
+    let mapper: Box<dyn Mapper> = match nesfile.mapper_number() {
+        0 => Box::new(Nrom::new(mirroring)),
+        1 => Box::new(Mmc1::new()),
+        2 => Box::new(Uxrom::new(mirroring, has_bus_conflict)),
+        3 => Box::new(Cnrom::new(mirroring, has_bus_conflict, ChrProtection::None)),
+        4 if submapper == 4 => Box::new(Mmc3::new(mirroring, Mmc3Revision::Nec)),
+        4 => Box::new(Mmc3::new(mirroring, Mmc3Revision::Sharp)),
+        5 => Box::new(Mmc5::new()),
+        7 => Box::new(Axrom::new(has_bus_conflict)),
+        9 => Box::new(Mmc2::new(mirroring)),
+        10 => Box::new(Mmc2::mmc4(mirroring)),
+        11 => Box::new(ColorDreams::new(mirroring, has_bus_conflict)),
+        19 => Box::new(Namco163::new()),
+        number @ 21..=23 | number @ 25 => Box::new(Vrc4::new(
+            VrcChip::new(number, submapper),
+            VrcPins::new(number, submapper),
+            mirroring,
+        )),
+        24 => Box::new(Vrc6::new(false)),
+        26 => Box::new(Vrc6::new(true)),
+        34 if submapper == 1 => Box::new(Nina001::new(mirroring)),
+        // Without a submapper, the boards with CHR ROM are NINA-001
+        34 if submapper == 2 || nesfile.chr_rom().is_empty() => Box::new(Bnrom::new(mirroring)),
+        34 => Box::new(Nina001::new(mirroring)),
+        66 => Box::new(Gxrom::new(mirroring)),
+        69 => Box::new(Fme7::new()),
+        71 if submapper == 1 => Box::new(Camerica::fire_hawk()),
+        71 => Box::new(Camerica::new(mirroring)),
+        79 => Box::new(Nina003::new(mirroring)),
+        85 => Box::new(Vrc7::new()),
+        185 => {
+            let protection = match submapper {
+                4..=7 => ChrProtection::EnabledBy(submapper & 0x03),
+                _ => ChrProtection::Heuristic,
+            };
+            Box::new(Cnrom::new(mirroring, true, protection))
+        }
+        206 => Box::new(Namco108::new(mirroring)),
+        number => return Err(EmulatorError::UnsupportedMapper(number)),
+    };
+
+    Ok(mapper)
+}
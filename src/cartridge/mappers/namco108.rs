@@ -0,0 +1,117 @@
+/*!  Namco 108 (mapper 206), DxROM boards, the ancestor of the MMC3
+
+Bank select    $8000 - $9FFE, even    .....RRR: bank register to update
+Bank data    $8001 - $9FFF, odd    New value of the selected bank register
+
+PRG ROM    $8000 - $FFFF, R6 and R7 at $8000 and $A000, then the second to last and last 8KB banks
+CHR ROM    $0000 - $1FFF, R0 and R1 are 2KB banks at $0000, R2-R5 1KB banks at $1000
+
+There are no mirroring, PRG RAM or IRQ registers, and the bank layout is fixed.
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+pub struct Namco108 {
+    mirroring: Mirroring,
+    bank_select: u8,
+    banks: [u8; 8],
+}
+
+impl Namco108 {
+    pub fn new(mirroring: Mirroring) -> Self {
+        Self {
+            mirroring,
+            bank_select: 0,
+            banks: [0, 2, 4, 5, 6, 7, 0, 1],
+        }
+    }
+
+    /// 8KB PRG ROM bank at `addr`
+    fn prg_rom_bank(&self, memory: &Memory, addr: u16) -> usize {
+        match (addr >> 13) & 0x03 {
+            0 => self.banks[6] as usize,
+            1 => self.banks[7] as usize,
+            // Second to last and last banks, the only banks of a 16KB chip
+            slot => (memory.prg_rom_banks(0x2000) + slot as usize).wrapping_sub(4),
+        }
+    }
+
+    /// 1KB CHR bank at `addr`
+    fn chr_bank(&self, addr: u16) -> usize {
+        let slot = (addr >> 10) as usize;
+        match slot {
+            0..=3 => (self.banks[slot / 2] & 0x3E) as usize | (slot & 1),
+            _ => (self.banks[slot - 2] & 0x3F) as usize,
+        }
+    }
+}
+
+impl Mapper for Namco108 {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x8000..=0xFFFF => {
+                Some(memory.read_prg_rom(0x2000, self.prg_rom_bank(memory, addr), addr))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, _memory: &mut Memory, addr: u16, value: u8) {
+        match addr {
+            0x8000..=0x9FFF if addr & 1 == 0 => self.bank_select = value & 0x07,
+            0x8000..=0x9FFF => self.banks[self.bank_select as usize] = value,
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x400, self.chr_bank(addr), addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x400, self.chr_bank(addr), addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[test]
+fn namco108_test() {
+    let mut memory = test_memory(0x20000, 0x10000);
+    let mut namco108 = Namco108::new(Mirroring::Vertical);
+
+    for (register, bank) in [9, 12, 20, 21, 22, 23, 3, 5].iter().enumerate() {
+        namco108.cpu_write(&mut memory, 0x8000, register as u8);
+        namco108.cpu_write(&mut memory, 0x8001, *bank);
+    }
+
+    assert_eq!(namco108.cpu_peek(&memory, 0x8000), Some(3));
+    assert_eq!(namco108.cpu_peek(&memory, 0xA000), Some(5));
+    assert_eq!(namco108.cpu_peek(&memory, 0xC000), Some(14));
+    assert_eq!(namco108.cpu_peek(&memory, 0xE000), Some(15));
+
+    // 2KB banks ignore the low bit
+    assert_eq!(namco108.ppu_read(&memory, 0x0000), 8);
+    assert_eq!(namco108.ppu_read(&memory, 0x0400), 9);
+    assert_eq!(namco108.ppu_read(&memory, 0x0800), 12);
+    assert_eq!(namco108.ppu_read(&memory, 0x1000), 20);
+    assert_eq!(namco108.ppu_read(&memory, 0x1C00), 23);
+
+    // No PRG mode or CHR inversion bits
+    namco108.cpu_write(&mut memory, 0x8000, 0xC6);
+    assert_eq!(namco108.cpu_peek(&memory, 0x8000), Some(3));
+    assert_eq!(namco108.ppu_read(&memory, 0x0000), 8);
+}
+
+#[test]
+fn namco108_small_prg_rom_test() {
+    let memory = test_memory(0x4000, 0x2000);
+    let namco108 = Namco108::new(Mirroring::Vertical);
+
+    assert_eq!(namco108.cpu_peek(&memory, 0xC000), Some(0));
+    assert_eq!(namco108.cpu_peek(&memory, 0xE000), Some(1));
+}
@@ -0,0 +1,383 @@
+/*!  Namco 163 (mapper 19)
+
+Sound data    $4800 - $4FFF    Internal RAM at the sound address
+IRQ counter low    $5000 - $57FF    Also readable
+IRQ counter high    $5800 - $5FFF    ECCCCCCC: IRQ enable, counter high bits. Also readable
+CHR banks    $8000 - $BFFF    1KB banks, a register every $800
+Nametable banks    $C000 - $DFFF    1KB banks, a register every $800. $E0-$FF select the nametable RAM
+PRG bank 0    $E000 - $E7FF    .SBBBBBB: sound disable, 8KB bank at $8000
+PRG bank 1    $E800 - $EFFF    HLBBBBBB: nametable RAM disable for the CHR banks, 8KB bank at $A000
+PRG bank 2    $F000 - $F7FF    ..BBBBBB: 8KB bank at $C000
+Write protect    $F800 - $FFFF    IAAAAAAA: auto increment and sound address.
+    Also 0100PPPP: PRG RAM writes enable, protect for each 2KB
+
+PRG RAM    $6000 - $7FFF
+PRG ROM    $8000 - $FFFF, $E000 is the last 8KB bank
+CHR ROM    $0000 - $1FFF, the nametables can also be CHR ROM banks
+
+Writing either IRQ counter register acknowledges the IRQ. The counter counts up every CPU
+cycle and raises the IRQ when it reaches $7FFF, then stops.
+
+The 128 bytes of internal RAM hold the wavetables and the channel registers, from the last
+channel at $78-$7F down to the first one at $40-$47:
+
+Frequency    +0, +2, +4    18 bits, in the low byte, middle byte and bits 0-1 of +4
+Phase    +1, +3, +5    24 bits, the high byte is the position in the wave
+Length    +4    LLLLLL..: wave length is 256 - %LLLLLL00 samples
+Wave address    +6    In samples of 4 bits, the low nibble of a byte first
+Volume    +7    ....VVVV. In $7F .CCC....: number of enabled channels - 1
+
+A channel is updated every 15 CPU cycles, the enabled channels take turns on the output.
+Games with a battery keep their saves in the internal RAM too.
+
+NOTE: CHR banks selecting the nametable RAM read CHR ROM instead, no game relies on them.
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+/// CPU cycles spent updating a channel
+const CHANNEL_CYCLES: u8 = 15;
+/// Output level of a step of a channel, a sample times its volume
+const OUTPUT_LEVEL: f32 = 0.0015;
+/// Value the IRQ counter stops at
+const IRQ_COUNTER_MAX: u16 = 0x7FFF;
+
+pub struct Namco163 {
+    chr_banks: [u8; 8],
+    nametable_banks: [u8; 4],
+    prg_banks: [u8; 3],
+    write_protect: u8,
+    irq_counter: u16,
+    irq_enabled: bool,
+    irq: bool,
+    ram: Vec<u8>,
+    sound_addr: u8,
+    sound_cycles: u8,
+    channel: u8,
+    output: i16,
+}
+
+impl Default for Namco163 {
+    fn default() -> Self {
+        Self {
+            chr_banks: [0; 8],
+            nametable_banks: [0xE0; 4],
+            prg_banks: [0; 3],
+            write_protect: 0,
+            irq_counter: 0,
+            irq_enabled: false,
+            irq: false,
+            ram: vec![0; 0x80],
+            sound_addr: 0,
+            sound_cycles: 0,
+            channel: 7,
+            output: 0,
+        }
+    }
+}
+
+impl Namco163 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 8KB PRG ROM bank at `addr`
+    fn prg_rom_bank(&self, memory: &Memory, addr: u16) -> usize {
+        match (addr >> 13) & 0x03 {
+            3 => memory.prg_rom_banks(0x2000) - 1,
+            slot => (self.prg_banks[slot as usize] & 0x3F) as usize,
+        }
+    }
+
+    fn is_prg_ram_writable(&self, addr: u16) -> bool {
+        let window = (addr - 0x6000) >> 11;
+        self.write_protect & 0xF0 == 0x40 && self.write_protect & (1 << window) == 0
+    }
+
+    fn is_sound_enabled(&self) -> bool {
+        self.prg_banks[0] & 0x40 == 0
+    }
+
+    fn enabled_channels(&self) -> u8 {
+        ((self.ram[0x7F] >> 4) & 0x07) + 1
+    }
+
+    /// The sound address moves to the next byte after accesses with auto increment
+    fn increment_sound_addr(&mut self) {
+        if self.sound_addr & 0x80 != 0 {
+            self.sound_addr = 0x80 | (self.sound_addr.wrapping_add(1) & 0x7F);
+        }
+    }
+
+    /// Moves the phase of `channel` and outputs its sample
+    fn update_channel(&mut self, channel: u8) {
+        let base = 0x40 + channel as usize * 8;
+        let registers = &mut self.ram[base..base + 8];
+        let frequency =
+            registers[0] as u32 | (registers[2] as u32) << 8 | (registers[4] as u32 & 0x03) << 16;
+        let phase = registers[1] as u32 | (registers[3] as u32) << 8 | (registers[5] as u32) << 16;
+        let length = 256 - (registers[4] as u32 & 0xFC);
+
+        let phase = (phase + frequency) % (length << 16);
+        registers[1] = phase as u8;
+        registers[3] = (phase >> 8) as u8;
+        registers[5] = (phase >> 16) as u8;
+
+        let volume = (registers[7] & 0x0F) as i16;
+        let position = ((phase >> 16) as u8).wrapping_add(registers[6]);
+        let sample = (self.ram[position as usize / 2] >> ((position & 1) * 4)) & 0x0F;
+        self.output = (sample as i16 - 8) * volume;
+    }
+}
+
+impl Mapper for Namco163 {
+    fn cpu_read(&mut self, memory: &Memory, addr: u16) -> Option<u8> {
+        let value = self.cpu_peek(memory, addr);
+        if let 0x4800..=0x4FFF = addr {
+            self.increment_sound_addr();
+        }
+        value
+    }
+
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x4800..=0x4FFF => Some(self.ram[(self.sound_addr & 0x7F) as usize]),
+            0x5000..=0x57FF => Some(self.irq_counter as u8),
+            0x5800..=0x5FFF => Some((self.irq_enabled as u8) << 7 | (self.irq_counter >> 8) as u8),
+            0x6000..=0x7FFF => memory.read_prg_ram(addr),
+            0x8000..=0xFFFF => {
+                Some(memory.read_prg_rom(0x2000, self.prg_rom_bank(memory, addr), addr))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        match addr {
+            0x4800..=0x4FFF => {
+                self.ram[(self.sound_addr & 0x7F) as usize] = value;
+                self.increment_sound_addr();
+            }
+            0x5000..=0x57FF => {
+                self.irq_counter = (self.irq_counter & 0x7F00) | value as u16;
+                self.irq = false;
+            }
+            0x5800..=0x5FFF => {
+                self.irq_counter = (self.irq_counter & 0x00FF) | (value as u16 & 0x7F) << 8;
+                self.irq_enabled = value & 0x80 != 0;
+                self.irq = false;
+            }
+            0x6000..=0x7FFF if self.is_prg_ram_writable(addr) => {
+                memory.write_prg_ram(addr, value);
+            }
+            0x8000..=0xBFFF => self.chr_banks[(addr as usize - 0x8000) >> 11] = value,
+            0xC000..=0xDFFF => self.nametable_banks[(addr as usize - 0xC000) >> 11] = value,
+            0xE000..=0xF7FF => self.prg_banks[(addr as usize - 0xE000) >> 11] = value,
+            0xF800..=0xFFFF => {
+                self.write_protect = value;
+                self.sound_addr = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x400, self.chr_banks[addr as usize >> 10] as usize, addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(
+            0x400,
+            self.chr_banks[addr as usize >> 10] as usize,
+            addr,
+            value,
+        );
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        let page = |nametable: usize| self.nametable_banks[nametable] & 0x01;
+        Mirroring::Mapped([page(0), page(1), page(2), page(3)])
+    }
+
+    fn nametable_read(&mut self, memory: &Memory, addr: u16) -> Option<u8> {
+        match self.nametable_banks[(addr as usize >> 10) & 0x03] {
+            0xE0..=0xFF => None,
+            bank => Some(memory.read_chr(0x400, bank as usize, addr)),
+        }
+    }
+
+    fn nametable_write(&mut self, memory: &mut Memory, addr: u16, value: u8) -> bool {
+        match self.nametable_banks[(addr as usize >> 10) & 0x03] {
+            0xE0..=0xFF => false,
+            bank => {
+                memory.write_chr(0x400, bank as usize, addr, value);
+                true
+            }
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq
+    }
+
+    fn tick(&mut self) {
+        if self.irq_enabled && self.irq_counter < IRQ_COUNTER_MAX {
+            self.irq_counter += 1;
+            if self.irq_counter == IRQ_COUNTER_MAX {
+                self.irq = true;
+            }
+        }
+
+        self.sound_cycles += 1;
+        if self.sound_cycles == CHANNEL_CYCLES {
+            self.sound_cycles = 0;
+            // The enabled channels are the last ones, updated from the last one down
+            let first = 8 - self.enabled_channels();
+            self.channel = if self.channel <= first {
+                7
+            } else {
+                self.channel - 1
+            };
+            self.update_channel(self.channel);
+        }
+    }
+
+    fn audio(&self) -> f32 {
+        if self.is_sound_enabled() {
+            self.output as f32 * OUTPUT_LEVEL
+        } else {
+            0.0
+        }
+    }
+
+    fn internal_ram(&mut self) -> Option<&mut [u8]> {
+        Some(&mut self.ram)
+    }
+}
+
+#[test]
+fn namco163_banks_test() {
+    let mut memory = test_memory(0x20000, 0x40000);
+    let mut namco163 = Namco163::new();
+
+    namco163.cpu_write(&mut memory, 0xE000, 3);
+    namco163.cpu_write(&mut memory, 0xE800, 4);
+    namco163.cpu_write(&mut memory, 0xF000, 5);
+    assert_eq!(namco163.cpu_peek(&memory, 0x8000), Some(3));
+    assert_eq!(namco163.cpu_peek(&memory, 0xA000), Some(4));
+    assert_eq!(namco163.cpu_peek(&memory, 0xC000), Some(5));
+    assert_eq!(namco163.cpu_peek(&memory, 0xE000), Some(15));
+
+    for bank in 0..8 {
+        namco163.cpu_write(&mut memory, 0x8000 + bank * 0x800, 0x30 + bank as u8);
+    }
+    assert_eq!(namco163.ppu_read(&memory, 0x0000), 0x30);
+    assert_eq!(namco163.ppu_read(&memory, 0x1C00), 0x37);
+
+    // PRG RAM writes need $4x in $F800, and the 2KB window not protected
+    namco163.cpu_write(&mut memory, 0x6000, 0x42);
+    assert_eq!(namco163.cpu_peek(&memory, 0x6000), Some(0x00));
+    namco163.cpu_write(&mut memory, 0xF800, 0x41);
+    namco163.cpu_write(&mut memory, 0x6000, 0x42);
+    namco163.cpu_write(&mut memory, 0x6800, 0x43);
+    assert_eq!(namco163.cpu_peek(&memory, 0x6000), Some(0x00));
+    assert_eq!(namco163.cpu_peek(&memory, 0x6800), Some(0x43));
+}
+
+#[test]
+fn namco163_nametables_test() {
+    let mut memory = test_memory(0x20000, 0x40000);
+    let mut namco163 = Namco163::new();
+
+    // Nametable RAM pages B and A, then CHR ROM banks
+    namco163.cpu_write(&mut memory, 0xC000, 0xE1);
+    namco163.cpu_write(&mut memory, 0xC800, 0xE0);
+    namco163.cpu_write(&mut memory, 0xD000, 0x12);
+    namco163.cpu_write(&mut memory, 0xD800, 0x13);
+    assert_eq!(namco163.mirroring(), Mirroring::Mapped([1, 0, 0, 1]));
+    assert_eq!(namco163.nametable_read(&memory, 0x2000), None);
+    assert_eq!(namco163.nametable_read(&memory, 0x2410), None);
+    assert_eq!(namco163.nametable_read(&memory, 0x2810), Some(0x12));
+    assert_eq!(namco163.nametable_read(&memory, 0x2C10), Some(0x13));
+
+    // CHR ROM can't be written
+    assert!(namco163.nametable_write(&mut memory, 0x2C10, 0x42));
+    assert_eq!(namco163.nametable_read(&memory, 0x2C10), Some(0x13));
+    assert!(!namco163.nametable_write(&mut memory, 0x2010, 0x42));
+}
+
+#[test]
+fn namco163_irq_test() {
+    let mut memory = test_memory(0x20000, 0x40000);
+    let mut namco163 = Namco163::new();
+
+    namco163.cpu_write(&mut memory, 0x5000, 0xFD);
+    namco163.cpu_write(&mut memory, 0x5800, 0xFF);
+    namco163.tick();
+    assert!(!namco163.irq());
+    namco163.tick();
+    assert!(namco163.irq());
+    assert_eq!(namco163.cpu_peek(&memory, 0x5000), Some(0xFF));
+    assert_eq!(namco163.cpu_peek(&memory, 0x5800), Some(0xFF));
+
+    // The counter stops at $7FFF
+    namco163.tick();
+    assert_eq!(namco163.cpu_peek(&memory, 0x5000), Some(0xFF));
+
+    // Writing the counter acknowledges
+    namco163.cpu_write(&mut memory, 0x5000, 0x00);
+    assert!(!namco163.irq());
+}
+
+#[test]
+fn namco163_audio_test() {
+    let mut memory = test_memory(0x20000, 0x40000);
+    let mut namco163 = Namco163::new();
+
+    // Internal RAM, with auto increment
+    namco163.cpu_write(&mut memory, 0xF800, 0x80);
+    for value in [0xF0, 0x5A].iter() {
+        namco163.cpu_write(&mut memory, 0x4800, *value);
+    }
+    namco163.cpu_write(&mut memory, 0xF800, 0x81);
+    assert_eq!(namco163.cpu_read(&memory, 0x4800), Some(0x5A));
+    assert_eq!(namco163.cpu_peek(&memory, 0x4800), Some(0x00));
+    namco163.cpu_write(&mut memory, 0xF800, 0x01);
+    namco163.cpu_read(&memory, 0x4800);
+    assert_eq!(namco163.cpu_peek(&memory, 0x4800), Some(0x5A));
+    assert_eq!(namco163.internal_ram().unwrap()[1], 0x5A);
+
+    // Channel 8 alone, a wave of 4 samples 0, F, A, 5 at full volume, one sample per update
+    let registers = [0x00, 0x00, 0x00, 0x00, 0x01 | 0xFC, 0x00, 0x00, 0x0F];
+    namco163.cpu_write(&mut memory, 0xF800, 0xF8);
+    for value in registers.iter() {
+        namco163.cpu_write(&mut memory, 0x4800, *value);
+    }
+
+    let mut outputs = vec![];
+    for _ in 0..4 {
+        for _ in 0..CHANNEL_CYCLES {
+            namco163.tick();
+        }
+        outputs.push((namco163.audio() / OUTPUT_LEVEL).round() as i16);
+    }
+    assert_eq!(outputs, [7 * 15, 2 * 15, -3 * 15, -8 * 15]);
+
+    // Two channels take turns
+    namco163.cpu_write(&mut memory, 0xF800, 0xFF);
+    namco163.cpu_write(&mut memory, 0x4800, 0x1F);
+    for _ in 0..CHANNEL_CYCLES {
+        namco163.tick();
+    }
+    assert_eq!(namco163.channel, 6);
+    for _ in 0..CHANNEL_CYCLES {
+        namco163.tick();
+    }
+    assert_eq!(namco163.channel, 7);
+
+    // Sound disable
+    namco163.cpu_write(&mut memory, 0xE000, 0x40);
+    assert_eq!(namco163.audio(), 0.0);
+}
@@ -0,0 +1,72 @@
+/*!  NINA-003 and NINA-006 (mapper 79)
+
+Bank select    $4100 - $5FFF, A8 set and A13-A15 like $4100    ....PCCC: 32KB PRG ROM bank, 8KB CHR ROM bank
+
+PRG ROM, switchable 32KB    $8000 - $FFFF
+CHR ROM, switchable 8KB    $0000 - $1FFF
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+pub struct Nina003 {
+    mirroring: Mirroring,
+    bank: u8,
+}
+
+impl Nina003 {
+    pub fn new(mirroring: Mirroring) -> Self {
+        Self { mirroring, bank: 0 }
+    }
+
+    fn chr_bank(&self) -> usize {
+        (self.bank & 0x07) as usize
+    }
+}
+
+impl Mapper for Nina003 {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x8000..=0xFFFF => {
+                Some(memory.read_prg_rom(0x8000, (self.bank >> 3) as usize & 1, addr))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, _memory: &mut Memory, addr: u16, value: u8) {
+        if addr & 0xE100 == 0x4100 {
+            self.bank = value;
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x2000, self.chr_bank(), addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x2000, self.chr_bank(), addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[test]
+fn nina003_test() {
+    let mut memory = test_memory(0x10000, 0x10000);
+    let mut nina003 = Nina003::new(Mirroring::Horizontal);
+
+    nina003.cpu_write(&mut memory, 0x4100, 0x0D);
+    assert_eq!(nina003.cpu_peek(&memory, 0x8000), Some(4));
+    assert_eq!(nina003.ppu_read(&memory, 0x0000), 40);
+
+    // A8 must be set
+    nina003.cpu_write(&mut memory, 0x4200, 0x02);
+    assert_eq!(nina003.ppu_read(&memory, 0x0000), 40);
+    nina003.cpu_write(&mut memory, 0x5F00, 0x02);
+    assert_eq!(nina003.cpu_peek(&memory, 0x8000), Some(0));
+    assert_eq!(nina003.ppu_read(&memory, 0x0000), 16);
+}
@@ -0,0 +1,80 @@
+/*!  NROM (mapper 0), no bank switching
+
+PRG RAM    $6000 - $7FFF
+PRG ROM, first 16KB    $8000 - $BFFF
+PRG ROM, last 16KB    $C000 - $FFFF (same as the first 16KB for NROM-128)
+CHR    $0000 - $1FFF
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+pub struct Nrom {
+    mirroring: Mirroring, // Soldered on the board
+}
+
+impl Nrom {
+    pub fn new(mirroring: Mirroring) -> Self {
+        Self { mirroring }
+    }
+}
+
+impl Mapper for Nrom {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF => memory.read_prg_ram(addr),
+            0x8000..=0xBFFF => Some(memory.read_prg_rom(0x4000, 0, addr)),
+            0xC000..=0xFFFF => {
+                let last = memory.prg_rom_banks(0x4000) - 1;
+                Some(memory.read_prg_rom(0x4000, last, addr))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        if let 0x6000..=0x7FFF = addr {
+            memory.write_prg_ram(addr, value);
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x2000, 0, addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x2000, 0, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[test]
+fn nrom_test() {
+    let mut memory = Memory {
+        chr_is_ram: true,
+        ..test_memory(0x4000, 0x2000)
+    };
+    let mut nrom = Nrom::new(Mirroring::Vertical);
+
+    // NROM-128 mirrors its 16KB at $C000
+    assert_eq!(nrom.cpu_peek(&memory, 0x8123), Some(0));
+    assert_eq!(nrom.cpu_peek(&memory, 0xA123), Some(1));
+    assert_eq!(nrom.cpu_peek(&memory, 0xC123), Some(0));
+    assert_eq!(nrom.cpu_peek(&memory, 0xFFFF), Some(1));
+
+    // ROM can't be written, RAM can
+    nrom.cpu_write(&mut memory, 0xA123, 0xAA);
+    assert_eq!(nrom.cpu_peek(&memory, 0xA123), Some(1));
+    nrom.cpu_write(&mut memory, 0x6001, 0xAA);
+    assert_eq!(nrom.cpu_peek(&memory, 0x6001), Some(0xAA));
+
+    // Nothing answers below $6000
+    assert_eq!(nrom.cpu_peek(&memory, 0x5000), None);
+
+    nrom.ppu_write(&mut memory, 0x1234, 0x42);
+    assert_eq!(nrom.ppu_read(&memory, 0x1234), 0x42);
+}
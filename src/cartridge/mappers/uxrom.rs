@@ -0,0 +1,110 @@
+/*!  UxROM (mapper 2), UNROM and UOROM boards
+
+Bank select    $8000 - $FFFF    16KB PRG ROM bank at $8000
+
+PRG ROM, switchable 16KB    $8000 - $BFFF
+PRG ROM, last 16KB    $C000 - $FFFF
+CHR RAM    $0000 - $1FFF
+
+With bus conflicts the ROM drives the data bus during the write, the register gets the value
+written ANDed with the ROM byte at that address.
+*/
+
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+pub struct Uxrom {
+    mirroring: Mirroring,
+    bus_conflicts: bool,
+    bank: u8,
+}
+
+impl Uxrom {
+    pub fn new(mirroring: Mirroring, bus_conflicts: bool) -> Self {
+        Self {
+            mirroring,
+            bus_conflicts,
+            bank: 0,
+        }
+    }
+
+    fn prg_rom_bank(&self, memory: &Memory, addr: u16) -> usize {
+        if addr < 0xC000 {
+            self.bank as usize
+        } else {
+            memory.prg_rom_banks(0x4000) - 1
+        }
+    }
+}
+
+impl Mapper for Uxrom {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x8000..=0xFFFF => {
+                Some(memory.read_prg_rom(0x4000, self.prg_rom_bank(memory, addr), addr))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        if addr >= 0x8000 {
+            self.bank = if self.bus_conflicts {
+                value & memory.read_prg_rom(0x4000, self.prg_rom_bank(memory, addr), addr)
+            } else {
+                value
+            };
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x2000, 0, addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x2000, 0, addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[test]
+fn uxrom_test() {
+    let mut memory = Memory {
+        chr_is_ram: true,
+        ..test_memory(0x20000, 0x2000)
+    };
+    let mut uxrom = Uxrom::new(Mirroring::Vertical, false);
+
+    assert_eq!(uxrom.cpu_peek(&memory, 0x8000), Some(0));
+    assert_eq!(uxrom.cpu_peek(&memory, 0xC000), Some(14));
+
+    uxrom.cpu_write(&mut memory, 0x8000, 5);
+    assert_eq!(uxrom.cpu_peek(&memory, 0xBFFF), Some(11));
+    assert_eq!(uxrom.cpu_peek(&memory, 0xFFFF), Some(15));
+
+    // No PRG RAM on the board
+    assert_eq!(uxrom.cpu_peek(&memory, 0x6000), None);
+
+    uxrom.ppu_write(&mut memory, 0x1FFF, 0x42);
+    assert_eq!(uxrom.ppu_read(&memory, 0x1FFF), 0x42);
+}
+
+#[test]
+fn uxrom_bus_conflicts_test() {
+    let mut memory = Memory {
+        chr_is_ram: true,
+        ..test_memory(0x20000, 0x2000)
+    };
+    let mut uxrom = Uxrom::new(Mirroring::Vertical, true);
+
+    // $C000 holds 14, 6 & 14 goes through
+    uxrom.cpu_write(&mut memory, 0xC000, 6);
+    assert_eq!(uxrom.cpu_peek(&memory, 0x8000), Some(12));
+    // Bank 6 starts with 12, 7 & 12 = 4
+    uxrom.cpu_write(&mut memory, 0x8000, 7);
+    assert_eq!(uxrom.cpu_peek(&memory, 0x8000), Some(8));
+}
@@ -0,0 +1,316 @@
+/*!  Konami VRC2 and VRC4 (mappers 21, 22, 23 and 25)
+
+The boards connect different CPU address lines to the A0/A1 pins of the chip, the registers
+below use the pins:
+
+PRG bank 0    $8000 - $8003    8KB bank at $8000, or at $C000 in swapped mode
+Mirroring    $9000    0: vertical, 1: horizontal, 2: single-screen A, 3: single-screen B (VRC2 only has bit 0)
+PRG mode    $9002    ......M.: swapped mode, VRC4 only
+PRG bank 1    $A000 - $A003    8KB bank at $A000
+CHR banks    $B000 - $E003    low then high bits of the eight 1KB banks, two registers per bank
+IRQ latch    $F000, $F001    low and high nibble of the value reloaded in the counter, VRC4 only
+IRQ control    $F002    .....MEA: cycle mode, enable, enable after acknowledge
+IRQ acknowledge    $F003    Copies A to E
+
+PRG RAM    $6000 - $7FFF
+PRG ROM    $8000 - $FFFF, $C000 (or $8000 when swapped) is the second to last bank, $E000 the last one
+CHR ROM    $0000 - $1FFF, eight 1KB banks
+
+The IRQ counter is the one of all the VRC chips, see `vrc_irq`.
+*/
+
+use super::vrc_irq::VrcIrq;
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+/// CPU address lines wired to the A0 and A1 pins
+///
+/// Submapper 0 is the iNES 1 case, both candidate lines are used at once like most emulators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VrcPins {
+    pub a0: u16,
+    pub a1: u16,
+}
+
+impl VrcPins {
+    pub fn new(mapper: u16, submapper: u8) -> Self {
+        let (a0, a1) = match (mapper, submapper) {
+            (21, 1) => (0x02, 0x04), // VRC4a
+            (21, 2) => (0x40, 0x80), // VRC4c
+            (21, _) => (0x42, 0x84),
+            (22, _) => (0x02, 0x01),           // VRC2a
+            (23, 1) | (23, 3) => (0x01, 0x02), // VRC4f, VRC2b
+            (23, 2) => (0x04, 0x08),           // VRC4e
+            (23, _) => (0x05, 0x0A),
+            (25, 1) | (25, 3) => (0x02, 0x01), // VRC4b, VRC2c
+            (25, 2) => (0x08, 0x04),           // VRC4d
+            _ => (0x0A, 0x05),
+        };
+
+        Self { a0, a1 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VrcChip {
+    Vrc2,
+    /// VRC2a, only wired to the high 7 bits of the CHR banks
+    Vrc2a,
+    Vrc4,
+}
+
+impl VrcChip {
+    pub fn new(mapper: u16, submapper: u8) -> Self {
+        match (mapper, submapper) {
+            (22, _) => VrcChip::Vrc2a,
+            (23, 3) | (25, 3) => VrcChip::Vrc2,
+            _ => VrcChip::Vrc4,
+        }
+    }
+}
+
+pub struct Vrc4 {
+    chip: VrcChip,
+    pins: VrcPins,
+    mirroring: Mirroring,
+    prg_banks: [u8; 2],
+    prg_swapped: bool,
+    chr_banks: [u16; 8],
+    irq: VrcIrq,
+}
+
+impl Vrc4 {
+    pub fn new(chip: VrcChip, pins: VrcPins, mirroring: Mirroring) -> Self {
+        Self {
+            chip,
+            pins,
+            mirroring,
+            prg_banks: [0, 1],
+            prg_swapped: false,
+            chr_banks: [0; 8],
+            irq: VrcIrq::default(),
+        }
+    }
+
+    /// The register at `addr`, with the pins as the low 2 bits
+    fn register(&self, addr: u16) -> u16 {
+        let a0 = (addr & self.pins.a0 != 0) as u16;
+        let a1 = (addr & self.pins.a1 != 0) as u16;
+        (addr & 0xF000) | a1 << 1 | a0
+    }
+
+    /// 8KB PRG ROM bank at `addr`
+    fn prg_rom_bank(&self, memory: &Memory, addr: u16) -> usize {
+        // Wraps around with a single bank, `Memory` then wraps the bank number
+        let second_last = memory.prg_rom_banks(0x2000).wrapping_sub(2);
+        match ((addr >> 13) & 0x03, self.prg_swapped) {
+            (0, false) | (2, true) => self.prg_banks[0] as usize,
+            (0, true) | (2, false) => second_last,
+            (1, _) => self.prg_banks[1] as usize,
+            _ => second_last.wrapping_add(1),
+        }
+    }
+
+    fn chr_bank(&self, addr: u16) -> usize {
+        let bank = self.chr_banks[(addr >> 10) as usize & 0x07] as usize;
+        if self.chip == VrcChip::Vrc2a {
+            bank >> 1
+        } else {
+            bank
+        }
+    }
+
+    fn write_chr_bank(&mut self, register: u16, value: u8) {
+        let bank = (((register - 0xB000) >> 12) * 2 + ((register >> 1) & 1)) as usize;
+        self.chr_banks[bank] = if register & 1 == 0 {
+            (self.chr_banks[bank] & 0x1F0) | (value as u16 & 0x0F)
+        } else {
+            (self.chr_banks[bank] & 0x00F) | ((value as u16 & 0x1F) << 4)
+        };
+    }
+}
+
+impl Mapper for Vrc4 {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            // VRC2 boards without RAM have a 1-bit latch there, RAM behaves the same
+            0x6000..=0x7FFF => memory.read_prg_ram(addr),
+            0x8000..=0xFFFF => {
+                Some(memory.read_prg_rom(0x2000, self.prg_rom_bank(memory, addr), addr))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        if let 0x6000..=0x7FFF = addr {
+            memory.write_prg_ram(addr, value);
+            return;
+        }
+
+        let vrc4 = self.chip == VrcChip::Vrc4;
+        match self.register(addr) {
+            0x8000..=0x8003 => self.prg_banks[0] = value & 0x1F,
+            0x9000..=0x9003 if !vrc4 => {
+                self.mirroring = if value & 1 == 0 {
+                    Mirroring::Vertical
+                } else {
+                    Mirroring::Horizontal
+                };
+            }
+            0x9000 | 0x9001 => {
+                self.mirroring = match value & 0x03 {
+                    0 => Mirroring::Vertical,
+                    1 => Mirroring::Horizontal,
+                    2 => Mirroring::SingleScreenA,
+                    _ => Mirroring::SingleScreenB,
+                };
+            }
+            0x9002 => self.prg_swapped = value & 0x02 != 0,
+            0xA000..=0xA003 => self.prg_banks[1] = value & 0x1F,
+            register @ 0xB000..=0xE003 => self.write_chr_bank(register, value),
+            0xF000 if vrc4 => {
+                let latch = (self.irq.latch() & 0xF0) | (value & 0x0F);
+                self.irq.write_latch(latch);
+            }
+            0xF001 if vrc4 => {
+                let latch = (self.irq.latch() & 0x0F) | (value << 4);
+                self.irq.write_latch(latch);
+            }
+            0xF002 if vrc4 => self.irq.write_control(value),
+            0xF003 if vrc4 => self.irq.acknowledge(),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x400, self.chr_bank(addr), addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x400, self.chr_bank(addr), addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn irq(&self) -> bool {
+        self.irq.irq()
+    }
+
+    fn tick(&mut self) {
+        self.irq.tick();
+    }
+}
+
+#[test]
+fn vrc4_banks_test() {
+    let mut memory = test_memory(0x40000, 0x40000);
+    // VRC4e, A2 and A3
+    let mut vrc4 = Vrc4::new(VrcChip::Vrc4, VrcPins::new(23, 2), Mirroring::Vertical);
+
+    vrc4.cpu_write(&mut memory, 0x8000, 3);
+    vrc4.cpu_write(&mut memory, 0xA000, 5);
+    assert_eq!(vrc4.cpu_peek(&memory, 0x8000), Some(3));
+    assert_eq!(vrc4.cpu_peek(&memory, 0xA000), Some(5));
+    assert_eq!(vrc4.cpu_peek(&memory, 0xC000), Some(30));
+    assert_eq!(vrc4.cpu_peek(&memory, 0xE000), Some(31));
+
+    vrc4.cpu_write(&mut memory, 0x9008, 0x02);
+    assert_eq!(vrc4.cpu_peek(&memory, 0x8000), Some(30));
+    assert_eq!(vrc4.cpu_peek(&memory, 0xC000), Some(3));
+
+    vrc4.cpu_write(&mut memory, 0x9000, 0x03);
+    assert_eq!(vrc4.mirroring(), Mirroring::SingleScreenB);
+
+    // Bank 3 is $C008/$C00C, low then high bits
+    vrc4.cpu_write(&mut memory, 0xC008, 0x05);
+    vrc4.cpu_write(&mut memory, 0xC00C, 0x11);
+    // $115 wraps around the 256 banks
+    assert_eq!(vrc4.ppu_read(&memory, 0x0C00), 0x15);
+    assert_eq!(vrc4.ppu_read(&memory, 0x0800), 0);
+}
+
+#[test]
+fn vrc4_small_prg_rom_test() {
+    // 8KB of PRG ROM, the fixed banks wrap around to its only bank
+    let mut memory = test_memory(0x2000, 0x2000);
+    memory.prg_rom[0x1FFF] = 0x42;
+    let vrc4 = Vrc4::new(VrcChip::Vrc4, VrcPins::new(25, 1), Mirroring::Vertical);
+
+    assert_eq!(vrc4.cpu_peek(&memory, 0xDFFF), Some(0x42));
+    assert_eq!(vrc4.cpu_peek(&memory, 0xFFFF), Some(0x42));
+}
+
+#[test]
+fn vrc4_pins_test() {
+    let mut memory = test_memory(0x40000, 0x40000);
+
+    // VRC4a and VRC4c share mapper 21, iNES files use both lines
+    let mut vrc4 = Vrc4::new(VrcChip::Vrc4, VrcPins::new(21, 0), Mirroring::Vertical);
+    vrc4.cpu_write(&mut memory, 0xB004, 0x07);
+    vrc4.cpu_write(&mut memory, 0xB040, 0x01);
+    assert_eq!(vrc4.ppu_read(&memory, 0x0400), 7);
+    assert_eq!(vrc4.ppu_read(&memory, 0x0000), 16);
+
+    // A6 isn't wired on VRC4a
+    let mut vrc4 = Vrc4::new(VrcChip::Vrc4, VrcPins::new(21, 1), Mirroring::Vertical);
+    vrc4.cpu_write(&mut memory, 0xB040, 0x01);
+    assert_eq!(vrc4.ppu_read(&memory, 0x0000), 1);
+
+    // VRC2a has A0 and A1 swapped and ignores the low bit of the CHR banks
+    let mut vrc2 = Vrc4::new(VrcChip::Vrc2a, VrcPins::new(22, 0), Mirroring::Vertical);
+    vrc2.cpu_write(&mut memory, 0xB001, 0x07);
+    assert_eq!(vrc2.ppu_read(&memory, 0x0400), 3);
+
+    // VRC2 mirroring is a single bit, and there's no PRG mode
+    vrc2.cpu_write(&mut memory, 0x9000, 0x03);
+    assert_eq!(vrc2.mirroring(), Mirroring::Horizontal);
+    vrc2.cpu_write(&mut memory, 0x9002, 0x02);
+    assert_eq!(vrc2.cpu_peek(&memory, 0x8000), Some(0));
+}
+
+#[test]
+fn vrc4_irq_test() {
+    let mut memory = test_memory(0x40000, 0x40000);
+    let mut vrc4 = Vrc4::new(VrcChip::Vrc4, VrcPins::new(25, 1), Mirroring::Vertical);
+
+    // VRC4b has A0 and A1 swapped: latch low, latch high, control
+    vrc4.cpu_write(&mut memory, 0xF000, 0x0E);
+    vrc4.cpu_write(&mut memory, 0xF002, 0x0F);
+    vrc4.cpu_write(&mut memory, 0xF001, 0x07);
+
+    // Cycle mode, from $FE: $FF then reload and IRQ
+    vrc4.tick();
+    assert!(!vrc4.irq());
+    vrc4.tick();
+    assert!(vrc4.irq());
+
+    // Acknowledge, A keeps it enabled
+    vrc4.cpu_write(&mut memory, 0xF003, 0);
+    assert!(!vrc4.irq());
+    vrc4.tick();
+    assert!(!vrc4.irq());
+    vrc4.tick();
+    assert!(vrc4.irq());
+
+    // Scanline mode, the prescaler clocks the counter every 113.67 cycles
+    vrc4.cpu_write(&mut memory, 0xF000, 0x0F);
+    vrc4.cpu_write(&mut memory, 0xF002, 0x0F);
+    vrc4.cpu_write(&mut memory, 0xF001, 0x02);
+    for _ in 0..113 {
+        vrc4.tick();
+    }
+    assert!(!vrc4.irq());
+    vrc4.tick();
+    assert!(vrc4.irq());
+
+    // Disabled
+    vrc4.cpu_write(&mut memory, 0xF001, 0x00);
+    for _ in 0..1000 {
+        vrc4.tick();
+    }
+    assert!(!vrc4.irq());
+}
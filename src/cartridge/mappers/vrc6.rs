@@ -0,0 +1,388 @@
+/*!  Konami VRC6 (mappers 24 and 26), with its expansion audio
+
+Mapper 26 has the A0 and A1 pins swapped, the registers below use the pins:
+
+PRG bank 0    $8000 - $8003    16KB bank at $8000
+Pulse 1    $9000 - $9002    MDDDVVVV: ignore duty, duty, volume / period low / E...PPPP: enable, period high
+Frequency control    $9003    .....LHS: 256x faster, 16x faster, halt
+Pulse 2    $A000 - $A002    Same as pulse 1
+Sawtooth    $B000 - $B002    ..AAAAAA: accumulator rate / period low / E...PPPP: enable, period high
+PPU banking    $B003    R.NMMBPP: PRG RAM enable, A10 rule, CHR nametables, mirroring, CHR mode
+PRG bank 1    $C000 - $C003    8KB bank at $C000
+CHR banks    $D000 - $E003    R0-R7
+IRQ latch    $F000
+IRQ control    $F001    .....MEA: cycle mode, enable, enable after acknowledge
+IRQ acknowledge    $F002    Copies A to E
+
+PRG RAM    $6000 - $7FFF
+PRG ROM    $8000 - $FFFF, $E000 is the last 8KB bank
+CHR ROM    $0000 - $1FFF, laid out by the CHR mode
+
+NOTE: Nametables read from CHR ROM aren't emulated, no game uses them.
+*/
+
+use super::vrc_irq::VrcIrq;
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+
+/// Output level of a step of the VRC6 channels, the same as a step of the APU pulses
+const OUTPUT_LEVEL: f32 = 0.00752;
+
+/// A pulse channel with 16 steps and 8 duty cycles
+#[derive(Default)]
+struct Vrc6Pulse {
+    volume: u8,
+    duty: u8,
+    ignore_duty: bool,
+    period: u16,
+    enabled: bool,
+    timer: u16,
+    step: u8,
+}
+
+impl Vrc6Pulse {
+    fn write(&mut self, register: u16, value: u8) {
+        match register & 0x03 {
+            0 => {
+                self.ignore_duty = value & 0x80 != 0;
+                self.duty = (value >> 4) & 0x07;
+                self.volume = value & 0x0F;
+            }
+            1 => self.period = (self.period & 0x0F00) | value as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | ((value as u16 & 0x0F) << 8);
+                self.enabled = value & 0x80 != 0;
+                if !self.enabled {
+                    self.step = 0;
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self, shift: u8) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.timer == 0 {
+            self.timer = self.period >> shift;
+            self.step = (self.step + 1) & 0x0F;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        if self.enabled && (self.ignore_duty || self.step <= self.duty) {
+            self.volume
+        } else {
+            0
+        }
+    }
+}
+
+/// A sawtooth made of 7 additions to an accumulator, which is reset on the 14th step
+#[derive(Default)]
+struct Vrc6Saw {
+    rate: u8,
+    period: u16,
+    enabled: bool,
+    timer: u16,
+    step: u8,
+    accumulator: u8,
+}
+
+impl Vrc6Saw {
+    fn write(&mut self, register: u16, value: u8) {
+        match register & 0x03 {
+            0 => self.rate = value & 0x3F,
+            1 => self.period = (self.period & 0x0F00) | value as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | ((value as u16 & 0x0F) << 8);
+                self.enabled = value & 0x80 != 0;
+                if !self.enabled {
+                    self.step = 0;
+                    self.accumulator = 0;
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self, shift: u8) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.timer == 0 {
+            self.timer = self.period >> shift;
+            self.step = (self.step + 1) % 14;
+            if self.step == 0 {
+                self.accumulator = 0;
+            } else if self.step & 1 == 0 {
+                self.accumulator = self.accumulator.wrapping_add(self.rate);
+            }
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        if self.enabled {
+            self.accumulator >> 3
+        } else {
+            0
+        }
+    }
+}
+
+pub struct Vrc6 {
+    swapped_pins: bool,
+    prg_banks: [u8; 2],
+    chr_banks: [u8; 8],
+    ppu_banking: u8,
+    irq: VrcIrq,
+    pulse1: Vrc6Pulse,
+    pulse2: Vrc6Pulse,
+    saw: Vrc6Saw,
+    halt: bool,
+    frequency_shift: u8,
+}
+
+impl Vrc6 {
+    /// `swapped_pins` is set for mapper 26
+    pub fn new(swapped_pins: bool) -> Self {
+        Self {
+            swapped_pins,
+            prg_banks: [0; 2],
+            chr_banks: [0; 8],
+            ppu_banking: 0,
+            irq: VrcIrq::default(),
+            pulse1: Vrc6Pulse::default(),
+            pulse2: Vrc6Pulse::default(),
+            saw: Vrc6Saw::default(),
+            halt: false,
+            frequency_shift: 0,
+        }
+    }
+
+    /// The register at `addr`, with the pins as the low 2 bits
+    fn register(&self, addr: u16) -> u16 {
+        let pins = if self.swapped_pins {
+            (addr & 1) << 1 | (addr >> 1) & 1
+        } else {
+            addr & 0x03
+        };
+        (addr & 0xF000) | pins
+    }
+
+    /// 8KB PRG ROM bank at `addr`
+    fn prg_rom_bank(&self, memory: &Memory, addr: u16) -> usize {
+        match addr {
+            0x8000..=0xBFFF => (self.prg_banks[0] as usize) << 1 | (addr as usize >> 13) & 1,
+            0xC000..=0xDFFF => self.prg_banks[1] as usize,
+            _ => memory.prg_rom_banks(0x2000) - 1,
+        }
+    }
+
+    fn is_prg_ram_enabled(&self) -> bool {
+        self.ppu_banking & 0x80 != 0
+    }
+
+    /// 1KB CHR bank at `addr`
+    fn chr_bank(&self, addr: u16) -> usize {
+        let slot = (addr >> 10) as usize & 0x07;
+        // The 2KB banks take their low bit from the register unless A10 is used
+        let (mask, a10) = if self.ppu_banking & 0x20 != 0 {
+            (0xFE, (slot & 1) as u8)
+        } else {
+            (0xFF, 0)
+        };
+
+        let bank = match (self.ppu_banking & 0x03, slot) {
+            (0, _) => self.chr_banks[slot],
+            (1, _) => (self.chr_banks[slot / 2] & mask) | a10,
+            (_, 0..=3) => self.chr_banks[slot],
+            (_, _) => (self.chr_banks[4 + (slot - 4) / 2] & mask) | a10,
+        };
+        bank as usize
+    }
+}
+
+impl Mapper for Vrc6 {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.is_prg_ram_enabled() => memory.read_prg_ram(addr),
+            0x8000..=0xFFFF => {
+                Some(memory.read_prg_rom(0x2000, self.prg_rom_bank(memory, addr), addr))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        if let 0x6000..=0x7FFF = addr {
+            if self.is_prg_ram_enabled() {
+                memory.write_prg_ram(addr, value);
+            }
+            return;
+        }
+
+        match self.register(addr) {
+            0x8000..=0x8003 => self.prg_banks[0] = value & 0x0F,
+            0x9003 => {
+                self.halt = value & 0x01 != 0;
+                self.frequency_shift = if value & 0x04 != 0 {
+                    8
+                } else if value & 0x02 != 0 {
+                    4
+                } else {
+                    0
+                };
+            }
+            register @ 0x9000..=0x9002 => self.pulse1.write(register, value),
+            register @ 0xA000..=0xA002 => self.pulse2.write(register, value),
+            register @ 0xB000..=0xB002 => self.saw.write(register, value),
+            0xB003 => self.ppu_banking = value,
+            0xC000..=0xC003 => self.prg_banks[1] = value & 0x1F,
+            register @ 0xD000..=0xE003 => {
+                let bank = ((register - 0xD000) >> 12) * 4 + (register & 0x03);
+                self.chr_banks[bank as usize] = value;
+            }
+            0xF000 => self.irq.write_latch(value),
+            0xF001 => self.irq.write_control(value),
+            0xF002 => self.irq.acknowledge(),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x400, self.chr_bank(addr), addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(0x400, self.chr_bank(addr), addr, value);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match (self.ppu_banking >> 2) & 0x03 {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenA,
+            _ => Mirroring::SingleScreenB,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq.irq()
+    }
+
+    fn tick(&mut self) {
+        self.irq.tick();
+
+        if !self.halt {
+            self.pulse1.clock(self.frequency_shift);
+            self.pulse2.clock(self.frequency_shift);
+            self.saw.clock(self.frequency_shift);
+        }
+    }
+
+    fn audio(&self) -> f32 {
+        let output = self.pulse1.output() + self.pulse2.output() + self.saw.output();
+        output as f32 * OUTPUT_LEVEL
+    }
+}
+
+#[test]
+fn vrc6_banks_test() {
+    let mut memory = test_memory(0x40000, 0x40000);
+    let mut vrc6 = Vrc6::new(false);
+
+    vrc6.cpu_write(&mut memory, 0x8000, 3);
+    vrc6.cpu_write(&mut memory, 0xC000, 9);
+    assert_eq!(vrc6.cpu_peek(&memory, 0x8000), Some(6));
+    assert_eq!(vrc6.cpu_peek(&memory, 0xA000), Some(7));
+    assert_eq!(vrc6.cpu_peek(&memory, 0xC000), Some(9));
+    assert_eq!(vrc6.cpu_peek(&memory, 0xE000), Some(31));
+
+    for bank in 0..8 {
+        let register = 0xD000 + (bank / 4) * 0x1000 + bank % 4;
+        vrc6.cpu_write(&mut memory, register, 0x10 + bank as u8);
+    }
+
+    // CHR mode 0, eight 1KB banks
+    vrc6.cpu_write(&mut memory, 0xB003, 0x84);
+    assert_eq!(vrc6.ppu_read(&memory, 0x0400), 0x11);
+    assert_eq!(vrc6.ppu_read(&memory, 0x1C00), 0x17);
+    assert_eq!(vrc6.mirroring(), Mirroring::Horizontal);
+
+    // CHR mode 1 with the A10 rule, four 2KB banks
+    vrc6.cpu_write(&mut memory, 0xB003, 0xA1);
+    assert_eq!(vrc6.ppu_read(&memory, 0x0800), 0x10);
+    assert_eq!(vrc6.ppu_read(&memory, 0x0C00), 0x11);
+    assert_eq!(vrc6.ppu_read(&memory, 0x1C00), 0x13);
+
+    // CHR mode 2, four 1KB banks then two 2KB banks
+    vrc6.cpu_write(&mut memory, 0xB003, 0xA2);
+    assert_eq!(vrc6.ppu_read(&memory, 0x0C00), 0x13);
+    assert_eq!(vrc6.ppu_read(&memory, 0x1400), 0x15);
+    assert_eq!(vrc6.ppu_read(&memory, 0x1800), 0x14);
+
+    // PRG RAM enable
+    vrc6.cpu_write(&mut memory, 0x6000, 0x42);
+    assert_eq!(vrc6.cpu_peek(&memory, 0x6000), Some(0x42));
+    vrc6.cpu_write(&mut memory, 0xB003, 0x00);
+    assert_eq!(vrc6.cpu_peek(&memory, 0x6000), None);
+}
+
+#[test]
+fn vrc6_swapped_pins_test() {
+    let mut memory = test_memory(0x40000, 0x40000);
+    let mut vrc6 = Vrc6::new(true);
+
+    // $D001 is R2 on mapper 26
+    vrc6.cpu_write(&mut memory, 0xD001, 0x42);
+    assert_eq!(vrc6.ppu_read(&memory, 0x0800), 0x42);
+    // $B003 is still $B003
+    vrc6.cpu_write(&mut memory, 0xB003, 0x08);
+    assert_eq!(vrc6.mirroring(), Mirroring::SingleScreenA);
+}
+
+#[test]
+fn vrc6_audio_test() {
+    let mut memory = test_memory(0x40000, 0x40000);
+    let mut vrc6 = Vrc6::new(false);
+    assert_eq!(vrc6.audio(), 0.0);
+
+    // Pulse with a duty of 8/16 at full volume, a period of 2 cycles per step
+    vrc6.cpu_write(&mut memory, 0x9000, 0x7F);
+    vrc6.cpu_write(&mut memory, 0x9001, 0x01);
+    vrc6.cpu_write(&mut memory, 0x9002, 0x80);
+    let mut high = 0;
+    for _ in 0..32 {
+        vrc6.tick();
+        if vrc6.audio() > 0.0 {
+            high += 1;
+        }
+    }
+    assert_eq!(high, 16);
+
+    // Halted
+    vrc6.cpu_write(&mut memory, 0x9003, 0x01);
+    let output = vrc6.audio();
+    vrc6.tick();
+    vrc6.tick();
+    assert_eq!(vrc6.audio(), output);
+
+    // The sawtooth adds its rate every other step, and starts over after 14 steps
+    vrc6.cpu_write(&mut memory, 0x9003, 0x00);
+    vrc6.cpu_write(&mut memory, 0x9002, 0x00);
+    vrc6.cpu_write(&mut memory, 0xB000, 0x08);
+    vrc6.cpu_write(&mut memory, 0xB002, 0x80);
+    let mut levels = vec![];
+    for _ in 0..14 {
+        vrc6.tick();
+        levels.push((vrc6.audio() / OUTPUT_LEVEL).round() as u8);
+    }
+    assert_eq!(levels, [0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 0]);
+}
@@ -0,0 +1,616 @@
+/*!  Konami VRC7 (mapper 85), with its expansion audio
+
+Lagrange Point boards have the registers at $x010 (A4) and Tiny Toon Adventures 2 boards at
+$x008 (A3), both are decoded:
+
+PRG banks    $8000, $8010, $9000    ..BBBBBB: 8KB banks at $8000, $A000 and $C000
+Audio register    $9010    Register of the sound chip written next
+Audio data    $9030    Value of the selected audio register
+CHR banks    $A000 - $D010    1KB banks, R0 at $A000, R1 at $A010, ... R7 at $D010
+Control    $E000    RS....MM: PRG RAM enable, silence the audio, mirroring
+IRQ latch    $E010
+IRQ control    $F000    .....MEA: cycle mode, enable, enable after acknowledge
+IRQ acknowledge    $F010    Copies A to E
+
+PRG RAM    $6000 - $7FFF
+PRG ROM    $8000 - $FFFF, $E000 is the last 8KB bank
+CHR ROM    $0000 - $1FFF
+*/
+
+use super::vrc_irq::VrcIrq;
+#[cfg(test)]
+use crate::cartridge::test_memory;
+use crate::cartridge::{Mapper, Memory, Mirroring};
+use std::f32::consts::TAU;
+
+/// Output of a carrier at full volume, close to an APU pulse at full volume
+const OUTPUT_LEVEL: f32 = 0.1;
+/// CPU cycles between two samples of the sound chip
+const SAMPLE_CYCLES: u8 = 36;
+/// Attenuation at which an operator is silent, in steps of 0.375dB
+const MAX_ATTENUATION: u8 = 127;
+/// Size of the phase of an operator for a full wave
+const PHASE_WAVE: u32 = 0x80000;
+
+/// The fixed patches 1-15 of the VRC7
+const PATCHES: [[u8; 8]; 15] = [
+    [0x03, 0x21, 0x05, 0x06, 0xE8, 0x81, 0x42, 0x27], // Buzzy bell
+    [0x13, 0x41, 0x14, 0x0D, 0xD8, 0xF6, 0x23, 0x12], // Guitar
+    [0x11, 0x11, 0x08, 0x08, 0xFA, 0xB2, 0x20, 0x12], // Wurly
+    [0x31, 0x61, 0x0C, 0x07, 0xA8, 0x64, 0x61, 0x27], // Flute
+    [0x32, 0x21, 0x1E, 0x06, 0xE1, 0x76, 0x01, 0x28], // Clarinet
+    [0x02, 0x01, 0x06, 0x00, 0xA3, 0xE2, 0xF4, 0xF4], // Synth
+    [0x21, 0x61, 0x1D, 0x07, 0x82, 0x81, 0x11, 0x07], // Trumpet
+    [0x23, 0x21, 0x22, 0x17, 0xA2, 0x72, 0x01, 0x17], // Organ
+    [0x35, 0x11, 0x25, 0x00, 0x40, 0x73, 0x72, 0x01], // Bells
+    [0xB5, 0x01, 0x0F, 0x0F, 0xA8, 0xA5, 0x51, 0x02], // Vibes
+    [0x17, 0xC1, 0x24, 0x07, 0xF8, 0xF8, 0x22, 0x12], // Vibraphone
+    [0x71, 0x23, 0x11, 0x06, 0x65, 0x74, 0x18, 0x16], // Tutti
+    [0x01, 0x02, 0xD3, 0x05, 0xC9, 0x95, 0x03, 0x02], // Fretless
+    [0x61, 0x63, 0x0C, 0x00, 0x94, 0xC0, 0x33, 0xF6], // Synth bass
+    [0x21, 0x72, 0x0D, 0x00, 0xC1, 0xD5, 0x56, 0x06], // Sweep
+];
+
+/// Frequency multipliers, doubled as the first one is 1/2
+const MULTIPLIERS: [u32; 16] = [1, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 20, 24, 24, 30, 30];
+
+/// Key scale levels for the 4 high bits of the F-number, in steps of 0.75dB at the highest block
+const KEY_SCALE_LEVELS: [i16; 16] = [
+    0, 32, 40, 45, 48, 51, 53, 55, 56, 58, 59, 60, 61, 62, 63, 64,
+];
+
+/// Envelope steps over 8 clocks, for the 4 rates sharing a period
+const ENVELOPE_STEPS: [[u8; 8]; 4] = [
+    [0, 1, 0, 1, 0, 1, 0, 1],
+    [0, 1, 0, 1, 1, 1, 0, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1],
+    [0, 1, 1, 1, 1, 1, 1, 1],
+];
+
+/// Vibrato added to the doubled F-number, by its 3 high bits over the 8 vibrato steps
+const VIBRATO: [[i8; 8]; 8] = [
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0, 0, -1, 0],
+    [0, 1, 2, 1, 0, -1, -2, -1],
+    [0, 1, 3, 1, 0, -1, -3, -1],
+    [0, 2, 4, 2, 0, -2, -4, -2],
+    [0, 2, 5, 2, 0, -2, -5, -2],
+    [0, 3, 6, 3, 0, -3, -6, -3],
+    [0, 3, 7, 3, 0, -3, -7, -3],
+];
+
+/// F-number and block of a channel
+#[derive(Clone, Copy)]
+struct Pitch {
+    fnum: u16,
+    block: u8,
+}
+
+impl Pitch {
+    /// 4 bits speeding up the envelopes of the higher notes
+    fn key_code(self) -> u8 {
+        self.block << 1 | (self.fnum >> 8) as u8
+    }
+
+    /// Attenuation of the higher notes, `key_scale_level` is none, 1.5dB, 3dB or 6dB per octave
+    fn key_scale_attenuation(self, key_scale_level: u8) -> u16 {
+        if key_scale_level == 0 {
+            return 0;
+        }
+        let level =
+            KEY_SCALE_LEVELS[(self.fnum >> 5) as usize] * 2 - ((8 - self.block as i16) << 4);
+        (level.max(0) >> (3 - key_scale_level)) as u16
+    }
+}
+
+/// The patch of the modulator (slot 0) or of the carrier (slot 1)
+struct OperatorPatch {
+    tremolo: bool,
+    vibrato: bool,
+    sustained: bool,
+    key_scale_rate: bool,
+    multiplier: u8,
+    key_scale_level: u8,
+    rectified: bool,
+    attack: u8,
+    decay: u8,
+    sustain_level: u8,
+    release: u8,
+}
+
+impl OperatorPatch {
+    fn new(patch: &[u8; 8], slot: usize) -> Self {
+        Self {
+            tremolo: patch[slot] & 0x80 != 0,
+            vibrato: patch[slot] & 0x40 != 0,
+            sustained: patch[slot] & 0x20 != 0,
+            key_scale_rate: patch[slot] & 0x10 != 0,
+            multiplier: patch[slot] & 0x0F,
+            key_scale_level: patch[2 + slot] >> 6,
+            rectified: patch[3] & (0x08 << slot) != 0,
+            attack: patch[4 + slot] >> 4,
+            decay: patch[4 + slot] & 0x0F,
+            sustain_level: patch[6 + slot] >> 4,
+            release: patch[6 + slot] & 0x0F,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Envelope {
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// A sine wave with an envelope, the modulator or the carrier of a channel
+#[derive(Clone, Copy)]
+struct Operator {
+    phase: u32,
+    envelope: Envelope,
+    attenuation: u8,
+    outputs: [f32; 2], // The last two outputs, for the feedback
+}
+
+impl Default for Operator {
+    fn default() -> Self {
+        Self {
+            phase: 0,
+            envelope: Envelope::Release,
+            attenuation: MAX_ATTENUATION,
+            outputs: [0.0; 2],
+        }
+    }
+}
+
+impl Operator {
+    fn key_on(&mut self) {
+        self.envelope = Envelope::Attack;
+        self.phase = 0;
+    }
+
+    fn key_off(&mut self) {
+        self.envelope = Envelope::Release;
+    }
+
+    /// Moves the phase and the envelope by a sample
+    fn clock(&mut self, patch: &OperatorPatch, pitch: Pitch, sustain: bool, counter: u32) {
+        let vibrato = if patch.vibrato {
+            VIBRATO[(pitch.fnum >> 6) as usize][(counter >> 10) as usize & 0x07]
+        } else {
+            0
+        };
+        let fnum = (pitch.fnum as i32 * 2 + vibrato as i32) as u32;
+        let step = ((fnum << pitch.block) * MULTIPLIERS[patch.multiplier as usize]) >> 3;
+        self.phase = (self.phase + step) % PHASE_WAVE;
+
+        // Percussive tones decay in the sustain phase, the channel sustain slows down the release
+        let rate = match self.envelope {
+            Envelope::Attack => patch.attack,
+            Envelope::Decay => patch.decay,
+            Envelope::Sustain if patch.sustained => 0,
+            Envelope::Sustain => patch.release,
+            Envelope::Release if sustain => 5,
+            Envelope::Release if patch.sustained => patch.release,
+            Envelope::Release => 7,
+        };
+        let rate = match rate {
+            0 => 0,
+            rate if patch.key_scale_rate => (rate * 4 + pitch.key_code()).min(63),
+            rate => rate * 4 + (pitch.key_code() >> 2),
+        };
+        let step = Operator::envelope_step(rate, counter);
+
+        match self.envelope {
+            Envelope::Attack if rate >= 60 => self.attenuation = 0,
+            Envelope::Attack => {
+                let decrease = ((self.attenuation as u16 + 1) * step as u16).div_ceil(8);
+                self.attenuation = self.attenuation.saturating_sub(decrease as u8);
+            }
+            _ => self.attenuation = (self.attenuation + step).min(MAX_ATTENUATION),
+        }
+
+        match self.envelope {
+            Envelope::Attack if self.attenuation == 0 => self.envelope = Envelope::Decay,
+            Envelope::Decay if self.attenuation >= patch.sustain_level * 8 => {
+                self.envelope = Envelope::Sustain
+            }
+            _ => {}
+        }
+    }
+
+    /// Change of the attenuation for a `rate` of 0-63, rates 4 apart are twice as fast
+    fn envelope_step(rate: u8, counter: u32) -> u8 {
+        if rate < 4 {
+            return 0;
+        }
+
+        let shift = 13u8.saturating_sub(rate >> 2);
+        if counter & ((1 << shift) - 1) != 0 {
+            return 0;
+        }
+        let step = ENVELOPE_STEPS[rate as usize & 0x03][(counter >> shift) as usize & 0x07];
+        step << (rate >> 2).saturating_sub(13)
+    }
+
+    /// Outputs a sample, `level` is the total level or the volume and `modulation` moves the phase in waves
+    fn output(
+        &mut self,
+        patch: &OperatorPatch,
+        pitch: Pitch,
+        level: u16,
+        tremolo: u16,
+        modulation: f32,
+    ) -> f32 {
+        let mut attenuation =
+            self.attenuation as u16 + level + pitch.key_scale_attenuation(patch.key_scale_level);
+        if patch.tremolo {
+            attenuation += tremolo;
+        }
+
+        let output = if attenuation >= MAX_ATTENUATION as u16 {
+            0.0
+        } else {
+            let sample = ((self.phase as f32 / PHASE_WAVE as f32 + modulation) * TAU).sin();
+            if patch.rectified && sample < 0.0 {
+                0.0
+            } else {
+                sample * 10f32.powf(attenuation as f32 * -0.375 / 20.0)
+            }
+        };
+        self.outputs = [output, self.outputs[0]];
+        output
+    }
+}
+
+/**
+The YM2413 derivative of the VRC7, six FM channels of two operators: a modulator changing the
+phase of a carrier
+
+Custom patch    $00 - $07    Patch 0, laid out like the fixed patches
+F-numbers low    $10 - $15
+Channel controls    $20 - $25    ..SKBBBF: sustain, key on, block, F-number high bit
+Instruments    $30 - $35    PPPPVVVV: patch, volume in steps of 3dB
+
+Patches, for the modulator then the carrier:
+$0 - $1    AVEKMMMM: tremolo, vibrato, sustained tone, key scale rate, frequency multiplier
+$2    KKTTTTTT: modulator key scale level, modulator total level in steps of 0.75dB
+$3    KK.CMFFF: carrier key scale level, carrier and modulator half sine, modulator feedback
+$4 - $5    AAAADDDD: attack and decay rates
+$6 - $7    SSSSRRRR: sustain level in steps of 3dB, release rate
+
+A sample is computed every 36 CPU cycles, the rhythm mode of the YM2413 isn't on the VRC7.
+
+NOTE: Key on starts the attack right away, without the damp phase of the real chip.
+*/
+struct Opll {
+    registers: [u8; 0x40],
+    operators: [[Operator; 2]; 6],
+    cycles: u8,
+    counter: u32, // Samples, for the envelopes, tremolo and vibrato
+    output: f32,
+}
+
+impl Opll {
+    fn new() -> Self {
+        Self {
+            registers: [0; 0x40],
+            operators: [[Operator::default(); 2]; 6],
+            cycles: 0,
+            counter: 0,
+            output: 0.0,
+        }
+    }
+
+    fn write(&mut self, register: u8, value: u8) {
+        let register = register as usize;
+        match register {
+            0x00..=0x07 | 0x10..=0x15 | 0x30..=0x35 => self.registers[register] = value,
+            0x20..=0x25 => {
+                let key_on = value & 0x10 != 0;
+                if key_on != (self.registers[register] & 0x10 != 0) {
+                    for operator in self.operators[register - 0x20].iter_mut() {
+                        if key_on {
+                            operator.key_on();
+                        } else {
+                            operator.key_off();
+                        }
+                    }
+                }
+                self.registers[register] = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn patch(&self, channel: usize) -> [u8; 8] {
+        match self.registers[0x30 + channel] >> 4 {
+            0 => {
+                let mut patch = [0; 8];
+                patch.copy_from_slice(&self.registers[..8]);
+                patch
+            }
+            instrument => PATCHES[instrument as usize - 1],
+        }
+    }
+
+    /// Attenuation of the tremolo, a triangle of 13 steps at 3.7Hz
+    fn tremolo(&self) -> u16 {
+        let step = (self.counter >> 6) % 210;
+        (step.min(209 - step) >> 3) as u16
+    }
+
+    /// Called on every CPU cycle
+    fn tick(&mut self) {
+        self.cycles += 1;
+        if self.cycles < SAMPLE_CYCLES {
+            return;
+        }
+        self.cycles = 0;
+        self.counter = self.counter.wrapping_add(1);
+
+        let output = (0..6).map(|channel| self.clock_channel(channel)).sum();
+        self.output = output;
+    }
+
+    fn clock_channel(&mut self, channel: usize) -> f32 {
+        let patch = self.patch(channel);
+        let control = self.registers[0x20 + channel];
+        let pitch = Pitch {
+            fnum: self.registers[0x10 + channel] as u16 | (control as u16 & 0x01) << 8,
+            block: (control >> 1) & 0x07,
+        };
+        let sustain = control & 0x20 != 0;
+        let volume = (self.registers[0x30 + channel] & 0x0F) as u16;
+        let (counter, tremolo) = (self.counter, self.tremolo());
+
+        let modulator_patch = OperatorPatch::new(&patch, 0);
+        let carrier_patch = OperatorPatch::new(&patch, 1);
+        let [modulator, carrier] = &mut self.operators[channel];
+        modulator.clock(&modulator_patch, pitch, sustain, counter);
+        carrier.clock(&carrier_patch, pitch, sustain, counter);
+
+        let feedback = match patch[3] & 0x07 {
+            0 => 0.0,
+            feedback => {
+                (modulator.outputs[0] + modulator.outputs[1]) / (1 << (7 - feedback)) as f32
+            }
+        };
+        let level = (patch[2] & 0x3F) as u16 * 2;
+        let modulation = modulator.output(&modulator_patch, pitch, level, tremolo, feedback);
+        // The modulator moves the carrier by up to 4 waves
+        carrier.output(&carrier_patch, pitch, volume * 8, tremolo, modulation * 4.0)
+    }
+}
+
+pub struct Vrc7 {
+    prg_banks: [u8; 3],
+    chr_banks: [u8; 8],
+    control: u8,
+    irq: VrcIrq,
+    audio_register: u8,
+    audio: Opll,
+}
+
+impl Default for Vrc7 {
+    fn default() -> Self {
+        Self {
+            prg_banks: [0; 3],
+            chr_banks: [0; 8],
+            control: 0,
+            irq: VrcIrq::default(),
+            audio_register: 0,
+            audio: Opll::new(),
+        }
+    }
+}
+
+impl Vrc7 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The register at `addr`, as $x000 or $x010
+    fn register(addr: u16) -> u16 {
+        match addr & 0xF030 {
+            0x9030 => 0x9030,
+            _ if addr & 0x18 != 0 => (addr & 0xF000) | 0x10,
+            _ => addr & 0xF000,
+        }
+    }
+
+    fn is_prg_ram_enabled(&self) -> bool {
+        self.control & 0x80 != 0
+    }
+
+    fn is_audio_silenced(&self) -> bool {
+        self.control & 0x40 != 0
+    }
+}
+
+impl Mapper for Vrc7 {
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.is_prg_ram_enabled() => memory.read_prg_ram(addr),
+            0x8000..=0xDFFF => {
+                let bank = self.prg_banks[(addr as usize - 0x8000) / 0x2000] & 0x3F;
+                Some(memory.read_prg_rom(0x2000, bank as usize, addr))
+            }
+            0xE000..=0xFFFF => {
+                let bank = memory.prg_rom_banks(0x2000) - 1;
+                Some(memory.read_prg_rom(0x2000, bank, addr))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        if let 0x6000..=0x7FFF = addr {
+            if self.is_prg_ram_enabled() {
+                memory.write_prg_ram(addr, value);
+            }
+            return;
+        }
+
+        match Vrc7::register(addr) {
+            0x8000 => self.prg_banks[0] = value,
+            0x8010 => self.prg_banks[1] = value,
+            0x9000 => self.prg_banks[2] = value,
+            0x9010 => self.audio_register = value,
+            0x9030 => self.audio.write(self.audio_register, value),
+            register @ 0xA000..=0xD010 => {
+                let bank = ((register - 0xA000) >> 12) * 2 + ((register >> 4) & 1);
+                self.chr_banks[bank as usize] = value;
+            }
+            0xE000 => self.control = value,
+            0xE010 => self.irq.write_latch(value),
+            0xF000 => self.irq.write_control(value),
+            0xF010 => self.irq.acknowledge(),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8 {
+        memory.read_chr(0x400, self.chr_banks[addr as usize >> 10] as usize, addr)
+    }
+
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8) {
+        memory.write_chr(
+            0x400,
+            self.chr_banks[addr as usize >> 10] as usize,
+            addr,
+            value,
+        );
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0x03 {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenA,
+            _ => Mirroring::SingleScreenB,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq.irq()
+    }
+
+    fn tick(&mut self) {
+        self.irq.tick();
+        self.audio.tick();
+    }
+
+    fn audio(&self) -> f32 {
+        if self.is_audio_silenced() {
+            0.0
+        } else {
+            self.audio.output * OUTPUT_LEVEL
+        }
+    }
+}
+
+#[cfg(test)]
+fn write_audio(vrc7: &mut Vrc7, memory: &mut Memory, register: u8, value: u8) {
+    vrc7.cpu_write(memory, 0x9010, register);
+    vrc7.cpu_write(memory, 0x9030, value);
+}
+
+#[test]
+fn vrc7_banks_test() {
+    let mut memory = test_memory(0x40000, 0x40000);
+    let mut vrc7 = Vrc7::new();
+
+    vrc7.cpu_write(&mut memory, 0x8000, 3);
+    vrc7.cpu_write(&mut memory, 0x8010, 4);
+    vrc7.cpu_write(&mut memory, 0x9000, 5);
+    assert_eq!(vrc7.cpu_peek(&memory, 0x8000), Some(3));
+    assert_eq!(vrc7.cpu_peek(&memory, 0xA000), Some(4));
+    assert_eq!(vrc7.cpu_peek(&memory, 0xC000), Some(5));
+    assert_eq!(vrc7.cpu_peek(&memory, 0xE000), Some(31));
+
+    // Boards using A3
+    vrc7.cpu_write(&mut memory, 0x8008, 6);
+    assert_eq!(vrc7.cpu_peek(&memory, 0xA000), Some(6));
+
+    for bank in 0..8 {
+        let register = 0xA000 + (bank / 2) * 0x1000 + (bank % 2) * 0x10;
+        vrc7.cpu_write(&mut memory, register, 0x10 + bank as u8);
+    }
+    assert_eq!(vrc7.ppu_read(&memory, 0x0400), 0x11);
+    assert_eq!(vrc7.ppu_read(&memory, 0x1C00), 0x17);
+
+    // PRG RAM enable and mirroring
+    vrc7.cpu_write(&mut memory, 0xE000, 0x81);
+    vrc7.cpu_write(&mut memory, 0x6000, 0x42);
+    assert_eq!(vrc7.cpu_peek(&memory, 0x6000), Some(0x42));
+    assert_eq!(vrc7.mirroring(), Mirroring::Horizontal);
+    vrc7.cpu_write(&mut memory, 0xE000, 0x02);
+    assert_eq!(vrc7.cpu_peek(&memory, 0x6000), None);
+    assert_eq!(vrc7.mirroring(), Mirroring::SingleScreenA);
+
+    // IRQ in cycle mode
+    vrc7.cpu_write(&mut memory, 0xE010, 0xFE);
+    vrc7.cpu_write(&mut memory, 0xF000, 0x06);
+    vrc7.tick();
+    assert!(!vrc7.irq());
+    vrc7.tick();
+    assert!(vrc7.irq());
+    vrc7.cpu_write(&mut memory, 0xF010, 0x00);
+    assert!(!vrc7.irq());
+}
+
+#[test]
+fn vrc7_audio_test() {
+    let mut memory = test_memory(0x40000, 0x40000);
+    let mut vrc7 = Vrc7::new();
+    let samples = |vrc7: &mut Vrc7, count: usize| -> Vec<f32> {
+        (0..count)
+            .map(|_| {
+                for _ in 0..SAMPLE_CYCLES {
+                    vrc7.tick();
+                }
+                vrc7.audio()
+            })
+            .collect()
+    };
+
+    // Custom patch: a silent modulator, a sustained carrier attacking right away
+    let patch = [0x00, 0x21, 0x3F, 0x00, 0x00, 0xF0, 0x00, 0x0F];
+    for (register, value) in patch.iter().enumerate() {
+        write_audio(&mut vrc7, &mut memory, register as u8, *value);
+    }
+
+    // F-number 256 at block 7 is a wave every 32 samples
+    write_audio(&mut vrc7, &mut memory, 0x30, 0x00);
+    write_audio(&mut vrc7, &mut memory, 0x10, 0x00);
+    write_audio(&mut vrc7, &mut memory, 0x20, 0x1F);
+    let outputs = samples(&mut vrc7, 128);
+    let waves = (1..outputs.len())
+        .filter(|&i| outputs[i - 1] <= 0.0 && outputs[i] > 0.0)
+        .count();
+    assert_eq!(waves + (outputs[0] > 0.0) as usize, 4);
+    assert!((outputs[7] - OUTPUT_LEVEL).abs() < 1e-6);
+
+    // A volume of 2 is 6dB lower
+    write_audio(&mut vrc7, &mut memory, 0x30, 0x02);
+    let outputs = samples(&mut vrc7, 64);
+    let peak = outputs.iter().cloned().fold(0.0, f32::max);
+    assert!((peak / OUTPUT_LEVEL - 0.5).abs() < 0.01);
+
+    // Key off, the release rate of 15 is over quickly
+    write_audio(&mut vrc7, &mut memory, 0x20, 0x0F);
+    let outputs = samples(&mut vrc7, 64);
+    assert_eq!(outputs[63], 0.0);
+
+    // Fixed patches
+    write_audio(&mut vrc7, &mut memory, 0x30, 0x30);
+    assert_eq!(vrc7.audio.patch(0), PATCHES[2]);
+
+    // Silenced
+    write_audio(&mut vrc7, &mut memory, 0x20, 0x1F);
+    assert!(samples(&mut vrc7, 16).iter().any(|&output| output != 0.0));
+    vrc7.cpu_write(&mut memory, 0xE000, 0x40);
+    assert_eq!(vrc7.audio(), 0.0);
+}
@@ -0,0 +1,129 @@
+/*!  The IRQ counter shared by the Konami VRC4, VRC6 and VRC7
+
+The counter counts up to $FF and reloads from the latch. In scanline mode a prescaler
+clocks it every 341/3 CPU cycles, in cycle mode it's clocked on every CPU cycle.
+
+Control    .....MEA: cycle mode, enable, enable after acknowledge
+Acknowledge    Copies A to E
+*/
+
+/// CPU cycles are counted in thirds of PPU dots by the prescaler
+const PRESCALER_PERIOD: i16 = 341;
+
+pub struct VrcIrq {
+    latch: u8,
+    counter: u8,
+    prescaler: i16,
+    enabled: bool,
+    enabled_after_ack: bool,
+    cycle_mode: bool,
+    irq: bool,
+}
+
+impl Default for VrcIrq {
+    fn default() -> Self {
+        Self {
+            latch: 0,
+            counter: 0,
+            prescaler: PRESCALER_PERIOD,
+            enabled: false,
+            enabled_after_ack: false,
+            cycle_mode: false,
+            irq: false,
+        }
+    }
+}
+
+impl VrcIrq {
+    pub fn latch(&self) -> u8 {
+        self.latch
+    }
+
+    pub fn write_latch(&mut self, value: u8) {
+        self.latch = value;
+    }
+
+    pub fn write_control(&mut self, value: u8) {
+        self.enabled_after_ack = value & 0x01 != 0;
+        self.enabled = value & 0x02 != 0;
+        self.cycle_mode = value & 0x04 != 0;
+        if self.enabled {
+            self.counter = self.latch;
+            self.prescaler = PRESCALER_PERIOD;
+        }
+        self.irq = false;
+    }
+
+    pub fn acknowledge(&mut self) {
+        self.enabled = self.enabled_after_ack;
+        self.irq = false;
+    }
+
+    pub fn irq(&self) -> bool {
+        self.irq
+    }
+
+    /// Called on every CPU cycle
+    pub fn tick(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.cycle_mode {
+            self.clock_counter();
+        } else {
+            self.prescaler -= 3;
+            if self.prescaler <= 0 {
+                self.prescaler += PRESCALER_PERIOD;
+                self.clock_counter();
+            }
+        }
+    }
+
+    fn clock_counter(&mut self) {
+        if self.counter == 0xFF {
+            self.counter = self.latch;
+            self.irq = true;
+        } else {
+            self.counter += 1;
+        }
+    }
+}
+
+#[test]
+fn vrc_irq_test() {
+    let mut irq = VrcIrq::default();
+
+    // Cycle mode, from $FE: $FF then reload and IRQ
+    irq.write_latch(0xFE);
+    irq.write_control(0x07);
+    irq.tick();
+    assert!(!irq.irq());
+    irq.tick();
+    assert!(irq.irq());
+
+    // Acknowledge, A keeps it enabled
+    irq.acknowledge();
+    assert!(!irq.irq());
+    irq.tick();
+    assert!(!irq.irq());
+    irq.tick();
+    assert!(irq.irq());
+
+    // Scanline mode, the prescaler clocks the counter every 113.67 cycles
+    irq.write_latch(0xFF);
+    irq.write_control(0x02);
+    for _ in 0..113 {
+        irq.tick();
+    }
+    assert!(!irq.irq());
+    irq.tick();
+    assert!(irq.irq());
+
+    // Disabled
+    irq.write_control(0x00);
+    for _ in 0..1000 {
+        irq.tick();
+    }
+    assert!(!irq.irq());
+}
@@ -0,0 +1,335 @@
+/*!  The cartridge: ROM and RAM chips, and the mapper wiring them to the CPU and PPU buses */
+
+pub mod mappers;
+
+use self::mappers::Nrom;
+use crate::{error::EmulatorError, nes_rom::RomFile};
+
+/// Size of a PRG RAM chip, mapped at $6000-$7FFF
+pub const PRG_RAM_SIZE: usize = 0x2000;
+/// Size of the CHR RAM given to cartridges without CHR ROM
+pub const CHR_RAM_SIZE: usize = 0x2000;
+/// Offset of the trainer in PRG RAM, it's loaded at $7000
+const TRAINER_OFFSET: usize = 0x1000;
+
+/// How the 2KB of nametable RAM in the console fill the four nametables of the PPU address space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirroring {
+    /// $2000 and $2400 are the first nametable, $2800 and $2C00 the second one
+    Horizontal,
+    /// $2000 and $2800 are the first nametable, $2400 and $2C00 the second one
+    Vertical,
+    /// All four nametables are the first one
+    SingleScreenA,
+    /// All four nametables are the second one
+    SingleScreenB,
+    /// The cartridge has RAM for the other two nametables, nothing is mirrored
+    FourScreen,
+    /// Each nametable is the nametable RAM page picked by the mapper
+    Mapped([u8; 4]),
+}
+
+impl Mirroring {
+    /// Offset in nametable RAM of a PPU address in $2000-$3EFF
+    pub fn nametable_offset(self, addr: u16) -> usize {
+        let addr = (addr & 0x0FFF) as usize;
+        let (nametable, offset) = (addr / 0x400, addr % 0x400);
+        let bank = match self {
+            Mirroring::Horizontal => nametable / 2,
+            Mirroring::Vertical => nametable % 2,
+            Mirroring::SingleScreenA => 0,
+            Mirroring::SingleScreenB => 1,
+            Mirroring::FourScreen => nametable,
+            Mirroring::Mapped(pages) => pages[nametable] as usize,
+        };
+
+        bank * 0x400 + offset
+    }
+}
+
+#[test]
+fn mirroring_test() {
+    assert_eq!(Mirroring::Horizontal.nametable_offset(0x2400), 0x000);
+    assert_eq!(Mirroring::Horizontal.nametable_offset(0x2C01), 0x401);
+    assert_eq!(Mirroring::Vertical.nametable_offset(0x2801), 0x001);
+    assert_eq!(Mirroring::Vertical.nametable_offset(0x2400), 0x400);
+    // $3000-$3EFF mirrors $2000-$2EFF
+    assert_eq!(Mirroring::Vertical.nametable_offset(0x3401), 0x401);
+    assert_eq!(Mirroring::SingleScreenA.nametable_offset(0x2C01), 0x001);
+    assert_eq!(Mirroring::SingleScreenB.nametable_offset(0x2001), 0x401);
+    assert_eq!(Mirroring::FourScreen.nametable_offset(0x2C01), 0xC01);
+    assert_eq!(
+        Mirroring::Mapped([1, 0, 0, 1]).nametable_offset(0x2C01),
+        0x401
+    );
+}
+
+/**
+The ROM and RAM chips on the cartridge
+
+Banks are addressed in units of the bank size of the mapper, bank numbers wrap around the
+size of the chip like the unconnected high address lines of the real boards do.
+*/
+pub struct Memory {
+    pub prg_rom: Vec<u8>,
+    pub prg_ram: Vec<u8>,
+    pub chr: Vec<u8>, // CHR ROM, or CHR RAM when the ROM has none
+    pub chr_is_ram: bool,
+}
+
+impl Memory {
+    /// Reads `addr` in PRG ROM bank `bank`, of `bank_size` bytes
+    pub fn read_prg_rom(&self, bank_size: usize, bank: usize, addr: u16) -> u8 {
+        Memory::banked(self.prg_rom.len(), bank_size, bank, addr)
+            .map_or(0, |index| self.prg_rom[index])
+    }
+
+    /// Reads `addr` in PRG RAM, mirrored when the chip is smaller than the 8KB window
+    pub fn read_prg_ram(&self, addr: u16) -> Option<u8> {
+        if self.prg_ram.is_empty() {
+            None
+        } else {
+            Some(self.prg_ram[addr as usize % self.prg_ram.len()])
+        }
+    }
+
+    pub fn write_prg_ram(&mut self, addr: u16, value: u8) {
+        if !self.prg_ram.is_empty() {
+            let len = self.prg_ram.len();
+            self.prg_ram[addr as usize % len] = value;
+        }
+    }
+
+    /// Reads `addr` in CHR bank `bank`, of `bank_size` bytes
+    pub fn read_chr(&self, bank_size: usize, bank: usize, addr: u16) -> u8 {
+        Memory::banked(self.chr.len(), bank_size, bank, addr).map_or(0, |index| self.chr[index])
+    }
+
+    /// Writes `addr` in CHR bank `bank`, ignored for CHR ROM
+    pub fn write_chr(&mut self, bank_size: usize, bank: usize, addr: u16, value: u8) {
+        if !self.chr_is_ram {
+            return;
+        }
+        if let Some(index) = Memory::banked(self.chr.len(), bank_size, bank, addr) {
+            self.chr[index] = value;
+        }
+    }
+
+    /// Number of banks of `bank_size` bytes in PRG ROM
+    pub fn prg_rom_banks(&self, bank_size: usize) -> usize {
+        (self.prg_rom.len() / bank_size).max(1)
+    }
+
+    /// Number of banks of `bank_size` bytes in CHR ROM or RAM
+    pub fn chr_banks(&self, bank_size: usize) -> usize {
+        (self.chr.len() / bank_size).max(1)
+    }
+
+    /// Index of `addr` in bank `bank` of a chip of `len` bytes, `None` for an empty chip
+    fn banked(len: usize, bank_size: usize, bank: usize, addr: u16) -> Option<usize> {
+        if len == 0 {
+            return None;
+        }
+        let banks = (len / bank_size).max(1);
+        Some(((bank % banks) * bank_size + addr as usize % bank_size) % len)
+    }
+}
+
+#[test]
+fn banked_test() {
+    let mut memory = Memory {
+        prg_rom: (0..0x6000).map(|i| (i / 0x2000) as u8).collect(),
+        prg_ram: Vec::new(),
+        chr: Vec::new(),
+        chr_is_ram: true,
+    };
+
+    // Bank numbers wrap around the 3 banks of the chip
+    assert_eq!(memory.read_prg_rom(0x2000, 4, 0x8000), 1);
+    // An empty chip reads as 0 and ignores writes
+    memory.write_chr(0x400, 1, 0x0400, 0x42);
+    assert_eq!(memory.read_chr(0x400, 1, 0x0400), 0);
+}
+
+/// Cartridge memory for the mapper tests, where every byte of ROM is the number of its bank
+///
+/// PRG ROM is numbered in 8KB banks and CHR ROM in 1KB banks, next to 8KB of PRG RAM.
+#[cfg(test)]
+pub(crate) fn test_memory(prg_rom_size: usize, chr_rom_size: usize) -> Memory {
+    Memory {
+        prg_rom: (0..prg_rom_size).map(|i| (i / 0x2000) as u8).collect(),
+        prg_ram: vec![0; PRG_RAM_SIZE],
+        chr: (0..chr_rom_size).map(|i| (i / 0x400) as u8).collect(),
+        chr_is_ram: false,
+    }
+}
+
+/**
+The logic of a cartridge board, deciding which chip answers each CPU and PPU access
+
+`cpu_read` and `cpu_write` see the cartridge space $4020-$FFFF, `ppu_read` and `ppu_write`
+the pattern tables $0000-$1FFF. CPU reads return `None` when nothing drives the data bus,
+which leaves the last value on it (open bus).
+The mapper also decides how the nametables are mirrored and can drive the CPU IRQ line.
+Nametable accesses go through `nametable_read` and `nametable_write` first, mappers with
+their own nametable memory answer them instead of the nametable RAM of the console.
+`tick` is called on every CPU cycle, `ppu_address` with every address the PPU puts on its bus.
+Mappers are `Send + Sync` so the emulator can live in a frontend resource.
+*/
+pub trait Mapper: Send + Sync {
+    fn cpu_read(&mut self, memory: &Memory, addr: u16) -> Option<u8> {
+        self.cpu_peek(memory, addr)
+    }
+
+    /// Same as `cpu_read` without any side effect, for debuggers and trace logs
+    fn cpu_peek(&self, memory: &Memory, addr: u16) -> Option<u8>;
+    fn cpu_write(&mut self, memory: &mut Memory, addr: u16, value: u8);
+
+    fn ppu_read(&mut self, memory: &Memory, addr: u16) -> u8;
+    fn ppu_write(&mut self, memory: &mut Memory, addr: u16, value: u8);
+
+    fn mirroring(&self) -> Mirroring;
+
+    /// Reads a nametable address in $2000-$3EFF, `None` leaves it to the nametable RAM
+    fn nametable_read(&mut self, _memory: &Memory, _addr: u16) -> Option<u8> {
+        None
+    }
+
+    /// Writes a nametable address in $2000-$3EFF, `false` leaves it to the nametable RAM
+    fn nametable_write(&mut self, _memory: &mut Memory, _addr: u16, _value: u8) -> bool {
+        false
+    }
+
+    fn irq(&self) -> bool {
+        false
+    }
+
+    fn tick(&mut self) {}
+
+    fn ppu_address(&mut self, _addr: u16) {}
+
+    /// CPU write to a PPU register, the cartridge sees them on the CPU bus
+    fn ppu_register_write(&mut self, _addr: u16, _value: u8) {}
+
+    /// Output of the expansion audio chip, on the scale of the APU output
+    fn audio(&self) -> f32 {
+        0.0
+    }
+
+    /// RAM inside the mapper chip, kept by the battery like the PRG RAM
+    fn internal_ram(&mut self) -> Option<&mut [u8]> {
+        None
+    }
+}
+
+/// A cartridge plugged in the console
+pub struct Cartridge {
+    pub memory: Memory,
+    mapper: Box<dyn Mapper>,
+}
+
+impl Default for Cartridge {
+    /// A 32KB NROM board with CHR RAM and PRG RAM, where everything is zero
+    fn default() -> Self {
+        Self {
+            memory: Memory {
+                prg_rom: vec![0; 0x8000],
+                prg_ram: vec![0; PRG_RAM_SIZE],
+                chr: vec![0; CHR_RAM_SIZE],
+                chr_is_ram: true,
+            },
+            mapper: Box::new(Nrom::new(Mirroring::Horizontal)),
+        }
+    }
+}
+
+impl Cartridge {
+    /// Builds the cartridge described by the header of `nesfile`, fails if its mapper isn't emulated
+    pub fn new(nesfile: &RomFile) -> Result<Self, EmulatorError> {
+        let mapper = mappers::new(nesfile)?;
+
+        let (chr, chr_is_ram) = if nesfile.chr_rom().is_empty() {
+            // Some NES 2.0 headers leave the CHR RAM size at 0
+            let size = match nesfile.chr_ram_size() {
+                0 => CHR_RAM_SIZE,
+                size => size,
+            };
+            (vec![0; size], true)
+        } else {
+            (nesfile.chr_rom().to_vec(), false)
+        };
+
+        let mut prg_ram = vec![0; nesfile.prg_ram_size()];
+        if let Some(trainer) = nesfile.trainer() {
+            prg_ram.resize(prg_ram.len().max(PRG_RAM_SIZE), 0);
+            prg_ram[TRAINER_OFFSET..TRAINER_OFFSET + trainer.len()].copy_from_slice(trainer);
+        }
+
+        Ok(Self {
+            memory: Memory {
+                prg_rom: nesfile.prg_rom().to_vec(),
+                prg_ram,
+                chr,
+                chr_is_ram,
+            },
+            mapper,
+        })
+    }
+
+    pub fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        self.mapper.cpu_read(&self.memory, addr)
+    }
+
+    pub fn cpu_peek(&self, addr: u16) -> Option<u8> {
+        self.mapper.cpu_peek(&self.memory, addr)
+    }
+
+    pub fn cpu_write(&mut self, addr: u16, value: u8) {
+        self.mapper.cpu_write(&mut self.memory, addr, value);
+    }
+
+    pub fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.mapper.ppu_read(&self.memory, addr)
+    }
+
+    pub fn ppu_write(&mut self, addr: u16, value: u8) {
+        self.mapper.ppu_write(&mut self.memory, addr, value);
+    }
+
+    pub fn mirroring(&self) -> Mirroring {
+        self.mapper.mirroring()
+    }
+
+    pub fn nametable_read(&mut self, addr: u16) -> Option<u8> {
+        self.mapper.nametable_read(&self.memory, addr)
+    }
+
+    pub fn nametable_write(&mut self, addr: u16, value: u8) -> bool {
+        self.mapper.nametable_write(&mut self.memory, addr, value)
+    }
+
+    pub fn irq(&self) -> bool {
+        self.mapper.irq()
+    }
+
+    pub fn tick(&mut self) {
+        self.mapper.tick();
+    }
+
+    pub fn ppu_address(&mut self, addr: u16) {
+        self.mapper.ppu_address(addr);
+    }
+
+    pub fn ppu_register_write(&mut self, addr: u16, value: u8) {
+        self.mapper.ppu_register_write(addr, value);
+    }
+
+    pub fn audio(&self) -> f32 {
+        self.mapper.audio()
+    }
+
+    /// RAM inside the mapper chip to save with the PRG RAM, for games with a battery
+    pub fn internal_ram(&mut self) -> Option<&mut [u8]> {
+        self.mapper.internal_ram()
+    }
+}
@@ -59,6 +59,7 @@ pub enum InstructionName {
     ROR,
     ROL,
     DEC,
+    CLI,
     // UNOFFICIALS
     LAX,
     SAX,
@@ -68,6 +69,57 @@ pub enum InstructionName {
     RLA,
     SRE,
     RRA,
+    ANC,
+    ALR, // Sometimes designated ASR
+    ARR,
+    AXS, // Sometimes designated SBX
+    LAS,
+    SHX,
+    SHY,
+    TAS, // Sometimes designated SHS
+    AHX, // Sometimes designated SHA
+    XAA, // Sometimes designated ANE
+    JAM, // Sometimes designated KIL
+}
+
+/// How an instruction accesses the memory its addressing mode points to
+///
+/// Instructions without a memory operand (implied, jumps, branches, stack) count as reads.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Operation {
+    Read,
+    Write,
+    ReadModifyWrite,
+}
+
+impl InstructionName {
+    /// Classifies the instruction, indexed writes and read-modify-writes always take the page crossing cycle
+    #[must_use]
+    pub fn operation(self) -> Operation {
+        match self {
+            InstructionName::STA
+            | InstructionName::STX
+            | InstructionName::STY
+            | InstructionName::SAX
+            | InstructionName::SHX
+            | InstructionName::SHY
+            | InstructionName::TAS
+            | InstructionName::AHX => Operation::Write,
+            InstructionName::ASL
+            | InstructionName::LSR
+            | InstructionName::ROL
+            | InstructionName::ROR
+            | InstructionName::INC
+            | InstructionName::DEC
+            | InstructionName::SLO
+            | InstructionName::RLA
+            | InstructionName::SRE
+            | InstructionName::RRA
+            | InstructionName::DCP
+            | InstructionName::ISB => Operation::ReadModifyWrite,
+            _ => Operation::Read,
+        }
+    }
 }
 
 /// Associates an InstructionName to an AddressingMode, used by `match_instruction`
@@ -76,12 +128,11 @@ pub enum InstructionName {
 pub enum Instruction {
     Official(InstructionName, AddressingMode),
     Unofficial(InstructionName, AddressingMode),
-    Unknown,
 }
 
 /// Given an `u8` opcode, returns the `Instruction` corresponding to the instruction and adressing mode
 ///
-/// Manages all official and unoffical unstrictions. Retursn `Unknown` if opcode is invalid.
+/// Manages all 256 official and unoffical instructions.
 #[must_use]
 pub fn match_instruction(opcode: u8) -> Instruction {
     match opcode {
@@ -102,6 +153,8 @@ pub fn match_instruction(opcode: u8) -> Instruction {
         ),
         // SEI
         0x78 => Instruction::Official(InstructionName::SEI, AddressingMode::Implied),
+        // CLI
+        0x58 => Instruction::Official(InstructionName::CLI, AddressingMode::Implied),
         0xd8 => Instruction::Official(InstructionName::CLD, AddressingMode::Implied),
         // BRK
         0x0 => Instruction::Official(InstructionName::BRK, AddressingMode::Implied),
@@ -192,7 +245,6 @@ pub fn match_instruction(opcode: u8) -> Instruction {
         0x08 => Instruction::Official(InstructionName::PHP, AddressingMode::Implied),
         // BIT
         0x2C => Instruction::Official(InstructionName::BIT, AddressingMode::Absolute),
-        0x89 => Instruction::Official(InstructionName::BIT, AddressingMode::Immediate),
         0x24 => Instruction::Official(InstructionName::BIT, AddressingMode::ZeroPage),
         // BVS
         0x70 => Instruction::Official(InstructionName::BVS, AddressingMode::Relative),
@@ -358,6 +410,10 @@ pub fn match_instruction(opcode: u8) -> Instruction {
         0xDA => Instruction::Unofficial(InstructionName::NOP, AddressingMode::Implied),
         0xFA => Instruction::Unofficial(InstructionName::NOP, AddressingMode::Implied),
         0x80 => Instruction::Unofficial(InstructionName::NOP, AddressingMode::Immediate),
+        0x82 => Instruction::Unofficial(InstructionName::NOP, AddressingMode::Immediate),
+        0x89 => Instruction::Unofficial(InstructionName::NOP, AddressingMode::Immediate),
+        0xC2 => Instruction::Unofficial(InstructionName::NOP, AddressingMode::Immediate),
+        0xE2 => Instruction::Unofficial(InstructionName::NOP, AddressingMode::Immediate),
         0x1C => {
             Instruction::Unofficial(InstructionName::NOP, AddressingMode::AbsoluteIndirectWithX)
         }
@@ -377,6 +433,7 @@ pub fn match_instruction(opcode: u8) -> Instruction {
             Instruction::Unofficial(InstructionName::NOP, AddressingMode::AbsoluteIndirectWithX)
         }
         // LAX
+        0xAB => Instruction::Unofficial(InstructionName::LAX, AddressingMode::Immediate),
         0xA3 => Instruction::Unofficial(
             InstructionName::LAX,
             AddressingMode::ZeroPageIndexedIndirect,
@@ -510,7 +567,45 @@ pub fn match_instruction(opcode: u8) -> Instruction {
             AddressingMode::ZeroPageIndirectIndexedWithY,
         ),
         // UNKNOWN
-        _ => Instruction::Unknown,
+        // ANC
+        0x0B => Instruction::Unofficial(InstructionName::ANC, AddressingMode::Immediate),
+        0x2B => Instruction::Unofficial(InstructionName::ANC, AddressingMode::Immediate),
+        // ALR
+        0x4B => Instruction::Unofficial(InstructionName::ALR, AddressingMode::Immediate),
+        // ARR
+        0x6B => Instruction::Unofficial(InstructionName::ARR, AddressingMode::Immediate),
+        // AXS
+        0xCB => Instruction::Unofficial(InstructionName::AXS, AddressingMode::Immediate),
+        // LAS
+        0xBB => {
+            Instruction::Unofficial(InstructionName::LAS, AddressingMode::AbsoluteIndirectWithY)
+        }
+        // SHX
+        0x9E => {
+            Instruction::Unofficial(InstructionName::SHX, AddressingMode::AbsoluteIndirectWithY)
+        }
+        // SHY
+        0x9C => {
+            Instruction::Unofficial(InstructionName::SHY, AddressingMode::AbsoluteIndirectWithX)
+        }
+        // TAS
+        0x9B => {
+            Instruction::Unofficial(InstructionName::TAS, AddressingMode::AbsoluteIndirectWithY)
+        }
+        // AHX
+        0x9F => {
+            Instruction::Unofficial(InstructionName::AHX, AddressingMode::AbsoluteIndirectWithY)
+        }
+        0x93 => Instruction::Unofficial(
+            InstructionName::AHX,
+            AddressingMode::ZeroPageIndirectIndexedWithY,
+        ),
+        // XAA
+        0x8B => Instruction::Unofficial(InstructionName::XAA, AddressingMode::Immediate),
+        // JAM
+        0x02 | 0x12 | 0x22 | 0x32 | 0x42 | 0x52 | 0x62 | 0x72 | 0x92 | 0xB2 | 0xD2 | 0xF2 => {
+            Instruction::Unofficial(InstructionName::JAM, AddressingMode::Implied)
+        }
     }
 }
 
@@ -529,6 +624,22 @@ fn sei_test() {
     assert_eq!(registers.status, 0b00000100);
 }
 
+/// Clear Interrupt Disable (CLI)
+///
+/// Status I flag is set to 0, allowing IRQs to be serviced
+pub fn cli(registers: &mut Registers) {
+    registers.set_flag(StatusFlag::I, false);
+}
+
+#[test]
+fn cli_test() {
+    let mut registers = Registers::new();
+    registers.status = 0b00100100;
+    registers.pc += 1; // Simulate reading insruction
+    cli(&mut registers);
+    assert_eq!(registers.status, 0b00100000);
+}
+
 /// Clear Decimal Mode (CLD)
 ///
 /// Status D flag is set to 0
@@ -550,7 +661,7 @@ fn cld_test() {
 /// Loads a byte of memory into the accumulator.
 /// Sets the zero and negative flags as appropriate.
 pub fn lda(registers: &mut Registers, operand: u8) {
-    registers.a = operand as u8;
+    registers.a = operand;
     registers.set_flag(StatusFlag::Z, registers.a == 0);
     registers.set_flag(StatusFlag::N, registers.a >= 0x80);
 }
@@ -581,19 +692,20 @@ fn lda_test() {
 /// The program counter and processor status are pushed on the stack then
 /// the IRQ interrupt vector at $FFFE/F is loaded into the PC
 /// and the break flag in the status set to one.
-pub fn brk(registers: &mut Registers, memory: &mut Memory) {
-    registers.pc += 1;
-    memory.stack_push(((registers.pc >> 8) & 0xFF) as u8);
-    memory.stack_push((registers.pc & 0xFF) as u8);
+///
+/// `vector` is normally `BREAK_VECTOR_ADDDRESS`, an NMI occuring before the vector is fetched
+/// (while BRK pushes its state) hijacks it and BRK jumps to the NMI handler instead.
+pub fn brk(registers: &mut Registers, bus: &mut impl Bus, vector: u16) {
+    registers.pc = registers.pc.wrapping_add(1);
+    registers.stack_push(bus, ((registers.pc >> 8) & 0xFF) as u8);
+    registers.stack_push(bus, (registers.pc & 0xFF) as u8);
 
     registers.set_flag(StatusFlag::B, true);
     registers.set_flag(StatusFlag::Unused, true);
-    memory.stack_push(registers.status);
+    registers.stack_push(bus, registers.status);
     registers.set_flag(StatusFlag::I, true);
-    registers.pc = utils::address_from_bytes(
-        memory.memory[utils::BREAK_VECTOR_ADDDRESS as usize],
-        memory.memory[(utils::BREAK_VECTOR_ADDDRESS + 1) as usize],
-    );
+    let vector = hijacked_vector(bus, vector);
+    registers.pc = utils::address_from_bytes(bus.read(vector), bus.read(vector + 1));
     // registers.set_flag(StatusFlag::B, false);
     // registers.set_flag(StatusFlag::Unused, false);
 }
@@ -605,18 +717,69 @@ fn brk_test() {
     memory.memory[utils::BREAK_VECTOR_ADDDRESS as usize] = 0x42;
     memory.memory[(utils::BREAK_VECTOR_ADDDRESS + 1) as usize] = 0x0;
     registers.pc += 1; // Simulate reading insruction
-    brk(&mut registers, &mut memory);
+    brk(
+        &mut registers,
+        &mut memory,
+        utils::BREAK_VECTOR_ADDDRESS as u16,
+    );
     assert_eq!(registers.status, 0b00110100);
     assert_eq!(memory.memory[0x01FE], 2);
     assert_eq!(memory.memory[0x01FF], 0);
     assert_eq!(registers.pc, 0x42);
 }
 
+/// Hardware interrupt (NMI and IRQ)
+///
+/// Same sequence as BRK without the padding byte: the program counter and processor status
+/// are pushed on the stack, the I flag is set and the PC is loaded from `vector`, or from the
+/// NMI vector when an NMI occurs during the pushes.
+/// The status is pushed with the break flag cleared so handlers can tell it apart from BRK.
+pub fn interrupt(registers: &mut Registers, bus: &mut impl Bus, vector: u16) {
+    registers.stack_push(bus, ((registers.pc >> 8) & 0xFF) as u8);
+    registers.stack_push(bus, (registers.pc & 0xFF) as u8);
+
+    let status =
+        (registers.status & !(0x1 << StatusFlag::B as u8)) | 0x1 << StatusFlag::Unused as u8;
+    registers.stack_push(bus, status);
+    registers.set_flag(StatusFlag::I, true);
+    let vector = hijacked_vector(bus, vector);
+    registers.pc = utils::address_from_bytes(bus.read(vector), bus.read(vector + 1));
+}
+
+/// The NMI vector if an NMI is pending once the state is pushed, `vector` otherwise
+fn hijacked_vector(bus: &mut impl Bus, vector: u16) -> u16 {
+    if bus.take_nmi() {
+        utils::NMI_VECTOR_ADDRESS as u16
+    } else {
+        vector
+    }
+}
+
+#[test]
+fn interrupt_test() {
+    let mut registers = Registers::new();
+    let mut memory = Memory::new();
+    memory.memory[utils::NMI_VECTOR_ADDRESS as usize] = 0x34;
+    memory.memory[(utils::NMI_VECTOR_ADDRESS + 1) as usize] = 0x12;
+    registers.pc = 0xC123;
+    registers.status = 0b11010011;
+    interrupt(
+        &mut registers,
+        &mut memory,
+        utils::NMI_VECTOR_ADDRESS as u16,
+    );
+    assert_eq!(memory.memory[0x01FF], 0xC1);
+    assert_eq!(memory.memory[0x01FE], 0x23);
+    assert_eq!(memory.memory[0x01FD], 0b11100011);
+    assert_eq!(registers.status, 0b11010111);
+    assert_eq!(registers.pc, 0x1234);
+}
+
 /// Store Accumulator (STA)
 ///
 /// Stores the contents of the accumulator into memory.
-pub fn sta(registers: &mut Registers, memory: &mut Memory, addr: u16) {
-    memory.memory[addr as usize] = registers.a;
+pub fn sta(registers: &mut Registers, bus: &mut impl Bus, addr: u16) {
+    bus.write(addr, registers.a);
 }
 
 #[test]
@@ -633,15 +796,12 @@ fn sta_test() {
 ///
 /// Adds one to the value held at a specified memory location.
 /// Sets the zero and negative flags as appropriate.
-pub fn inc(registers: &mut Registers, memory: &mut Memory, addr: u16) {
-    let operand = memory.memory[addr as usize] as u16;
-    if operand == 0xFF {
-        memory.memory[addr as usize] = 0;
-    } else {
-        memory.memory[addr as usize] += 1;
-    }
-
-    let operand = memory.memory[addr as usize];
+/// Returns the value written back to memory.
+pub fn inc(registers: &mut Registers, bus: &mut impl Bus, addr: u16) -> u8 {
+    let operand = bus.read(addr);
+    bus.write(addr, operand); // Dummy write of the unmodified value
+    let operand = operand.wrapping_add(1);
+    bus.write(addr, operand);
 
     registers.status = if operand == 0 {
         registers.status | 0b00000010
@@ -653,6 +813,7 @@ pub fn inc(registers: &mut Registers, memory: &mut Memory, addr: u16) {
     } else {
         registers.status & 0b01111111
     };
+    operand
 }
 
 #[test]
@@ -696,20 +857,19 @@ fn ldx_test() {
 /// Transfer X to stack pointer (TXS)
 ///
 /// Copies the current contents of the X register into the stack register.
-pub fn txs(registers: &mut Registers, memory: &mut Memory) {
-    memory.stack_pointer = registers.x as u16;
+pub fn txs(registers: &mut Registers) {
+    registers.s = registers.x;
 }
 
 #[test]
 fn txs_test() {
     let mut registers = Registers::new();
-    let mut memory = Memory::new();
 
     registers.x = 42;
     registers.pc += 1; // Simulate reading insruction
-    txs(&mut registers, &mut memory);
+    txs(&mut registers);
 
-    assert_eq!(memory.stack_pointer, 42);
+    assert_eq!(registers.s, 42);
 }
 
 /// Logical And (AND)
@@ -800,7 +960,7 @@ pub fn cpx(registers: &mut Registers, value: u8) {
     registers.set_flag(StatusFlag::C, false);
     registers.set_flag(StatusFlag::Z, false);
 
-    match registers.x.cmp(&(value as u8)) {
+    match registers.x.cmp(&{ value }) {
         std::cmp::Ordering::Less => {
             // registers.status &= 0b00000000;
         }
@@ -813,7 +973,7 @@ pub fn cpx(registers: &mut Registers, value: u8) {
 
     let res = if value >= 0x80 {
         let value = value as i16 - (1 << 8);
-        (registers.x as i16 - value as i16) as u8
+        (registers.x as i16 - value) as u8
     } else {
         (registers.x as i16 - value as i16) as u8
     };
@@ -916,8 +1076,9 @@ fn bpl_test() {
 ///
 /// Pulls an 8 bit value from the stack and into the accumulator.
 /// The zero and negative flags are set as appropriate.
-pub fn pla(registers: &mut Registers, memory: &mut Memory) {
-    registers.a = memory.stack_pop();
+pub fn pla(registers: &mut Registers, bus: &mut impl Bus) {
+    let _ = bus.read(0x100 | registers.s as u16); // Dummy read while S is incremented
+    registers.a = registers.stack_pop(bus);
 
     registers.set_flag(StatusFlag::Z, registers.a == 0);
     registers.set_flag(StatusFlag::N, registers.a >= 0x80);
@@ -928,18 +1089,18 @@ fn pla_test() {
     let mut registers = Registers::new();
     let mut memory = Memory::new();
 
-    memory.stack_push(0x42);
+    registers.stack_push(&mut memory, 0x42);
     registers.pc += 1; // Simulate reading insruction
     pla(&mut registers, &mut memory);
     assert_eq!(registers.a, 0x42);
-    assert_eq!(memory.stack_pointer, 0x1FF);
+    assert_eq!(registers.s, 0xFF);
 
-    memory.stack_push(0x6F);
+    registers.stack_push(&mut memory, 0x6F);
     registers.status = 0x6F;
     registers.pc += 1; // Simulate reading insruction
     pla(&mut registers, &mut memory);
     assert_eq!(registers.a, 0x6F);
-    assert_eq!(memory.stack_pointer, 0x1FF);
+    assert_eq!(registers.s, 0xFF);
     assert_eq!(registers.status, 0x6D);
 }
 
@@ -977,7 +1138,7 @@ pub fn cpy(registers: &mut Registers, value: u8) {
     registers.set_flag(StatusFlag::C, false);
     registers.set_flag(StatusFlag::Z, false);
 
-    match registers.y.cmp(&(value as u8)) {
+    match registers.y.cmp(&{ value }) {
         std::cmp::Ordering::Less => {
             // registers.status &= 0b00000000;
         }
@@ -990,7 +1151,7 @@ pub fn cpy(registers: &mut Registers, value: u8) {
 
     let res = if value >= 0x80 {
         let value = (value as i32 - (1 << 8)) as i16;
-        (registers.y as i16 - value as i16) as u8
+        (registers.y as i16 - value) as u8
     } else {
         (registers.y as i16 - value as i16) as u8
     };
@@ -1025,7 +1186,7 @@ pub fn bne(registers: &mut Registers, value: u16) -> bool {
     // Check if zero flag is not enabled
     if !registers.is_flag_set(StatusFlag::Z) {
         if value >= 0x80 {
-            let value = (value as i16 - (1 << 8)) as i16;
+            let value = value as i16 - (1 << 8);
             registers.pc = 1 + (registers.pc as i16 + value) as u16;
         } else {
             registers.pc = 1 + (registers.pc as i16 + value as i16) as u16;
@@ -1062,12 +1223,14 @@ fn bne_test() {
 ///
 /// Used at the end of a subroutine to return to the calling routine.
 /// It pulls the program counter (minus one) from the stack.
-pub fn rts(registers: &mut Registers, memory: &mut Memory) {
-    let low = memory.stack_pop();
-    let high = memory.stack_pop();
+pub fn rts(registers: &mut Registers, bus: &mut impl Bus) {
+    let _ = bus.read(0x100 | registers.s as u16); // Dummy read while S is incremented
+    let low = registers.stack_pop(bus);
+    let high = registers.stack_pop(bus);
     let addr = utils::address_from_bytes(low, high);
+    let _ = bus.read(addr); // Dummy read while PC is incremented
     registers.pc = addr;
-    registers.pc += 1;
+    registers.pc = registers.pc.wrapping_add(1);
 }
 
 #[test]
@@ -1075,8 +1238,8 @@ fn rts_test() {
     let mut registers = Registers::new();
     let mut memory = Memory::new();
 
-    memory.stack_push(0x0);
-    memory.stack_push(0x4);
+    registers.stack_push(&mut memory, 0x0);
+    registers.stack_push(&mut memory, 0x4);
 
     registers.pc += 1; // Simulate reading insruction
     rts(&mut registers, &mut memory);
@@ -1102,8 +1265,8 @@ fn jmp_test() {
 /// Store X Register (STX)
 ///
 /// Stores the contents of the X register into memory.
-pub fn stx(registers: &mut Registers, memory: &mut Memory, addr: u16) {
-    memory.memory[addr as usize] = registers.x;
+pub fn stx(registers: &mut Registers, bus: &mut impl Bus, addr: u16) {
+    bus.write(addr, registers.x);
 }
 
 #[test]
@@ -1121,10 +1284,11 @@ fn stx_test() {
 ///
 /// Pushes the address (minus one) of the return point on to the stack.
 /// Sets the program counter to the target memory address.
-pub fn jsr(registers: &mut Registers, memory: &mut Memory, addr: u16) {
-    registers.pc += 1;
-    memory.stack_push(((registers.pc >> 8) & 0xFF) as u8);
-    memory.stack_push((registers.pc & 0xFF) as u8);
+pub fn jsr(registers: &mut Registers, bus: &mut impl Bus, addr: u16) {
+    let _ = bus.read(0x100 | registers.s as u16); // Dummy read while the low byte of the address is buffered
+    registers.pc = registers.pc.wrapping_add(1);
+    registers.stack_push(bus, ((registers.pc >> 8) & 0xFF) as u8);
+    registers.stack_push(bus, (registers.pc & 0xFF) as u8);
     registers.pc = addr;
 }
 
@@ -1212,7 +1376,7 @@ fn clc_test() {
     let mut registers = Registers::new();
     registers.status = 0b1;
     registers.pc += 1; // Simulate reading insruction
-    let _ = clc(&mut registers);
+    clc(&mut registers);
     assert_eq!(registers.status, 0x0);
 }
 
@@ -1258,10 +1422,10 @@ fn bcc_test() {
 /// Push Processor Status (PHP)
 ///
 /// Pushes a copy of the status flags on to the stack.
-pub fn php(registers: &mut Registers, memory: &mut Memory) {
+pub fn php(registers: &mut Registers, bus: &mut impl Bus) {
     registers.set_flag(StatusFlag::B, true);
     registers.set_flag(StatusFlag::Unused, true);
-    memory.stack_push(registers.status);
+    registers.stack_push(bus, registers.status);
     registers.set_flag(StatusFlag::B, false);
     // registers.set_flag(StatusFlag::Unused, false);
 }
@@ -1278,11 +1442,11 @@ fn php_test() {
 
 /// Bit Test (BIT)
 ///
-/// This instructions is used to test if one or more bits are set in a target memory location. 
-/// The mask pattern in A is ANDed with the value in memory to set or clear the zero flag, but the result is not kept. 
+/// This instructions is used to test if one or more bits are set in a target memory location.
+/// The mask pattern in A is ANDed with the value in memory to set or clear the zero flag, but the result is not kept.
 /// Bits 7 and 6 of the value from memory are copied into the N and V flags.
-pub fn bit(registers: &mut Registers, memory: &mut Memory, addr: u16) {
-    let m = memory.memory[addr as usize];
+pub fn bit(registers: &mut Registers, bus: &mut impl Bus, addr: u16) {
+    let m = bus.read(addr);
     let test = registers.a & m;
     if test == 0 {
         registers.set_flag(StatusFlag::Z, true);
@@ -1407,31 +1571,34 @@ fn ldy_test() {
 
 /// Arithmetic Shift Left (ASL)
 ///
-/// This operation shifts all the bits of the memory contents one bit left. 
-/// Bit 0 is set to 0 and bit 7 is placed in the carry flag. 
+/// This operation shifts all the bits of the memory contents one bit left.
+/// Bit 0 is set to 0 and bit 7 is placed in the carry flag.
 /// The effect of this operation is to multiply the memory contents by 2 (ignoring 2's complement considerations), setting the carry if the result will not fit in 8 bits.
-pub fn asl(registers: &mut Registers, memory: &mut Memory, addr: u16, val: u8) {
+/// Returns the value written back to memory.
+pub fn asl(registers: &mut Registers, bus: &mut impl Bus, addr: u16, val: u8) -> u8 {
+    bus.write(addr, val); // Dummy write of the unmodified value
     let mut m = val;
-    let c = (m & 0b10000000) as u8 == 0b10000000;
+    let c = (m & 0b10000000) == 0b10000000;
 
     m <<= 1;
-    memory.memory[addr as usize] = m as u8;
+    bus.write(addr, m);
 
     registers.set_flag(StatusFlag::Z, m == 0);
     registers.set_flag(StatusFlag::N, m >= 0x80);
     registers.set_flag(StatusFlag::C, c);
+    m
 }
 
 /// Arithmetic Shift Left (ASL) with accumulator
 ///
-/// This operation shifts all the bits of the accumulator contents one bit left. 
-/// Bit 0 is set to 0 and bit 7 is placed in the carry flag. 
+/// This operation shifts all the bits of the accumulator contents one bit left.
+/// Bit 0 is set to 0 and bit 7 is placed in the carry flag.
 /// The effect of this operation is to multiply the memory contents by 2 (ignoring 2's complement considerations), setting the carry if the result will not fit in 8 bits.
 pub fn asl_acc(registers: &mut Registers) {
     let mut m = registers.a;
-    let c = (m & 0b10000000) as u8 == 0b10000000;
+    let c = (m & 0b10000000) == 0b10000000;
     m <<= 1;
-    registers.a = m as u8;
+    registers.a = m;
 
     registers.set_flag(StatusFlag::Z, m == 0);
     registers.set_flag(StatusFlag::N, m >= 0x80);
@@ -1451,10 +1618,11 @@ fn asl_test() {
 ///
 /// The RTI instruction is used at the end of an interrupt processing routine.
 /// It pulls the processor flags from the stack followed by the program counter.
-pub fn rti(registers: &mut Registers, memory: &mut Memory) {
-    let status = memory.stack_pop();
-    let pc_lsb = memory.stack_pop();
-    let pc_msb = memory.stack_pop();
+pub fn rti(registers: &mut Registers, bus: &mut impl Bus) {
+    let _ = bus.read(0x100 | registers.s as u16); // Dummy read while S is incremented
+    let status = registers.stack_pop(bus);
+    let pc_lsb = registers.stack_pop(bus);
+    let pc_msb = registers.stack_pop(bus);
     let pc = utils::address_from_bytes(pc_lsb, pc_msb);
 
     let old_registers = registers.clone();
@@ -1473,18 +1641,18 @@ fn rti_test() {
     let mut registers = Registers::new();
     let mut memory = Memory::new();
 
-    memory.stack_push(0x0);
-    memory.stack_push(0x2);
-    memory.stack_push(0b10101010);
+    registers.stack_push(&mut memory, 0x0);
+    registers.stack_push(&mut memory, 0x2);
+    registers.stack_push(&mut memory, 0b10101010);
     registers.pc += 1; // Simulate reading insruction
     rti(&mut registers, &mut memory);
 
     assert_eq!(registers.status, 0b10001010);
     assert_eq!(registers.pc, 0x2);
 
-    memory.stack_push(0xCE);
-    memory.stack_push(0xCE);
-    memory.stack_push(0x87);
+    registers.stack_push(&mut memory, 0xCE);
+    registers.stack_push(&mut memory, 0xCE);
+    registers.stack_push(&mut memory, 0x87);
     registers.pc += 1; // Simulate reading insruction
     rti(&mut registers, &mut memory);
     assert_eq!(registers.status, 0x87);
@@ -1493,7 +1661,7 @@ fn rti_test() {
 
 /// Subtract with Carry (SBC)
 ///
-/// This instruction subtracts the contents of a memory location to the accumulator together with the not of the carry bit. 
+/// This instruction subtracts the contents of a memory location to the accumulator together with the not of the carry bit.
 /// If overflow occurs the carry bit is clear, this enables multiple byte subtraction to be performed.
 pub fn sbc(registers: &mut Registers, value: u8) {
     adc(registers, !value);
@@ -1536,13 +1704,12 @@ fn sed_test() {
 /// Compare (CMP)
 ///
 /// This instruction compares the contents of the accumulator with another memory held value and sets the zero and carry flags as appropriate.
-
 pub fn cmp(registers: &mut Registers, value: u8) {
     registers.set_flag(StatusFlag::N, false);
     registers.set_flag(StatusFlag::C, false);
     registers.set_flag(StatusFlag::Z, false);
 
-    match registers.a.cmp(&(value as u8)) {
+    match registers.a.cmp(&{ value }) {
         std::cmp::Ordering::Less => {
             // registers.status &= 0b00000000;
         }
@@ -1555,7 +1722,7 @@ pub fn cmp(registers: &mut Registers, value: u8) {
 
     let res = if value >= 0x80 {
         let value = (value as i32 - (1 << 8)) as i16;
-        (registers.a as i16 - value as i16) as u8
+        (registers.a as i16 - value) as u8
     } else {
         (registers.a as i16 - value as i16) as u8
     };
@@ -1606,8 +1773,8 @@ fn cmp_test() {
 /// Push Accumulator (PHA)
 ///
 /// Pushes a copy of the accumulator on to the stack.
-pub fn pha(registers: &mut Registers, memory: &mut Memory) {
-    memory.stack_push(registers.a);
+pub fn pha(registers: &mut Registers, bus: &mut impl Bus) {
+    registers.stack_push(bus, registers.a);
 }
 
 #[test]
@@ -1617,16 +1784,17 @@ fn pha_test() {
 
     registers.a = 0x42;
     pha(&mut registers, &mut memory);
-    assert_eq!(memory.stack_pop(), 0x42);
+    assert_eq!(registers.stack_pop(&mut memory), 0x42);
 }
 
 /// Pull Processor Status (PLP)
 ///
-/// Pulls an 8 bit value from the stack and into the processor flags. 
+/// Pulls an 8 bit value from the stack and into the processor flags.
 /// The flags will take on new states as determined by the value pulled.
-pub fn plp(registers: &mut Registers, memory: &mut Memory) {
+pub fn plp(registers: &mut Registers, bus: &mut impl Bus) {
+    let _ = bus.read(0x100 | registers.s as u16); // Dummy read while S is incremented
     let old_registers = registers.clone();
-    registers.status = memory.stack_pop();
+    registers.status = registers.stack_pop(bus);
 
     registers.set_flag(StatusFlag::B, old_registers.is_flag_set(StatusFlag::B));
     registers.set_flag(
@@ -1640,11 +1808,11 @@ fn plp_test() {
     let mut registers = Registers::new();
     let mut memory = Memory::new();
 
-    memory.stack_push(0xFF);
+    registers.stack_push(&mut memory, 0xFF);
     plp(&mut registers, &mut memory);
     assert_eq!(registers.status, 0xCF);
 
-    memory.stack_push(0xFF);
+    registers.stack_push(&mut memory, 0xFF);
     registers.set_flag(StatusFlag::Unused, true);
     plp(&mut registers, &mut memory);
     assert_eq!(registers.status, 0xEF);
@@ -1708,7 +1876,7 @@ fn ora_test() {
 }
 
 /// Clear Overflow Flag (CLV)
-/// 
+///
 /// Clears the overflow flag.
 pub fn clv(registers: &mut Registers) {
     registers.set_flag(StatusFlag::V, false);
@@ -1720,7 +1888,7 @@ fn clv_test() {
     registers.set_flag(StatusFlag::V, true);
     registers.pc += 1; // Simulate reading insruction
     clv(&mut registers);
-    assert_eq!(registers.is_flag_set(StatusFlag::V), false);
+    assert!(!registers.is_flag_set(StatusFlag::V));
 }
 
 /// Exclusive OR (EOR)
@@ -1752,7 +1920,7 @@ fn eor_test() {
 
 /// Add with Carry (ADC)
 ///
-/// This instruction adds the contents of a memory location to the accumulator together with the carry bit. 
+/// This instruction adds the contents of a memory location to the accumulator together with the carry bit.
 /// If overflow occurs the carry bit is set, this enables multiple byte addition to be performed.
 pub fn adc(registers: &mut Registers, value: u8) {
     // ~CARRY
@@ -1826,8 +1994,8 @@ fn adc_test() {
 /// Store Y Register (STY)
 ///
 /// Stores the contents of the Y register into memory.
-pub fn sty(registers: &mut Registers, memory: &mut Memory, addr: u16) {
-    memory.memory[addr as usize] = registers.y;
+pub fn sty(registers: &mut Registers, bus: &mut impl Bus, addr: u16) {
+    bus.write(addr, registers.y);
 }
 
 #[test]
@@ -1972,7 +2140,7 @@ fn tya_test() {
 }
 
 /// Transfer X to Accumulator
-/// 
+///
 /// Copies the current contents of the X register into the accumulator and sets the zero and negative flags as appropriate.
 pub fn txa(registers: &mut Registers) {
     registers.a = registers.x;
@@ -2004,8 +2172,8 @@ fn txa_test() {
 /// Transfer Stack Pointer to X (TSX)
 ///
 /// Copies the current contents of the stack register into the X register and sets the zero and negative flags as appropriate.
-pub fn tsx(registers: &mut Registers, memory: &mut Memory) {
-    registers.x = memory.stack_pointer as u8;
+pub fn tsx(registers: &mut Registers) {
+    registers.x = registers.s;
 
     registers.set_flag(StatusFlag::Z, registers.x == 0);
     registers.set_flag(StatusFlag::N, registers.x >= 0x80);
@@ -2014,11 +2182,10 @@ pub fn tsx(registers: &mut Registers, memory: &mut Memory) {
 #[test]
 fn tsx_test() {
     let mut registers = Registers::new();
-    let mut memory = Memory::new();
 
     registers.pc += 1; // Simulate reading insruction
-    tsx(&mut registers, &mut memory);
-    assert_eq!(registers.x, memory.stack_pointer as u8);
+    tsx(&mut registers);
+    assert_eq!(registers.x, registers.s);
 }
 
 /// Decrement X Register (DEX)
@@ -2045,26 +2212,29 @@ fn dex_test() {
 }
 
 /// Logical Shift Right (LSR)
-/// 
-/// Each of the bits in M is shift one place to the right. 
+///
+/// Each of the bits in M is shift one place to the right.
 /// The bit that was in bit 0 is shifted into the carry flag. Bit 7 is set to zero.
-pub fn lsr(registers: &mut Registers, memory: &mut Memory, addr: u16) {
-    let m = memory.memory[addr as usize];
-    let carry = m as u8 & 0b1 == 0b1;
+/// Returns the value written back to memory.
+pub fn lsr(registers: &mut Registers, bus: &mut impl Bus, addr: u16) -> u8 {
+    let m = bus.read(addr);
+    bus.write(addr, m); // Dummy write of the unmodified value
+    let carry = m & 0b1 == 0b1;
     let m = m >> 1;
-    memory.memory[addr as usize] = m;
+    bus.write(addr, m);
     registers.set_flag(StatusFlag::C, carry);
     registers.set_flag(StatusFlag::Z, m == 0);
     registers.set_flag(StatusFlag::N, m >= 0x80);
+    m
 }
 
 /// Logical Shift Right (LSR) with accumulator
-/// 
-/// Each of the bits in A is shift one place to the right. 
+///
+/// Each of the bits in A is shift one place to the right.
 /// The bit that was in bit 0 is shifted into the carry flag. Bit 7 is set to zero.
 pub fn lsr_acc(registers: &mut Registers) {
     let m = registers.a;
-    let carry = m as u8 & 0b1 == 0b1;
+    let carry = m & 0b1 == 0b1;
     let m = m >> 1;
     registers.a = m;
     registers.set_flag(StatusFlag::C, carry);
@@ -2088,27 +2258,30 @@ fn lsr_test() {
 
 /// Rotate Right (ROR)
 ///
-/// Move each of the bits in either M one place to the right. 
+/// Move each of the bits in either M one place to the right.
 /// Bit 7 is filled with the current value of the carry flag whilst the old bit 0 becomes the new carry flag value.
-pub fn ror(registers: &mut Registers, memory: &mut Memory, addr: u16) {
-    let m = memory.memory[addr as usize];
-    let bit0 = m as u8 & 0b1 == 0b1;
+/// Returns the value written back to memory.
+pub fn ror(registers: &mut Registers, bus: &mut impl Bus, addr: u16) -> u8 {
+    let m = bus.read(addr);
+    bus.write(addr, m); // Dummy write of the unmodified value
+    let bit0 = m & 0b1 == 0b1;
     let mut m = m >> 1;
     let carry = registers.is_flag_set(StatusFlag::C);
     m |= if carry { 1 << 7 } else { 0 };
-    memory.memory[addr as usize] = m;
+    bus.write(addr, m);
     registers.set_flag(StatusFlag::C, bit0);
     registers.set_flag(StatusFlag::Z, m == 0);
     registers.set_flag(StatusFlag::N, m >= 0x80);
+    m
 }
 
 /// Rotate Right (ROR) with accumulator
 ///
-/// Move each of the bits in either A one place to the right. 
+/// Move each of the bits in either A one place to the right.
 /// Bit 7 is filled with the current value of the carry flag whilst the old bit 0 becomes the new carry flag value.
 pub fn ror_acc(registers: &mut Registers) {
     let m = registers.a;
-    let bit0 = m as u8 & 0b1 == 0b1;
+    let bit0 = m & 0b1 == 0b1;
     let mut m = m >> 1;
     let carry = registers.is_flag_set(StatusFlag::C);
     m |= if carry { 1 << 7 } else { 0 };
@@ -2139,27 +2312,30 @@ fn ror_test() {
 
 /// Rotate Left (ROL)
 ///
-/// Move each of the bits in either M one place to the left. 
+/// Move each of the bits in either M one place to the left.
 /// Bit 0 is filled with the current value of the carry flag whilst the old bit 7 becomes the new carry flag value.
-pub fn rol(registers: &mut Registers, memory: &mut Memory, addr: u16, value: u8) {
+/// Returns the value written back to memory.
+pub fn rol(registers: &mut Registers, bus: &mut impl Bus, addr: u16, value: u8) -> u8 {
+    bus.write(addr, value); // Dummy write of the unmodified value
     let m = value;
-    let bit7 = m as u8 & 0b10000000 == 0b10000000;
+    let bit7 = m & 0b10000000 == 0b10000000;
     let mut m = m << 1;
     let carry = registers.is_flag_set(StatusFlag::C);
     m |= if carry { 1 } else { 0 };
-    memory.memory[addr as usize] = m;
+    bus.write(addr, m);
     registers.set_flag(StatusFlag::C, bit7);
     registers.set_flag(StatusFlag::Z, m == 0);
     registers.set_flag(StatusFlag::N, m >= 0x80);
+    m
 }
 
 /// Rotate Left (ROL) with accumulator
 ///
-/// Move each of the bits in either A one place to the left. 
+/// Move each of the bits in either A one place to the left.
 /// Bit 0 is filled with the current value of the carry flag whilst the old bit 7 becomes the new carry flag value.
 pub fn rol_acc(registers: &mut Registers) {
     let m = registers.a;
-    let bit7 = m as u8 & 0b10000000 == 0b10000000;
+    let bit7 = m & 0b10000000 == 0b10000000;
     let mut m = m << 1;
     let carry = registers.is_flag_set(StatusFlag::C);
     m |= if carry { 1 } else { 0 };
@@ -2191,10 +2367,15 @@ fn rol_test() {
 /// Decrement Memory (DEC)
 ///
 /// Subtracts one from the value held at a specified memory location setting the zero and negative flags as appropriate.
-pub fn dec(registers: &mut Registers, memory: &mut Memory, addr: u16) {
-    memory.memory[addr as usize] = memory.memory[addr as usize].wrapping_sub(1);
-    registers.set_flag(StatusFlag::Z, memory.memory[addr as usize] == 0);
-    registers.set_flag(StatusFlag::N, memory.memory[addr as usize] >= 0x80);
+/// Returns the value written back to memory.
+pub fn dec(registers: &mut Registers, bus: &mut impl Bus, addr: u16) -> u8 {
+    let m = bus.read(addr);
+    bus.write(addr, m); // Dummy write of the unmodified value
+    let m = m.wrapping_sub(1);
+    bus.write(addr, m);
+    registers.set_flag(StatusFlag::Z, m == 0);
+    registers.set_flag(StatusFlag::N, m >= 0x80);
+    m
 }
 
 #[test]
@@ -2206,3 +2387,268 @@ fn dec_test() {
     dec(&mut registers, &mut memory, 0x42);
     assert_eq!(memory.memory[0x42], 0x3);
 }
+
+/// AND Byte then Copy N to C (ANC), unofficial
+///
+/// A logical AND is performed on the accumulator, then bit 7 of the result is copied to the carry flag.
+pub fn anc(registers: &mut Registers, value: u8) {
+    and(registers, value);
+    registers.set_flag(StatusFlag::C, registers.a >= 0x80);
+}
+
+#[test]
+fn anc_test() {
+    let mut registers = Registers::new();
+
+    registers.a = 0xF0;
+    anc(&mut registers, 0x9F);
+    assert_eq!(registers.a, 0x90);
+    assert_eq!(registers.status, 0b10000001);
+
+    anc(&mut registers, 0x0F);
+    assert_eq!(registers.a, 0x0);
+    assert_eq!(registers.status, 0b00000010);
+}
+
+/// AND then Logical Shift Right (ALR), unofficial
+///
+/// A logical AND is performed on the accumulator, then the accumulator is shifted one bit right.
+pub fn alr(registers: &mut Registers, value: u8) {
+    and(registers, value);
+    lsr_acc(registers);
+}
+
+#[test]
+fn alr_test() {
+    let mut registers = Registers::new();
+
+    registers.a = 0xFF;
+    alr(&mut registers, 0x03);
+    assert_eq!(registers.a, 0x01);
+    assert_eq!(registers.status, 0b00000001);
+}
+
+/// AND then Rotate Right (ARR), unofficial
+///
+/// A logical AND is performed on the accumulator, then the accumulator is rotated one bit right.
+/// The carry flag is set from bit 6 of the result, and the overflow flag from bit 6 XOR bit 5.
+pub fn arr(registers: &mut Registers, value: u8) {
+    let carry = if registers.is_flag_set(StatusFlag::C) {
+        0x80
+    } else {
+        0x0
+    };
+    registers.a = ((registers.a & value) >> 1) | carry;
+
+    registers.set_flag(StatusFlag::Z, registers.a == 0);
+    registers.set_flag(StatusFlag::N, registers.a >= 0x80);
+    registers.set_flag(StatusFlag::C, registers.a & 0b01000000 != 0);
+    registers.set_flag(
+        StatusFlag::V,
+        ((registers.a >> 6) ^ (registers.a >> 5)) & 0x1 == 0x1,
+    );
+}
+
+#[test]
+fn arr_test() {
+    let mut registers = Registers::new();
+
+    registers.a = 0xFF;
+    registers.set_flag(StatusFlag::C, true);
+    arr(&mut registers, 0xC0);
+    assert_eq!(registers.a, 0xE0);
+    assert_eq!(registers.status, 0b10000001);
+
+    registers.a = 0xFF;
+    registers.status = 0;
+    arr(&mut registers, 0x80);
+    assert_eq!(registers.a, 0x40);
+    assert_eq!(registers.status, 0b01000001);
+}
+
+/// AND X Register with Accumulator then Subtract (AXS), unofficial
+///
+/// X is set to (A AND X) minus the value, without borrow. Flags are set like CMP.
+pub fn axs(registers: &mut Registers, value: u8) {
+    let ax = registers.a & registers.x;
+    registers.x = ax.wrapping_sub(value);
+
+    registers.set_flag(StatusFlag::C, ax >= value);
+    registers.set_flag(StatusFlag::Z, registers.x == 0);
+    registers.set_flag(StatusFlag::N, registers.x >= 0x80);
+}
+
+#[test]
+fn axs_test() {
+    let mut registers = Registers::new();
+
+    registers.a = 0xF3;
+    registers.x = 0x3F;
+    axs(&mut registers, 0x02);
+    assert_eq!(registers.x, 0x31);
+    assert_eq!(registers.status, 0b00000001);
+
+    registers.a = 0x01;
+    registers.x = 0x01;
+    axs(&mut registers, 0x02);
+    assert_eq!(registers.x, 0xFF);
+    assert_eq!(registers.status, 0b10000000);
+}
+
+/// Load Accumulator, X and Stack Pointer (LAS), unofficial
+///
+/// The value in memory is ANDed with the stack pointer, and the result is loaded into A, X and S.
+pub fn las(registers: &mut Registers, value: u8) {
+    let res = value & registers.s;
+    registers.a = res;
+    registers.x = res;
+    registers.s = res;
+
+    registers.set_flag(StatusFlag::Z, res == 0);
+    registers.set_flag(StatusFlag::N, res >= 0x80);
+}
+
+#[test]
+fn las_test() {
+    let mut registers = Registers::new();
+
+    registers.s = 0xF0;
+    las(&mut registers, 0x9F);
+    assert_eq!(registers.a, 0x90);
+    assert_eq!(registers.x, 0x90);
+    assert_eq!(registers.s, 0x90);
+    assert_eq!(registers.status, 0b10000000);
+}
+
+/// Magic constant of XAA, depends on the chip and its temperature, $EE is the most common value
+const XAA_MAGIC: u8 = 0xEE;
+
+/// AND X Register with Accumulator then AND Byte (XAA), unofficial and unstable
+///
+/// The accumulator is set to (A OR magic) AND X AND the value.
+pub fn xaa(registers: &mut Registers, value: u8) {
+    registers.a = (registers.a | XAA_MAGIC) & registers.x & value;
+
+    registers.set_flag(StatusFlag::Z, registers.a == 0);
+    registers.set_flag(StatusFlag::N, registers.a >= 0x80);
+}
+
+#[test]
+fn xaa_test() {
+    let mut registers = Registers::new();
+
+    registers.a = 0x01;
+    registers.x = 0xFF;
+    xaa(&mut registers, 0xFF);
+    assert_eq!(registers.a, 0xEF);
+    assert_eq!(registers.status, 0b10000000);
+}
+
+/// Store shared by SHX, SHY, TAS and AHX
+///
+/// The value is ANDed with the high byte of the base address plus one. When indexing crosses a page,
+/// the high byte of the target address is replaced with the stored value.
+fn store_and_high(bus: &mut impl Bus, base: u16, addr: u16, value: u8) {
+    let value = value & ((base >> 8) as u8).wrapping_add(1);
+    let addr = if (base ^ addr) & 0xFF00 != 0 {
+        (addr & 0x00FF) | (value as u16) << 8
+    } else {
+        addr
+    };
+    bus.write(addr, value);
+}
+
+/// Store X AND High Byte (SHX), unofficial
+///
+/// Stores X ANDed with the high byte of the base address plus one.
+pub fn shx(registers: &mut Registers, bus: &mut impl Bus, base: u16, addr: u16) {
+    store_and_high(bus, base, addr, registers.x);
+}
+
+#[test]
+fn shx_test() {
+    let mut registers = Registers::new();
+    let mut memory = Memory::new();
+
+    registers.x = 0xFF;
+    shx(&mut registers, &mut memory, 0x1200, 0x1234);
+    assert_eq!(memory.memory[0x1234], 0x13);
+
+    // Page crossed, the high byte of the address is corrupted
+    registers.x = 0x05;
+    shx(&mut registers, &mut memory, 0x12FF, 0x1301);
+    assert_eq!(memory.memory[0x0101], 0x01);
+}
+
+/// Store Y AND High Byte (SHY), unofficial
+///
+/// Stores Y ANDed with the high byte of the base address plus one.
+pub fn shy(registers: &mut Registers, bus: &mut impl Bus, base: u16, addr: u16) {
+    store_and_high(bus, base, addr, registers.y);
+}
+
+#[test]
+fn shy_test() {
+    let mut registers = Registers::new();
+    let mut memory = Memory::new();
+
+    registers.y = 0xF3;
+    shy(&mut registers, &mut memory, 0x1200, 0x1234);
+    assert_eq!(memory.memory[0x1234], 0x13);
+}
+
+/// Transfer A AND X to Stack Pointer then Store (TAS), unofficial
+///
+/// S is set to A AND X, then stores S ANDed with the high byte of the base address plus one.
+pub fn tas(registers: &mut Registers, bus: &mut impl Bus, base: u16, addr: u16) {
+    registers.s = registers.a & registers.x;
+    store_and_high(bus, base, addr, registers.s);
+}
+
+#[test]
+fn tas_test() {
+    let mut registers = Registers::new();
+    let mut memory = Memory::new();
+
+    registers.a = 0xF7;
+    registers.x = 0x3F;
+    tas(&mut registers, &mut memory, 0x1200, 0x1234);
+    assert_eq!(registers.s, 0x37);
+    assert_eq!(memory.memory[0x1234], 0x13);
+}
+
+/// Store A AND X AND High Byte (AHX), unofficial
+///
+/// Stores A AND X ANDed with the high byte of the base address plus one.
+pub fn ahx(registers: &mut Registers, bus: &mut impl Bus, base: u16, addr: u16) {
+    store_and_high(bus, base, addr, registers.a & registers.x);
+}
+
+#[test]
+fn ahx_test() {
+    let mut registers = Registers::new();
+    let mut memory = Memory::new();
+
+    registers.a = 0xF7;
+    registers.x = 0x3F;
+    ahx(&mut registers, &mut memory, 0x3000, 0x3034);
+    assert_eq!(memory.memory[0x3034], 0x31);
+}
+
+#[test]
+fn all_opcodes_decoded_test() {
+    let mut unofficial = 0;
+    for opcode in 0..=0xFF {
+        let (instruction, addressing_mode) = match match_instruction(opcode) {
+            Instruction::Official(instr, addr) => (instr, addr),
+            Instruction::Unofficial(instr, addr) => {
+                unofficial += 1;
+                (instr, addr)
+            }
+        };
+
+        // Panics if the cycle count is missing
+        let _ = utils::get_cycles(instruction, addressing_mode, false, false);
+    }
+    assert_eq!(unofficial, 256 - 151);
+}
@@ -3,6 +3,7 @@
 pub mod instructions;
 pub mod utils;
 
+use crate::bus::Bus;
 
 /**
 A struct to represent MOS6502 registers
@@ -37,13 +38,19 @@ pub enum StatusFlag {
     C = 0,
 }
 
+impl Default for Registers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Registers {
     pub fn new() -> Self {
         Self {
             a: 0,
             x: 0,
             y: 0,
-            s: 0,
+            s: 0xFF,
             pc: 0,
             status: 0,
         }
@@ -61,20 +68,215 @@ impl Registers {
         let val = 0x1 << flag as u8;
         self.status & val == val
     }
+
+    /// Pushes a byte on the stack, page $01
+    pub fn stack_push(&mut self, bus: &mut impl Bus, val: u8) {
+        bus.write(0x100 | self.s as u16, val);
+        self.s = self.s.wrapping_sub(1);
+    }
+
+    /// Pops a byte from the stack, page $01
+    #[must_use]
+    pub fn stack_pop(&mut self, bus: &mut impl Bus) -> u8 {
+        self.s = self.s.wrapping_add(1);
+        bus.read(0x100 | self.s as u16)
+    }
+}
+
+/// The two interrupt inputs of the 6502
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    Nmi,
+    Irq,
 }
 
 /**
-Represents a NES memory
+State of the CPU interrupt lines
+
+NMI is edge triggered: the line going active latches a pending NMI, which is serviced
+even if the line is released before the CPU gets to it, and only once per edge.
+IRQ is level triggered: it is serviced as long as the line is held and the I flag is clear.
+*/
+pub struct Interrupts {
+    nmi_line: bool,
+    nmi_pending: bool,
+    irq_line: bool,
+}
+
+impl Default for Interrupts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interrupts {
+    pub fn new() -> Self {
+        Self {
+            nmi_line: false,
+            nmi_pending: false,
+            irq_line: false,
+        }
+    }
+
+    /// Samples the NMI line, latching an NMI on its active edge
+    pub fn set_nmi(&mut self, active: bool) {
+        if active && !self.nmi_line {
+            self.nmi_pending = true;
+        }
+        self.nmi_line = active;
+    }
+
+    /// Samples the IRQ line
+    pub fn set_irq(&mut self, active: bool) {
+        self.irq_line = active;
+    }
+
+    pub fn is_nmi_pending(&self) -> bool {
+        self.nmi_pending
+    }
+
+    /// Acknowledges the pending NMI, returns whether there was one
+    pub fn take_nmi(&mut self) -> bool {
+        let pending = self.nmi_pending;
+        self.nmi_pending = false;
+        pending
+    }
+
+    /// Returns the interrupt to service before the next instruction, NMI has priority over IRQ
+    pub fn poll(&self, irq_disabled: bool) -> Option<Interrupt> {
+        if self.nmi_pending {
+            Some(Interrupt::Nmi)
+        } else if self.irq_line && !irq_disabled {
+            Some(Interrupt::Irq)
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn interrupts_test() {
+    let mut interrupts = Interrupts::new();
+    assert_eq!(interrupts.poll(false), None);
+
+    // NMI only triggers on the edge
+    interrupts.set_nmi(true);
+    assert_eq!(interrupts.poll(true), Some(Interrupt::Nmi));
+    assert!(interrupts.take_nmi());
+    interrupts.set_nmi(true);
+    assert_eq!(interrupts.poll(true), None);
+    interrupts.set_nmi(false);
+    interrupts.set_nmi(true);
+    assert_eq!(interrupts.poll(true), Some(Interrupt::Nmi));
+    assert!(interrupts.take_nmi());
+
+    // IRQ is serviced while held, unless the I flag is set
+    interrupts.set_irq(true);
+    assert_eq!(interrupts.poll(true), None);
+    assert_eq!(interrupts.poll(false), Some(Interrupt::Irq));
+    assert_eq!(interrupts.poll(false), Some(Interrupt::Irq));
+    interrupts.set_irq(false);
+    assert_eq!(interrupts.poll(false), None);
+}
+
+/**
+The bus as seen by the CPU, where every access takes one cycle
+
+The 6502 reads or writes memory on every single cycle, so each access lets the rest of the
+system run for one CPU cycle with `Bus::tick`, then samples the interrupt lines.
+*/
+pub struct CpuBus<'a, B: Bus> {
+    pub bus: &'a mut B,
+    pub interrupts: &'a mut Interrupts,
+    pub cycle: &'a mut usize,
+}
+
+impl<'a, B: Bus> CpuBus<'a, B> {
+    pub fn new(bus: &'a mut B, interrupts: &'a mut Interrupts, cycle: &'a mut usize) -> Self {
+        Self {
+            bus,
+            interrupts,
+            cycle,
+        }
+    }
+
+    /// Spends a cycle without accessing the bus, when the CPU is halted
+    pub fn idle(&mut self) {
+        self.tick();
+    }
+
+    /// Ends the current cycle
+    fn tick(&mut self) {
+        self.bus.tick();
+        *self.cycle += 1;
+
+        self.interrupts.set_nmi(self.bus.nmi());
+        self.interrupts.set_irq(self.bus.irq());
+    }
+}
+
+impl<'a, B: Bus> Bus for CpuBus<'a, B> {
+    fn read(&mut self, addr: u16) -> u8 {
+        let value = self.bus.read(addr);
+        self.tick();
+        value
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        self.bus.write(addr, value);
+        self.tick();
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        self.bus.peek(addr)
+    }
+
+    fn nmi(&self) -> bool {
+        self.bus.nmi()
+    }
+
+    fn irq(&self) -> bool {
+        self.bus.irq()
+    }
+
+    fn take_nmi(&mut self) -> bool {
+        self.interrupts.take_nmi()
+    }
+}
+
+#[test]
+fn cpu_bus_test() {
+    let mut memory = Memory::new();
+    let mut interrupts = Interrupts::new();
+    let mut cycle = 0;
+    let mut bus = CpuBus::new(&mut memory, &mut interrupts, &mut cycle);
+
+    let mut registers = Registers::new();
+    instructions::inc(&mut registers, &mut bus, 0x42);
+    assert_eq!(bus.peek(0x42), 1);
+
+    // Read, dummy write, write
+    assert_eq!(cycle, 3);
+}
+
+/**
+Represents a flat 64KB memory, without any mirroring or memory mapped registers
 
 Zero page    $0000 - $00FF
 Stack    $0100 - $01FF
 General-purpose    $0200 - $FFFF
 
+Used as a simple `Bus` to test instructions, see `crate::bus::NesBus` for the real NES memory map.
 */
 pub struct Memory {
     pub memory: Vec<u8>,
     pub ppu: Vec<u8>,
-    pub stack_pointer: u16,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Memory {
@@ -84,34 +286,35 @@ impl Memory {
         let mut ppu = Vec::new();
         ppu.resize_with(0x4000, || 0);
 
-        Self {
-            memory,
-            ppu,
-            stack_pointer: 0x01FF,
-        }
+        Self { memory, ppu }
     }
+}
 
-    pub fn stack_push(&mut self, val: u8) {
-        self.memory[0x100 | self.stack_pointer as usize] = val;
-        self.stack_pointer -= 1;
+impl Bus for Memory {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.memory[addr as usize]
     }
 
-    #[must_use]
-    pub fn stack_pop(&mut self) -> u8 {
-        self.stack_pointer += 1;
-        self.memory[0x100 | self.stack_pointer as usize]
+    fn write(&mut self, addr: u16, value: u8) {
+        self.memory[addr as usize] = value;
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        self.memory[addr as usize]
     }
 }
 
 #[test]
 fn stack_test() {
+    let mut registers = Registers::new();
     let mut memory = Memory::new();
 
-    memory.stack_push(0x42);
-    assert_eq!(memory.stack_pointer, 0x01FE);
+    registers.stack_push(&mut memory, 0x42);
+    assert_eq!(registers.s, 0xFE);
+    assert_eq!(memory.memory[0x01FF], 0x42);
 
-    let val = memory.stack_pop();
-    assert_eq!(memory.stack_pointer, 0x01FF);
+    let val = registers.stack_pop(&mut memory);
+    assert_eq!(registers.s, 0xFF);
     assert_eq!(val, 0x42);
 }
 
@@ -1,16 +1,21 @@
-use super::{*, instructions::InstructionName};
+use super::{
+    instructions::{InstructionName, Operation},
+    *,
+};
 
 pub fn address_from_bytes(low_byte: u8, high_byte: u8) -> u16 {
     ((high_byte as u16) << 8) | low_byte as u16
 }
 
-// pub const NMI_VECTOR_ADDRESS: u32 = 0xFFFA;
+pub const NMI_VECTOR_ADDRESS: u32 = 0xFFFA;
 pub const RESET_VECTOR_ADDRESS: u32 = 0xFFFC;
 pub const BREAK_VECTOR_ADDDRESS: u32 = 0xFFFE;
+pub const IRQ_VECTOR_ADDRESS: u32 = 0xFFFE; // Shared with BRK
 
-pub fn get_operands(registers: &Registers, memory: &Memory) -> (u8, u8) {
-    let low = memory.memory[(registers.pc + 1) as usize];
-    let high = memory.memory[(registers.pc + 2) as usize];
+/// Reads the two bytes following the opcode, without triggering any bus side effect
+pub fn get_operands(registers: &Registers, bus: &impl Bus) -> (u8, u8) {
+    let low = bus.peek(registers.pc.wrapping_add(1));
+    let high = bus.peek(registers.pc.wrapping_add(2));
     (low, high)
 }
 
@@ -33,313 +38,259 @@ pub fn get_cycles(
     page_crossed: bool,
     branches: bool,
 ) -> u8 {
-    let page_cross = if page_crossed { 1 } else { 0 };
-    match (instruction, addressing_mode) {
-        (InstructionName::SEI, AddressingMode::Implied) => 2,
-        (InstructionName::CLD, AddressingMode::Implied) => 2,
-        (InstructionName::LDA, AddressingMode::Immediate) => 2,
-        (InstructionName::LDA, AddressingMode::Absolute) => 4,
-        (InstructionName::LDA, AddressingMode::ZeroPage) => 3,
-        (InstructionName::LDA, AddressingMode::AbsoluteIndirectWithX) => 4 + page_cross,
-        (InstructionName::LDA, AddressingMode::AbsoluteIndirectWithY) => 4 + page_cross,
-        (InstructionName::LDA, AddressingMode::ZeroPageIndexedWithX) => 4,
-        (InstructionName::LDA, AddressingMode::ZeroPageIndexedIndirect) => 6,
-        (InstructionName::LDA, AddressingMode::ZeroPageIndirectIndexedWithY) => 5 + page_cross,
-        (InstructionName::BRK, AddressingMode::Implied) => 7,
-        (InstructionName::STA, AddressingMode::Absolute) => 4,
-        (InstructionName::STA, AddressingMode::ZeroPage) => 3,
-        (InstructionName::STA, AddressingMode::AbsoluteIndirectWithX) => 5,
-        (InstructionName::STA, AddressingMode::AbsoluteIndirectWithY) => 5,
-        (InstructionName::STA, AddressingMode::ZeroPageIndexedWithX) => 4,
-        (InstructionName::STA, AddressingMode::ZeroPageIndexedIndirect) => 6,
-        (InstructionName::STA, AddressingMode::ZeroPageIndirectIndexedWithY) => 6,
-        (InstructionName::INC, AddressingMode::Absolute) => 6,
-        (InstructionName::INC, AddressingMode::ZeroPage) => 5,
-        (InstructionName::INC, AddressingMode::AbsoluteIndirectWithX) => 7,
-        (InstructionName::INC, AddressingMode::ZeroPageIndexedWithX) => 6,
-        (InstructionName::LDX, AddressingMode::Immediate) => 2,
-        (InstructionName::LDX, AddressingMode::Absolute) => 4,
-        (InstructionName::LDX, AddressingMode::ZeroPage) => 3,
-        (InstructionName::LDX, AddressingMode::AbsoluteIndirectWithY) => 4 + page_cross,
-        (InstructionName::LDX, AddressingMode::ZeroPageIndexedWithY) => 4,
-        (InstructionName::TXS, AddressingMode::Implied) => 2,
-        (InstructionName::AND, AddressingMode::Immediate) => 2,
-        (InstructionName::AND, AddressingMode::Absolute) => 4,
-        (InstructionName::AND, AddressingMode::ZeroPage) => 3,
-        (InstructionName::AND, AddressingMode::AbsoluteIndirectWithX) => 4 + page_cross,
-        (InstructionName::AND, AddressingMode::AbsoluteIndirectWithY) => 4 + page_cross,
-        (InstructionName::AND, AddressingMode::ZeroPageIndexedWithX) => 4,
-        (InstructionName::AND, AddressingMode::ZeroPageIndexedIndirect) => 6,
-        (InstructionName::AND, AddressingMode::ZeroPageIndirectIndexedWithY) => 5 + page_cross,
-        (InstructionName::BEQ, AddressingMode::Relative) => {
-            2 + if branches {
-                if page_crossed {
-                    2
-                } else {
-                    1
-                }
-            } else {
-                0
-            }
-        }
-        (InstructionName::CPX, AddressingMode::Immediate) => 2,
-        (InstructionName::CPX, AddressingMode::Absolute) => 4,
-        (InstructionName::CPX, AddressingMode::ZeroPage) => 3,
-        (InstructionName::DEY, AddressingMode::Implied) => 2,
-        (InstructionName::BPL, AddressingMode::Relative) => {
-            2 + if branches {
-                if page_crossed {
-                    2
-                } else {
-                    1
-                }
-            } else {
-                0
-            }
-        }
-        (InstructionName::PLA, AddressingMode::Implied) => 4,
-        (InstructionName::TAY, AddressingMode::Implied) => 2,
-        (InstructionName::CPY, AddressingMode::Immediate) => 2,
-        (InstructionName::CPY, AddressingMode::Absolute) => 4,
-        (InstructionName::CPY, AddressingMode::ZeroPage) => 3,
-        (InstructionName::BNE, AddressingMode::Relative) => {
-            2 + if branches {
-                if page_crossed {
-                    2
-                } else {
-                    1
-                }
-            } else {
-                0
-            }
-        }
-        (InstructionName::RTS, AddressingMode::Implied) => 6,
-        (InstructionName::JMP, AddressingMode::Absolute) => 3,
-        (InstructionName::JMP, AddressingMode::AbsoluteIndirect) => 5,
-        (InstructionName::STX, AddressingMode::Absolute) => 4,
-        (InstructionName::STX, AddressingMode::ZeroPage) => 3,
-        (InstructionName::STX, AddressingMode::ZeroPageIndexedWithY) => 4,
-        (InstructionName::JSR, AddressingMode::Absolute) => 6,
-        (InstructionName::NOP, AddressingMode::Implied) => 2,
-        (InstructionName::NOP, AddressingMode::Immediate) => 2,
-        (InstructionName::NOP, AddressingMode::Absolute) => 4,
-        (InstructionName::NOP, AddressingMode::AbsoluteIndirectWithX) => 4 + page_cross,
-        (InstructionName::NOP, AddressingMode::ZeroPage) => 3,
-        (InstructionName::NOP, AddressingMode::ZeroPageIndexedWithX) => 4 + page_cross,
-        (InstructionName::SEC, AddressingMode::Implied) => 2,
-        (InstructionName::BCS, AddressingMode::Relative) => {
-            2 + if branches {
-                if page_crossed {
-                    2
-                } else {
-                    1
-                }
-            } else {
-                0
-            }
+    // Taken branches and reads crossing a page need extra cycles to fix the high byte of the address
+    let penalty = match addressing_mode {
+        AddressingMode::Relative if branches => 1 + page_crossed as u8,
+        AddressingMode::AbsoluteIndirectWithX
+        | AddressingMode::AbsoluteIndirectWithY
+        | AddressingMode::ZeroPageIndirectIndexedWithY
+            if page_crossed && instruction.operation() == Operation::Read =>
+        {
+            1
         }
-        (InstructionName::CLC, AddressingMode::Implied) => 2,
-        (InstructionName::BCC, AddressingMode::Relative) => {
-            2 + if branches {
-                if page_crossed {
-                    2
-                } else {
-                    1
-                }
-            } else {
-                0
-            }
-        }
-        (InstructionName::PHP, AddressingMode::Implied) => 3,
-        (InstructionName::BIT, AddressingMode::Absolute) => 4,
-        (InstructionName::BIT, AddressingMode::ZeroPage) => 3,
-        (InstructionName::BVS, AddressingMode::Relative) => {
-            2 + if branches {
-                if page_crossed {
-                    2
-                } else {
-                    1
-                }
-            } else {
-                0
-            }
-        }
-        (InstructionName::BVC, AddressingMode::Relative) => {
-            2 + if branches {
-                if page_crossed {
-                    2
-                } else {
-                    1
-                }
-            } else {
-                0
-            }
-        }
-        (InstructionName::LDY, AddressingMode::Immediate) => 2,
-        (InstructionName::LDY, AddressingMode::Absolute) => 4,
-        (InstructionName::LDY, AddressingMode::ZeroPage) => 3,
-        (InstructionName::LDY, AddressingMode::AbsoluteIndirectWithX) => 4 + page_cross,
-        (InstructionName::LDY, AddressingMode::ZeroPageIndexedWithX) => 4,
-        (InstructionName::ASL, AddressingMode::Accumulator) => 2,
-        (InstructionName::ASL, AddressingMode::Absolute) => 6,
-        (InstructionName::ASL, AddressingMode::ZeroPage) => 5,
-        (InstructionName::ASL, AddressingMode::AbsoluteIndirectWithX) => 7,
-        (InstructionName::ASL, AddressingMode::ZeroPageIndexedWithX) => 6,
-        (InstructionName::RTI, AddressingMode::Implied) => 6,
-        (InstructionName::SBC, AddressingMode::Immediate) => 2,
-        (InstructionName::SBC, AddressingMode::Absolute) => 4,
-        (InstructionName::SBC, AddressingMode::ZeroPage) => 3,
-        (InstructionName::SBC, AddressingMode::AbsoluteIndirectWithX) => 4 + page_cross,
-        (InstructionName::SBC, AddressingMode::AbsoluteIndirectWithY) => 4 + page_cross,
-        (InstructionName::SBC, AddressingMode::ZeroPageIndexedWithX) => 4,
-        (InstructionName::SBC, AddressingMode::ZeroPageIndexedIndirect) => 6,
-        (InstructionName::SBC, AddressingMode::ZeroPageIndirectIndexedWithY) => 5 + page_cross,
-        (InstructionName::SED, AddressingMode::Implied) => 2,
-        (InstructionName::CMP, AddressingMode::Immediate) => 2,
-        (InstructionName::CMP, AddressingMode::Absolute) => 4,
-        (InstructionName::CMP, AddressingMode::ZeroPage) => 3,
-        (InstructionName::CMP, AddressingMode::AbsoluteIndirectWithX) => 4 + page_cross,
-        (InstructionName::CMP, AddressingMode::AbsoluteIndirectWithY) => 4 + page_cross,
-        (InstructionName::CMP, AddressingMode::ZeroPageIndexedWithX) => 4,
-        (InstructionName::CMP, AddressingMode::ZeroPageIndexedIndirect) => 6,
-        (InstructionName::CMP, AddressingMode::ZeroPageIndirectIndexedWithY) => 5 + page_cross,
-        (InstructionName::PHA, AddressingMode::Implied) => 3,
-        (InstructionName::PLP, AddressingMode::Implied) => 4,
-        (InstructionName::BMI, AddressingMode::Relative) => {
-            2 + if branches {
-                if page_crossed {
-                    2
-                } else {
-                    1
-                }
-            } else {
-                0
-            }
+        _ => 0,
+    };
+
+    penalty
+        + match (instruction, addressing_mode) {
+            (InstructionName::SEI, AddressingMode::Implied) => 2,
+            (InstructionName::CLI, AddressingMode::Implied) => 2,
+            (InstructionName::CLD, AddressingMode::Implied) => 2,
+            (InstructionName::LDA, AddressingMode::Immediate) => 2,
+            (InstructionName::LDA, AddressingMode::Absolute) => 4,
+            (InstructionName::LDA, AddressingMode::ZeroPage) => 3,
+            (InstructionName::LDA, AddressingMode::AbsoluteIndirectWithX) => 4,
+            (InstructionName::LDA, AddressingMode::AbsoluteIndirectWithY) => 4,
+            (InstructionName::LDA, AddressingMode::ZeroPageIndexedWithX) => 4,
+            (InstructionName::LDA, AddressingMode::ZeroPageIndexedIndirect) => 6,
+            (InstructionName::LDA, AddressingMode::ZeroPageIndirectIndexedWithY) => 5,
+            (InstructionName::BRK, AddressingMode::Implied) => 7,
+            (InstructionName::STA, AddressingMode::Absolute) => 4,
+            (InstructionName::STA, AddressingMode::ZeroPage) => 3,
+            (InstructionName::STA, AddressingMode::AbsoluteIndirectWithX) => 5,
+            (InstructionName::STA, AddressingMode::AbsoluteIndirectWithY) => 5,
+            (InstructionName::STA, AddressingMode::ZeroPageIndexedWithX) => 4,
+            (InstructionName::STA, AddressingMode::ZeroPageIndexedIndirect) => 6,
+            (InstructionName::STA, AddressingMode::ZeroPageIndirectIndexedWithY) => 6,
+            (InstructionName::INC, AddressingMode::Absolute) => 6,
+            (InstructionName::INC, AddressingMode::ZeroPage) => 5,
+            (InstructionName::INC, AddressingMode::AbsoluteIndirectWithX) => 7,
+            (InstructionName::INC, AddressingMode::ZeroPageIndexedWithX) => 6,
+            (InstructionName::LDX, AddressingMode::Immediate) => 2,
+            (InstructionName::LDX, AddressingMode::Absolute) => 4,
+            (InstructionName::LDX, AddressingMode::ZeroPage) => 3,
+            (InstructionName::LDX, AddressingMode::AbsoluteIndirectWithY) => 4,
+            (InstructionName::LDX, AddressingMode::ZeroPageIndexedWithY) => 4,
+            (InstructionName::TXS, AddressingMode::Implied) => 2,
+            (InstructionName::AND, AddressingMode::Immediate) => 2,
+            (InstructionName::AND, AddressingMode::Absolute) => 4,
+            (InstructionName::AND, AddressingMode::ZeroPage) => 3,
+            (InstructionName::AND, AddressingMode::AbsoluteIndirectWithX) => 4,
+            (InstructionName::AND, AddressingMode::AbsoluteIndirectWithY) => 4,
+            (InstructionName::AND, AddressingMode::ZeroPageIndexedWithX) => 4,
+            (InstructionName::AND, AddressingMode::ZeroPageIndexedIndirect) => 6,
+            (InstructionName::AND, AddressingMode::ZeroPageIndirectIndexedWithY) => 5,
+            (InstructionName::BEQ, AddressingMode::Relative) => 2,
+            (InstructionName::CPX, AddressingMode::Immediate) => 2,
+            (InstructionName::CPX, AddressingMode::Absolute) => 4,
+            (InstructionName::CPX, AddressingMode::ZeroPage) => 3,
+            (InstructionName::DEY, AddressingMode::Implied) => 2,
+            (InstructionName::BPL, AddressingMode::Relative) => 2,
+            (InstructionName::PLA, AddressingMode::Implied) => 4,
+            (InstructionName::TAY, AddressingMode::Implied) => 2,
+            (InstructionName::CPY, AddressingMode::Immediate) => 2,
+            (InstructionName::CPY, AddressingMode::Absolute) => 4,
+            (InstructionName::CPY, AddressingMode::ZeroPage) => 3,
+            (InstructionName::BNE, AddressingMode::Relative) => 2,
+            (InstructionName::RTS, AddressingMode::Implied) => 6,
+            (InstructionName::JMP, AddressingMode::Absolute) => 3,
+            (InstructionName::JMP, AddressingMode::AbsoluteIndirect) => 5,
+            (InstructionName::STX, AddressingMode::Absolute) => 4,
+            (InstructionName::STX, AddressingMode::ZeroPage) => 3,
+            (InstructionName::STX, AddressingMode::ZeroPageIndexedWithY) => 4,
+            (InstructionName::JSR, AddressingMode::Absolute) => 6,
+            (InstructionName::NOP, AddressingMode::Implied) => 2,
+            (InstructionName::NOP, AddressingMode::Immediate) => 2,
+            (InstructionName::NOP, AddressingMode::Absolute) => 4,
+            (InstructionName::NOP, AddressingMode::AbsoluteIndirectWithX) => 4,
+            (InstructionName::NOP, AddressingMode::ZeroPage) => 3,
+            (InstructionName::NOP, AddressingMode::ZeroPageIndexedWithX) => 4,
+            (InstructionName::SEC, AddressingMode::Implied) => 2,
+            (InstructionName::BCS, AddressingMode::Relative) => 2,
+            (InstructionName::CLC, AddressingMode::Implied) => 2,
+            (InstructionName::BCC, AddressingMode::Relative) => 2,
+            (InstructionName::PHP, AddressingMode::Implied) => 3,
+            (InstructionName::BIT, AddressingMode::Absolute) => 4,
+            (InstructionName::BIT, AddressingMode::ZeroPage) => 3,
+            (InstructionName::BVS, AddressingMode::Relative) => 2,
+            (InstructionName::BVC, AddressingMode::Relative) => 2,
+            (InstructionName::LDY, AddressingMode::Immediate) => 2,
+            (InstructionName::LDY, AddressingMode::Absolute) => 4,
+            (InstructionName::LDY, AddressingMode::ZeroPage) => 3,
+            (InstructionName::LDY, AddressingMode::AbsoluteIndirectWithX) => 4,
+            (InstructionName::LDY, AddressingMode::ZeroPageIndexedWithX) => 4,
+            (InstructionName::ASL, AddressingMode::Accumulator) => 2,
+            (InstructionName::ASL, AddressingMode::Absolute) => 6,
+            (InstructionName::ASL, AddressingMode::ZeroPage) => 5,
+            (InstructionName::ASL, AddressingMode::AbsoluteIndirectWithX) => 7,
+            (InstructionName::ASL, AddressingMode::ZeroPageIndexedWithX) => 6,
+            (InstructionName::RTI, AddressingMode::Implied) => 6,
+            (InstructionName::SBC, AddressingMode::Immediate) => 2,
+            (InstructionName::SBC, AddressingMode::Absolute) => 4,
+            (InstructionName::SBC, AddressingMode::ZeroPage) => 3,
+            (InstructionName::SBC, AddressingMode::AbsoluteIndirectWithX) => 4,
+            (InstructionName::SBC, AddressingMode::AbsoluteIndirectWithY) => 4,
+            (InstructionName::SBC, AddressingMode::ZeroPageIndexedWithX) => 4,
+            (InstructionName::SBC, AddressingMode::ZeroPageIndexedIndirect) => 6,
+            (InstructionName::SBC, AddressingMode::ZeroPageIndirectIndexedWithY) => 5,
+            (InstructionName::SED, AddressingMode::Implied) => 2,
+            (InstructionName::CMP, AddressingMode::Immediate) => 2,
+            (InstructionName::CMP, AddressingMode::Absolute) => 4,
+            (InstructionName::CMP, AddressingMode::ZeroPage) => 3,
+            (InstructionName::CMP, AddressingMode::AbsoluteIndirectWithX) => 4,
+            (InstructionName::CMP, AddressingMode::AbsoluteIndirectWithY) => 4,
+            (InstructionName::CMP, AddressingMode::ZeroPageIndexedWithX) => 4,
+            (InstructionName::CMP, AddressingMode::ZeroPageIndexedIndirect) => 6,
+            (InstructionName::CMP, AddressingMode::ZeroPageIndirectIndexedWithY) => 5,
+            (InstructionName::PHA, AddressingMode::Implied) => 3,
+            (InstructionName::PLP, AddressingMode::Implied) => 4,
+            (InstructionName::BMI, AddressingMode::Relative) => 2,
+            (InstructionName::ORA, AddressingMode::Immediate) => 2,
+            (InstructionName::ORA, AddressingMode::Absolute) => 4,
+            (InstructionName::ORA, AddressingMode::ZeroPage) => 3,
+            (InstructionName::ORA, AddressingMode::AbsoluteIndirectWithX) => 4,
+            (InstructionName::ORA, AddressingMode::AbsoluteIndirectWithY) => 4,
+            (InstructionName::ORA, AddressingMode::ZeroPageIndexedWithX) => 4,
+            (InstructionName::ORA, AddressingMode::ZeroPageIndexedIndirect) => 6,
+            (InstructionName::ORA, AddressingMode::ZeroPageIndirectIndexedWithY) => 5,
+            (InstructionName::CLV, AddressingMode::Implied) => 2,
+            (InstructionName::EOR, AddressingMode::Immediate) => 2,
+            (InstructionName::EOR, AddressingMode::Absolute) => 4,
+            (InstructionName::EOR, AddressingMode::ZeroPage) => 3,
+            (InstructionName::EOR, AddressingMode::AbsoluteIndirectWithX) => 4,
+            (InstructionName::EOR, AddressingMode::AbsoluteIndirectWithY) => 4,
+            (InstructionName::EOR, AddressingMode::ZeroPageIndexedWithX) => 4,
+            (InstructionName::EOR, AddressingMode::ZeroPageIndexedIndirect) => 6,
+            (InstructionName::EOR, AddressingMode::ZeroPageIndirectIndexedWithY) => 5,
+            (InstructionName::ADC, AddressingMode::Immediate) => 2,
+            (InstructionName::ADC, AddressingMode::Absolute) => 4,
+            (InstructionName::ADC, AddressingMode::ZeroPage) => 3,
+            (InstructionName::ADC, AddressingMode::AbsoluteIndirectWithX) => 4,
+            (InstructionName::ADC, AddressingMode::AbsoluteIndirectWithY) => 4,
+            (InstructionName::ADC, AddressingMode::ZeroPageIndexedWithX) => 4,
+            (InstructionName::ADC, AddressingMode::ZeroPageIndexedIndirect) => 6,
+            (InstructionName::ADC, AddressingMode::ZeroPageIndirectIndexedWithY) => 5,
+            (InstructionName::STY, AddressingMode::Absolute) => 4,
+            (InstructionName::STY, AddressingMode::ZeroPage) => 3,
+            (InstructionName::STY, AddressingMode::ZeroPageIndexedWithX) => 4,
+            (InstructionName::INY, AddressingMode::Implied) => 2,
+            (InstructionName::INX, AddressingMode::Implied) => 2,
+            (InstructionName::TAX, AddressingMode::Implied) => 2,
+            (InstructionName::TYA, AddressingMode::Implied) => 2,
+            (InstructionName::TXA, AddressingMode::Implied) => 2,
+            (InstructionName::TSX, AddressingMode::Implied) => 2,
+            (InstructionName::DEX, AddressingMode::Implied) => 2,
+            (InstructionName::LSR, AddressingMode::Accumulator) => 2,
+            (InstructionName::LSR, AddressingMode::Absolute) => 6,
+            (InstructionName::LSR, AddressingMode::ZeroPage) => 5,
+            (InstructionName::LSR, AddressingMode::AbsoluteIndirectWithX) => 7,
+            (InstructionName::LSR, AddressingMode::ZeroPageIndexedWithX) => 6,
+            (InstructionName::ROR, AddressingMode::Accumulator) => 2,
+            (InstructionName::ROR, AddressingMode::Absolute) => 6,
+            (InstructionName::ROR, AddressingMode::ZeroPage) => 5,
+            (InstructionName::ROR, AddressingMode::AbsoluteIndirectWithX) => 7,
+            (InstructionName::ROR, AddressingMode::ZeroPageIndexedWithX) => 6,
+            (InstructionName::ROL, AddressingMode::Accumulator) => 2,
+            (InstructionName::ROL, AddressingMode::Absolute) => 6,
+            (InstructionName::ROL, AddressingMode::ZeroPage) => 5,
+            (InstructionName::ROL, AddressingMode::AbsoluteIndirectWithX) => 7,
+            (InstructionName::ROL, AddressingMode::ZeroPageIndexedWithX) => 6,
+            (InstructionName::DEC, AddressingMode::Absolute) => 6,
+            (InstructionName::DEC, AddressingMode::ZeroPage) => 5,
+            (InstructionName::DEC, AddressingMode::AbsoluteIndirectWithX) => 7,
+            (InstructionName::DEC, AddressingMode::ZeroPageIndexedWithX) => 6,
+            (InstructionName::LAX, AddressingMode::Immediate) => 2,
+            (InstructionName::LAX, AddressingMode::Absolute) => 4,
+            (InstructionName::LAX, AddressingMode::ZeroPage) => 3,
+            (InstructionName::LAX, AddressingMode::AbsoluteIndirectWithY) => 4,
+            (InstructionName::LAX, AddressingMode::ZeroPageIndexedWithY) => 4,
+            (InstructionName::LAX, AddressingMode::ZeroPageIndexedIndirect) => 6,
+            (InstructionName::LAX, AddressingMode::ZeroPageIndirectIndexedWithY) => 5,
+            (InstructionName::SAX, AddressingMode::Absolute) => 4,
+            (InstructionName::SAX, AddressingMode::ZeroPage) => 3,
+            (InstructionName::SAX, AddressingMode::ZeroPageIndexedWithY) => 4,
+            (InstructionName::SAX, AddressingMode::ZeroPageIndexedIndirect) => 6,
+            (InstructionName::SAX, AddressingMode::ZeroPageIndirectIndexedWithY) => 6,
+            (InstructionName::DCP, AddressingMode::Absolute) => 6,
+            (InstructionName::DCP, AddressingMode::ZeroPage) => 5,
+            (InstructionName::DCP, AddressingMode::AbsoluteIndirectWithX) => 7,
+            (InstructionName::DCP, AddressingMode::AbsoluteIndirectWithY) => 7,
+            (InstructionName::DCP, AddressingMode::ZeroPageIndexedWithX) => 6,
+            (InstructionName::DCP, AddressingMode::ZeroPageIndexedIndirect) => 8,
+            (InstructionName::DCP, AddressingMode::ZeroPageIndirectIndexedWithY) => 8,
+            (InstructionName::ISB, AddressingMode::Absolute) => 6,
+            (InstructionName::ISB, AddressingMode::ZeroPage) => 5,
+            (InstructionName::ISB, AddressingMode::AbsoluteIndirectWithX) => 7,
+            (InstructionName::ISB, AddressingMode::AbsoluteIndirectWithY) => 7,
+            (InstructionName::ISB, AddressingMode::ZeroPageIndexedWithX) => 6,
+            (InstructionName::ISB, AddressingMode::ZeroPageIndexedIndirect) => 8,
+            (InstructionName::ISB, AddressingMode::ZeroPageIndirectIndexedWithY) => 8,
+            (InstructionName::SLO, AddressingMode::Absolute) => 6,
+            (InstructionName::SLO, AddressingMode::ZeroPage) => 5,
+            (InstructionName::SLO, AddressingMode::AbsoluteIndirectWithX) => 7,
+            (InstructionName::SLO, AddressingMode::AbsoluteIndirectWithY) => 7,
+            (InstructionName::SLO, AddressingMode::ZeroPageIndexedWithX) => 6,
+            (InstructionName::SLO, AddressingMode::ZeroPageIndexedIndirect) => 8,
+            (InstructionName::SLO, AddressingMode::ZeroPageIndirectIndexedWithY) => 8,
+            (InstructionName::RLA, AddressingMode::Absolute) => 6,
+            (InstructionName::RLA, AddressingMode::ZeroPage) => 5,
+            (InstructionName::RLA, AddressingMode::AbsoluteIndirectWithX) => 7,
+            (InstructionName::RLA, AddressingMode::AbsoluteIndirectWithY) => 7,
+            (InstructionName::RLA, AddressingMode::ZeroPageIndexedWithX) => 6,
+            (InstructionName::RLA, AddressingMode::ZeroPageIndexedIndirect) => 8,
+            (InstructionName::RLA, AddressingMode::ZeroPageIndirectIndexedWithY) => 8,
+            (InstructionName::SRE, AddressingMode::Absolute) => 6,
+            (InstructionName::SRE, AddressingMode::ZeroPage) => 5,
+            (InstructionName::SRE, AddressingMode::AbsoluteIndirectWithX) => 7,
+            (InstructionName::SRE, AddressingMode::AbsoluteIndirectWithY) => 7,
+            (InstructionName::SRE, AddressingMode::ZeroPageIndexedWithX) => 6,
+            (InstructionName::SRE, AddressingMode::ZeroPageIndexedIndirect) => 8,
+            (InstructionName::SRE, AddressingMode::ZeroPageIndirectIndexedWithY) => 8,
+            (InstructionName::RRA, AddressingMode::Absolute) => 6,
+            (InstructionName::RRA, AddressingMode::ZeroPage) => 5,
+            (InstructionName::RRA, AddressingMode::AbsoluteIndirectWithX) => 7,
+            (InstructionName::RRA, AddressingMode::AbsoluteIndirectWithY) => 7,
+            (InstructionName::RRA, AddressingMode::ZeroPageIndexedWithX) => 6,
+            (InstructionName::RRA, AddressingMode::ZeroPageIndexedIndirect) => 8,
+            (InstructionName::RRA, AddressingMode::ZeroPageIndirectIndexedWithY) => 8,
+            (InstructionName::ANC, AddressingMode::Immediate) => 2,
+            (InstructionName::ALR, AddressingMode::Immediate) => 2,
+            (InstructionName::ARR, AddressingMode::Immediate) => 2,
+            (InstructionName::AXS, AddressingMode::Immediate) => 2,
+            (InstructionName::LAS, AddressingMode::AbsoluteIndirectWithY) => 4,
+            (InstructionName::SHX, AddressingMode::AbsoluteIndirectWithY) => 5,
+            (InstructionName::SHY, AddressingMode::AbsoluteIndirectWithX) => 5,
+            (InstructionName::TAS, AddressingMode::AbsoluteIndirectWithY) => 5,
+            (InstructionName::AHX, AddressingMode::AbsoluteIndirectWithY) => 5,
+            (InstructionName::AHX, AddressingMode::ZeroPageIndirectIndexedWithY) => 6,
+            (InstructionName::XAA, AddressingMode::Immediate) => 2,
+            // The CPU is halted, only counted so time keeps going
+            (InstructionName::JAM, AddressingMode::Implied) => 2,
+            _ => unreachable!(),
         }
-        (InstructionName::ORA, AddressingMode::Immediate) => 2,
-        (InstructionName::ORA, AddressingMode::Absolute) => 4,
-        (InstructionName::ORA, AddressingMode::ZeroPage) => 3,
-        (InstructionName::ORA, AddressingMode::AbsoluteIndirectWithX) => 4 + page_cross,
-        (InstructionName::ORA, AddressingMode::AbsoluteIndirectWithY) => 4 + page_cross,
-        (InstructionName::ORA, AddressingMode::ZeroPageIndexedWithX) => 4,
-        (InstructionName::ORA, AddressingMode::ZeroPageIndexedIndirect) => 6,
-        (InstructionName::ORA, AddressingMode::ZeroPageIndirectIndexedWithY) => 5 + page_cross,
-        (InstructionName::CLV, AddressingMode::Implied) => 2,
-        (InstructionName::EOR, AddressingMode::Immediate) => 2,
-        (InstructionName::EOR, AddressingMode::Absolute) => 4,
-        (InstructionName::EOR, AddressingMode::ZeroPage) => 3,
-        (InstructionName::EOR, AddressingMode::AbsoluteIndirectWithX) => 4 + page_cross,
-        (InstructionName::EOR, AddressingMode::AbsoluteIndirectWithY) => 4 + page_cross,
-        (InstructionName::EOR, AddressingMode::ZeroPageIndexedWithX) => 4,
-        (InstructionName::EOR, AddressingMode::ZeroPageIndexedIndirect) => 6,
-        (InstructionName::EOR, AddressingMode::ZeroPageIndirectIndexedWithY) => 5 + page_cross,
-        (InstructionName::ADC, AddressingMode::Immediate) => 2,
-        (InstructionName::ADC, AddressingMode::Absolute) => 4,
-        (InstructionName::ADC, AddressingMode::ZeroPage) => 3,
-        (InstructionName::ADC, AddressingMode::AbsoluteIndirectWithX) => 4 + page_cross,
-        (InstructionName::ADC, AddressingMode::AbsoluteIndirectWithY) => 4 + page_cross,
-        (InstructionName::ADC, AddressingMode::ZeroPageIndexedWithX) => 4,
-        (InstructionName::ADC, AddressingMode::ZeroPageIndexedIndirect) => 6,
-        (InstructionName::ADC, AddressingMode::ZeroPageIndirectIndexedWithY) => 5 + page_cross,
-        (InstructionName::STY, AddressingMode::Absolute) => 4,
-        (InstructionName::STY, AddressingMode::ZeroPage) => 3,
-        (InstructionName::STY, AddressingMode::ZeroPageIndexedWithX) => 4,
-        (InstructionName::INY, AddressingMode::Implied) => 2,
-        (InstructionName::INX, AddressingMode::Implied) => 2,
-        (InstructionName::TAX, AddressingMode::Implied) => 2,
-        (InstructionName::TYA, AddressingMode::Implied) => 2,
-        (InstructionName::TXA, AddressingMode::Implied) => 2,
-        (InstructionName::TSX, AddressingMode::Implied) => 2,
-        (InstructionName::DEX, AddressingMode::Implied) => 2,
-        (InstructionName::LSR, AddressingMode::Accumulator) => 2,
-        (InstructionName::LSR, AddressingMode::Absolute) => 6,
-        (InstructionName::LSR, AddressingMode::ZeroPage) => 5,
-        (InstructionName::LSR, AddressingMode::AbsoluteIndirectWithX) => 7,
-        (InstructionName::LSR, AddressingMode::ZeroPageIndexedWithX) => 6,
-        (InstructionName::ROR, AddressingMode::Accumulator) => 2,
-        (InstructionName::ROR, AddressingMode::Absolute) => 6,
-        (InstructionName::ROR, AddressingMode::ZeroPage) => 5,
-        (InstructionName::ROR, AddressingMode::AbsoluteIndirectWithX) => 7,
-        (InstructionName::ROR, AddressingMode::ZeroPageIndexedWithX) => 6,
-        (InstructionName::ROL, AddressingMode::Accumulator) => 2,
-        (InstructionName::ROL, AddressingMode::Absolute) => 6,
-        (InstructionName::ROL, AddressingMode::ZeroPage) => 5,
-        (InstructionName::ROL, AddressingMode::AbsoluteIndirectWithX) => 7,
-        (InstructionName::ROL, AddressingMode::ZeroPageIndexedWithX) => 6,
-        (InstructionName::DEC, AddressingMode::Absolute) => 6,
-        (InstructionName::DEC, AddressingMode::ZeroPage) => 5,
-        (InstructionName::DEC, AddressingMode::AbsoluteIndirectWithX) => 7,
-        (InstructionName::DEC, AddressingMode::ZeroPageIndexedWithX) => 6,
-        (InstructionName::LAX, AddressingMode::Immediate) => 2,
-        (InstructionName::LAX, AddressingMode::Absolute) => 4,
-        (InstructionName::LAX, AddressingMode::ZeroPage) => 3,
-        (InstructionName::LAX, AddressingMode::AbsoluteIndirectWithY) => 4 + page_cross,
-        (InstructionName::LAX, AddressingMode::ZeroPageIndexedWithY) => 4,
-        (InstructionName::LAX, AddressingMode::ZeroPageIndexedIndirect) => 6,
-        (InstructionName::LAX, AddressingMode::ZeroPageIndirectIndexedWithY) => 5 + page_cross,
-        (InstructionName::SAX, AddressingMode::Absolute) => 4,
-        (InstructionName::SAX, AddressingMode::ZeroPage) => 3,
-        (InstructionName::SAX, AddressingMode::ZeroPageIndexedWithY) => 4,
-        (InstructionName::SAX, AddressingMode::ZeroPageIndexedIndirect) => 6,
-        (InstructionName::SAX, AddressingMode::ZeroPageIndirectIndexedWithY) => 6,
-        (InstructionName::DCP, AddressingMode::Absolute) => 6,
-        (InstructionName::DCP, AddressingMode::ZeroPage) => 5,
-        (InstructionName::DCP, AddressingMode::AbsoluteIndirectWithX) => 7,
-        (InstructionName::DCP, AddressingMode::AbsoluteIndirectWithY) => 7,
-        (InstructionName::DCP, AddressingMode::ZeroPageIndexedWithX) => 6,
-        (InstructionName::DCP, AddressingMode::ZeroPageIndexedIndirect) => 8,
-        (InstructionName::DCP, AddressingMode::ZeroPageIndirectIndexedWithY) => 8,
-        (InstructionName::ISB, AddressingMode::Absolute) => 6,
-        (InstructionName::ISB, AddressingMode::ZeroPage) => 5,
-        (InstructionName::ISB, AddressingMode::AbsoluteIndirectWithX) => 7,
-        (InstructionName::ISB, AddressingMode::AbsoluteIndirectWithY) => 7,
-        (InstructionName::ISB, AddressingMode::ZeroPageIndexedWithX) => 6,
-        (InstructionName::ISB, AddressingMode::ZeroPageIndexedIndirect) => 8,
-        (InstructionName::ISB, AddressingMode::ZeroPageIndirectIndexedWithY) => 8,
-        (InstructionName::SLO, AddressingMode::Absolute) => 6,
-        (InstructionName::SLO, AddressingMode::ZeroPage) => 5,
-        (InstructionName::SLO, AddressingMode::AbsoluteIndirectWithX) => 7,
-        (InstructionName::SLO, AddressingMode::AbsoluteIndirectWithY) => 7,
-        (InstructionName::SLO, AddressingMode::ZeroPageIndexedWithX) => 6,
-        (InstructionName::SLO, AddressingMode::ZeroPageIndexedIndirect) => 8,
-        (InstructionName::SLO, AddressingMode::ZeroPageIndirectIndexedWithY) => 8,
-        (InstructionName::RLA, AddressingMode::Absolute) => 6,
-        (InstructionName::RLA, AddressingMode::ZeroPage) => 5,
-        (InstructionName::RLA, AddressingMode::AbsoluteIndirectWithX) => 7,
-        (InstructionName::RLA, AddressingMode::AbsoluteIndirectWithY) => 7,
-        (InstructionName::RLA, AddressingMode::ZeroPageIndexedWithX) => 6,
-        (InstructionName::RLA, AddressingMode::ZeroPageIndexedIndirect) => 8,
-        (InstructionName::RLA, AddressingMode::ZeroPageIndirectIndexedWithY) => 8,
-        (InstructionName::SRE, AddressingMode::Absolute) => 6,
-        (InstructionName::SRE, AddressingMode::ZeroPage) => 5,
-        (InstructionName::SRE, AddressingMode::AbsoluteIndirectWithX) => 7,
-        (InstructionName::SRE, AddressingMode::AbsoluteIndirectWithY) => 7,
-        (InstructionName::SRE, AddressingMode::ZeroPageIndexedWithX) => 6,
-        (InstructionName::SRE, AddressingMode::ZeroPageIndexedIndirect) => 8,
-        (InstructionName::SRE, AddressingMode::ZeroPageIndirectIndexedWithY) => 8,
-        (InstructionName::RRA, AddressingMode::Absolute) => 6,
-        (InstructionName::RRA, AddressingMode::ZeroPage) => 5,
-        (InstructionName::RRA, AddressingMode::AbsoluteIndirectWithX) => 7,
-        (InstructionName::RRA, AddressingMode::AbsoluteIndirectWithY) => 7,
-        (InstructionName::RRA, AddressingMode::ZeroPageIndexedWithX) => 6,
-        (InstructionName::RRA, AddressingMode::ZeroPageIndexedIndirect) => 8,
-        (InstructionName::RRA, AddressingMode::ZeroPageIndirectIndexedWithY) => 8,
-        _ => unreachable!(),
-    }
 }
 
 /**
 Applies addressing mode rules to operands and gives out 16-bit results
  */
 pub fn apply_addressing(
-    memory: &Memory,
+    bus: &impl Bus,
     registers: &Registers,
     adressing_mode: AddressingMode,
     low_byte: u8,
     high_byte: u8,
 ) -> Option<u16> {
-    let memory = &memory.memory;
-    let addr = match adressing_mode {
+    match adressing_mode {
         AddressingMode::Accumulator => None,
         AddressingMode::Implied => None,
         AddressingMode::Immediate => Some(low_byte.into()),
@@ -358,25 +309,22 @@ pub fn apply_addressing(
             if low_byte == 0xFF {
                 let addr2 = address_from_bytes(0x0, high_byte);
 
-                Some(address_from_bytes(
-                    memory[addr as usize],
-                    memory[addr2 as usize],
-                ))
+                Some(address_from_bytes(bus.peek(addr), bus.peek(addr2)))
             } else {
                 let addr2 = addr + 1;
-                let res = address_from_bytes(memory[addr as usize], memory[addr2 as usize]);
+                let res = address_from_bytes(bus.peek(addr), bus.peek(addr2));
                 Some(res)
             }
         }
         AddressingMode::AbsoluteIndirectWithX => {
             let tmp = address_from_bytes(low_byte, high_byte);
-            let addr = tmp.wrapping_add(registers.x.into()) as u16;
-            Some(addr as u16)
+            let addr = tmp.wrapping_add(registers.x.into());
+            Some(addr)
         }
         AddressingMode::AbsoluteIndirectWithY => {
             let tmp = address_from_bytes(low_byte, high_byte);
-            let addr = tmp.wrapping_add(registers.y.into()) as u16;
-            Some(addr as u16)
+            let addr = tmp.wrapping_add(registers.y.into());
+            Some(addr)
         }
         AddressingMode::ZeroPageIndexedWithX => {
             let addr = low_byte.wrapping_add(registers.x);
@@ -388,22 +336,115 @@ pub fn apply_addressing(
         }
         AddressingMode::ZeroPageIndexedIndirect => {
             let base = low_byte.wrapping_add(registers.x);
-            let addr = memory[base as usize];
-            let addr2 = memory[base.wrapping_add(1) as usize];
+            let addr = bus.peek(base as u16);
+            let addr2 = bus.peek(base.wrapping_add(1) as u16);
             let res = address_from_bytes(addr, addr2);
-            Some(res as u16)
+            Some(res)
         }
         AddressingMode::ZeroPageIndirectIndexedWithY => {
             let addr = low_byte;
-            let low_byte = *memory.get(addr as usize).unwrap();
-            let high_byte = *memory.get((addr.wrapping_add(1)) as usize).unwrap();
-            let addr =
-                address_from_bytes(low_byte, high_byte).wrapping_add(registers.y.into()) as u16;
-            Some(addr as u16)
+            let low_byte = bus.peek(addr as u16);
+            let high_byte = bus.peek(addr.wrapping_add(1) as u16);
+            let addr = address_from_bytes(low_byte, high_byte).wrapping_add(registers.y.into());
+            Some(addr)
+        }
+    }
+}
+
+/**
+Fetches the operands of an instruction and computes its effective address, one bus access per cycle
+
+Same results as `apply_addressing` but goes through the bus, dummy reads included.
+Also returns whether indexing crossed a page.
+Expects `registers.pc` to point to the first operand.
+Implied and accumulator modes read the next byte and throw it away.
+Indexed modes read from the address before its high byte is fixed, which reads can skip when no page is crossed.
+ */
+pub fn fetch_address(
+    bus: &mut impl Bus,
+    registers: &Registers,
+    adressing_mode: AddressingMode,
+    operation: Operation,
+) -> (Option<u16>, bool) {
+    let pc = registers.pc;
+
+    let addr = match adressing_mode {
+        AddressingMode::Accumulator | AddressingMode::Implied => {
+            let _ = bus.read(pc);
+            return (None, false);
+        }
+        AddressingMode::Immediate | AddressingMode::Relative => bus.read(pc) as u16,
+        AddressingMode::ZeroPage => bus.read(pc) as u16,
+        AddressingMode::Absolute => {
+            let low_byte = bus.read(pc);
+            let high_byte = bus.read(pc.wrapping_add(1));
+            address_from_bytes(low_byte, high_byte)
+        }
+        AddressingMode::AbsoluteIndirect => {
+            let low_byte = bus.read(pc);
+            let high_byte = bus.read(pc.wrapping_add(1));
+            // NOTE: Same hardware bug as in `apply_addressing`, the pointer never crosses a page
+            let low = bus.read(address_from_bytes(low_byte, high_byte));
+            let high = bus.read(address_from_bytes(low_byte.wrapping_add(1), high_byte));
+            address_from_bytes(low, high)
+        }
+        AddressingMode::AbsoluteIndirectWithX | AddressingMode::AbsoluteIndirectWithY => {
+            let low_byte = bus.read(pc);
+            let high_byte = bus.read(pc.wrapping_add(1));
+            let index = if adressing_mode == AddressingMode::AbsoluteIndirectWithX {
+                registers.x
+            } else {
+                registers.y
+            };
+            return index_address(
+                bus,
+                address_from_bytes(low_byte, high_byte),
+                index,
+                operation,
+            );
+        }
+        AddressingMode::ZeroPageIndexedWithX | AddressingMode::ZeroPageIndexedWithY => {
+            let base = bus.read(pc);
+            let _ = bus.read(base as u16); // Dummy read while the index is added
+            let index = if adressing_mode == AddressingMode::ZeroPageIndexedWithX {
+                registers.x
+            } else {
+                registers.y
+            };
+            base.wrapping_add(index) as u16
+        }
+        AddressingMode::ZeroPageIndexedIndirect => {
+            let base = bus.read(pc);
+            let _ = bus.read(base as u16); // Dummy read while X is added
+            let base = base.wrapping_add(registers.x);
+            let low = bus.read(base as u16);
+            let high = bus.read(base.wrapping_add(1) as u16);
+            address_from_bytes(low, high)
+        }
+        AddressingMode::ZeroPageIndirectIndexedWithY => {
+            let pointer = bus.read(pc);
+            let low = bus.read(pointer as u16);
+            let high = bus.read(pointer.wrapping_add(1) as u16);
+            return index_address(bus, address_from_bytes(low, high), registers.y, operation);
         }
     };
 
-    addr
+    (Some(addr), false)
+}
+
+/// Adds an index to a base address, with the dummy read of the address before the carry reaches its high byte
+fn index_address(
+    bus: &mut impl Bus,
+    base: u16,
+    index: u8,
+    operation: Operation,
+) -> (Option<u16>, bool) {
+    let addr = base.wrapping_add(index as u16);
+    let page_crossed = is_page_crossed(base, addr);
+    if page_crossed || operation != Operation::Read {
+        let _ = bus.read((base & 0xFF00) | (addr & 0x00FF));
+    }
+    (Some(addr), page_crossed)
 }
 
 pub fn num_operands_from_addressing(adressing_mode: &AddressingMode) -> u8 {
@@ -541,3 +582,74 @@ pub fn num_operands_from_addressing(adressing_mode: &AddressingMode) -> u8 {
 //     );
 //     assert_eq!(res, Some(0xC238));
 // }
+
+#[test]
+fn cycles_table_test() {
+    use super::instructions::{match_instruction, Instruction};
+
+    // Base cycle count of every opcode, JAM opcodes are counted as 2
+    #[rustfmt::skip]
+    let cycles: [u8; 256] = [
+        7, 6, 2, 8, 3, 3, 5, 5, 3, 2, 2, 2, 4, 4, 6, 6, // 0x00
+        2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 0x10
+        6, 6, 2, 8, 3, 3, 5, 5, 4, 2, 2, 2, 4, 4, 6, 6, // 0x20
+        2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 0x30
+        6, 6, 2, 8, 3, 3, 5, 5, 3, 2, 2, 2, 3, 4, 6, 6, // 0x40
+        2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 0x50
+        6, 6, 2, 8, 3, 3, 5, 5, 4, 2, 2, 2, 5, 4, 6, 6, // 0x60
+        2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 0x70
+        2, 6, 2, 6, 3, 3, 3, 3, 2, 2, 2, 2, 4, 4, 4, 4, // 0x80
+        2, 6, 2, 6, 4, 4, 4, 4, 2, 5, 2, 5, 5, 5, 5, 5, // 0x90
+        2, 6, 2, 6, 3, 3, 3, 3, 2, 2, 2, 2, 4, 4, 4, 4, // 0xA0
+        2, 5, 2, 5, 4, 4, 4, 4, 2, 4, 2, 4, 4, 4, 4, 4, // 0xB0
+        2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6, // 0xC0
+        2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 0xD0
+        2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6, // 0xE0
+        2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 0xF0
+    ];
+
+    // Reads through abs,X, abs,Y and (zp),Y take one more cycle when crossing a page
+    let page_cross_penalty = [
+        0x11, 0x19, 0x1C, 0x1D, 0x31, 0x39, 0x3C, 0x3D, 0x51, 0x59, 0x5C, 0x5D, 0x71, 0x79, 0x7C,
+        0x7D, 0xB1, 0xB3, 0xB9, 0xBB, 0xBC, 0xBD, 0xBE, 0xBF, 0xD1, 0xD9, 0xDC, 0xDD, 0xF1, 0xF9,
+        0xFC, 0xFD,
+    ];
+
+    for opcode in 0..=0xFF {
+        let (instruction, addressing_mode) = match match_instruction(opcode) {
+            Instruction::Official(instr, addr) => (instr, addr),
+            Instruction::Unofficial(instr, addr) => (instr, addr),
+        };
+        let expected = cycles[opcode as usize];
+
+        assert_eq!(
+            get_cycles(instruction, addressing_mode.clone(), false, false),
+            expected,
+            "opcode {:02X}",
+            opcode
+        );
+
+        if addressing_mode == AddressingMode::Relative {
+            assert_eq!(
+                get_cycles(instruction, addressing_mode.clone(), false, true),
+                expected + 1
+            );
+            assert_eq!(
+                get_cycles(instruction, addressing_mode, true, true),
+                expected + 2
+            );
+        } else {
+            let penalty = if page_cross_penalty.contains(&opcode) {
+                1
+            } else {
+                0
+            };
+            assert_eq!(
+                get_cycles(instruction, addressing_mode, true, false),
+                expected + penalty,
+                "opcode {:02X} crossing a page",
+                opcode
+            );
+        }
+    }
+}
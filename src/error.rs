@@ -0,0 +1,33 @@
+/*!  Errors reported by the emulator */
+
+use std::fmt;
+
+/// Everything that can go wrong while loading or running a ROM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmulatorError {
+    /// The file doesn't start with a valid iNES header, or its header announces no PRG ROM
+    InvalidHeader,
+    /// The file is shorter than the size announced by its header
+    TruncatedRom { expected: usize, actual: usize },
+    /// The cartridge uses a mapper that isn't emulated
+    UnsupportedMapper(u16),
+    /// The CPU executed a JAM opcode and is stuck at this address until reset
+    CpuJammed { pc: u16 },
+}
+
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmulatorError::InvalidHeader => write!(f, "invalid iNES header"),
+            EmulatorError::TruncatedRom { expected, actual } => write!(
+                f,
+                "truncated ROM, expected {} bytes but got {}",
+                expected, actual
+            ),
+            EmulatorError::UnsupportedMapper(mapper) => write!(f, "unsupported mapper {}", mapper),
+            EmulatorError::CpuJammed { pc } => write!(f, "CPU jammed at ${:04X}", pc),
+        }
+    }
+}
+
+impl std::error::Error for EmulatorError {}
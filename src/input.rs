@@ -0,0 +1,100 @@
+/*!  Standard NES controller, read serially through $4016 and $4017 */
+
+/// Buttons of a standard controller, in the order they are reported by the shift register
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Button {
+    A = 0,
+    B = 1,
+    Select = 2,
+    Start = 3,
+    Up = 4,
+    Down = 5,
+    Left = 6,
+    Right = 7,
+}
+
+/**
+A standard controller
+
+Writing 1 to bit 0 of $4016 (strobe) continuously reloads the shift register with the current
+button states. Once strobe goes back to 0, each read returns the next button in bit 0,
+then 1s once all eight buttons have been reported.
+*/
+pub struct Controller {
+    buttons: u8,
+    shift: u8,
+    strobe: bool,
+}
+
+impl Default for Controller {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Controller {
+    pub fn new() -> Self {
+        Self {
+            buttons: 0,
+            shift: 0,
+            strobe: false,
+        }
+    }
+
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        if pressed {
+            self.buttons |= 0x1 << button as u8;
+        } else {
+            self.buttons &= !(0x1 << button as u8);
+        }
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.buttons & (0x1 << button as u8) != 0
+    }
+
+    /// Strobe write, shared by both controllers through $4016
+    pub fn write(&mut self, value: u8) {
+        self.strobe = value & 0x1 == 0x1;
+        if self.strobe {
+            self.shift = self.buttons;
+        }
+    }
+
+    /// Serial read, only bit 0 is driven by the controller
+    pub fn read(&mut self) -> u8 {
+        if self.strobe {
+            return self.buttons & 0x1;
+        }
+
+        let bit = self.shift & 0x1;
+        self.shift = (self.shift >> 1) | 0x80;
+        bit
+    }
+
+    /// Returns the bit the next read would return, without shifting
+    pub fn peek(&self) -> u8 {
+        if self.strobe {
+            self.buttons & 0x1
+        } else {
+            self.shift & 0x1
+        }
+    }
+}
+
+#[test]
+fn controller_read_test() {
+    let mut controller = Controller::new();
+    controller.set_button(Button::A, true);
+    controller.set_button(Button::Start, true);
+    controller.set_button(Button::Right, true);
+
+    controller.write(1);
+    assert_eq!(controller.read(), 1);
+    assert_eq!(controller.read(), 1);
+    controller.write(0);
+
+    let bits: Vec<u8> = (0..10).map(|_| controller.read()).collect();
+    assert_eq!(bits, vec![1, 0, 0, 1, 0, 0, 0, 1, 1, 1]);
+}
@@ -0,0 +1,28 @@
+/*!  Nessy, a NES emulator
+
+The core of the emulator, without any frontend.
+Load a ROM with `RomFile::new`, give it to `Nessy::load`, then call `Nessy::run_frame` for each frame,
+or `Nessy::execute` to step one instruction at a time.
+*/
+
+pub mod apu;
+pub mod blargg;
+pub mod bus;
+pub mod cartridge;
+pub mod cpu;
+pub mod error;
+pub mod input;
+pub mod nes_rom;
+pub mod nessy;
+pub mod ppu;
+
+mod test_cpu;
+mod test_mappers;
+mod test_nestest;
+
+pub use crate::{
+    error::EmulatorError,
+    input::{Button, Controller},
+    nes_rom::RomFile,
+    nessy::{Nessy, Output},
+};
@@ -1,25 +1,33 @@
-use std::{
-    fs::File,
-    io::{BufRead, BufReader, Read},
-};
-mod cpu;
-pub mod nessy;
-mod test_cpu;
-mod test_nestest;
+use std::path::PathBuf;
+
 use bevy::{
-    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    app::AppExit,
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadState, LoadedAsset},
     prelude::*,
-    prelude::{App, IntoSystem},
     reflect::TypeUuid,
+    render::texture::{Extent3d, TextureDimension, TextureFormat},
     DefaultPlugins,
 };
-use cpu::{instructions::*, utils::RESET_VECTOR_ADDRESS, utils::*, Memory, *};
+use nessy_rs::{
+    nes_rom,
+    ppu::{SCREEN_HEIGHT, SCREEN_WIDTH},
+    Button, Nessy,
+};
 
-mod ppu;
-use nes_rom::RomFile;
+/// Window size when the emulator starts, the picture is then scaled by the largest integer that fits
+const INITIAL_SCALE: f32 = 3.0;
 
-use crate::nessy::Nessy;
-mod nes_rom;
+/// Keyboard layout of the first controller
+const KEY_BINDINGS: [(KeyCode, Button); 8] = [
+    (KeyCode::X, Button::A),
+    (KeyCode::Z, Button::B),
+    (KeyCode::RShift, Button::Select),
+    (KeyCode::Return, Button::Start),
+    (KeyCode::Up, Button::Up),
+    (KeyCode::Down, Button::Down),
+    (KeyCode::Left, Button::Left),
+    (KeyCode::Right, Button::Right),
+];
 
 #[derive(TypeUuid)]
 #[uuid = "39cadc56-aa9c-4543-8640-a018b74b5052"]
@@ -38,7 +46,7 @@ impl AssetLoader for NESRomAssetLoader {
     ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
         Box::pin(async move {
             let custom_asset = NESRomAsset {
-                rom: nes_rom::RomFile::new(bytes),
+                rom: nes_rom::RomFile::new(bytes)?,
             };
             load_context.set_default_asset(LoadedAsset::new(custom_asset));
             Ok(())
@@ -50,37 +58,161 @@ impl AssetLoader for NESRomAssetLoader {
     }
 }
 
+/// The console, and the ROM it runs once the asset server has loaded it
+struct Emulator {
+    nessy: Nessy,
+    rom_path: PathBuf,
+    rom: Handle<NESRomAsset>,
+    running: bool,
+}
+
+/// Texture the frame buffer is copied to, displayed by the screen sprite
+struct Screen {
+    texture: Handle<Texture>,
+}
+
 fn main() {
     println!("Nessy 🐉!");
 
     let args: Vec<String> = std::env::args().collect();
-    println!("{:#?}", args);
-
-    let mut nessy = Nessy::new();
-
-    // Load ROM and decode header
-    let nesfile = if args.len() > 1 {
-        let input = std::fs::File::open(&args[1]).unwrap();
-        let mut buffered = BufReader::new(input);
-        let mut rom = Vec::new();
-        buffered.read_to_end(&mut rom).unwrap();
-        let rom = rom.as_slice();
-        nes_rom::RomFile::new(rom)
+
+    let path = if let Some(path) = args.get(1) {
+        path
     } else {
-        panic!("No ROM file provided");
+        eprintln!("No ROM file provided");
+        std::process::exit(1);
     };
 
-    nessy.load(&nesfile);
+    // The asset server resolves relative paths from the assets folder
+    let rom_path = match std::fs::canonicalize(path) {
+        Ok(rom_path) => rom_path,
+        Err(err) => {
+            eprintln!("Couldn't load {}: {}", path, err);
+            std::process::exit(1);
+        }
+    };
 
     App::build()
+        .insert_resource(WindowDescriptor {
+            title: "Nessy".to_string(),
+            width: SCREEN_WIDTH as f32 * INITIAL_SCALE,
+            height: SCREEN_HEIGHT as f32 * INITIAL_SCALE,
+            ..Default::default()
+        })
+        .insert_resource(ClearColor(Color::BLACK))
+        .insert_resource(Emulator {
+            nessy: Nessy::new(),
+            rom_path,
+            rom: Handle::default(),
+            running: false,
+        })
         .add_plugins(DefaultPlugins)
         .add_asset::<NESRomAsset>()
+        .init_asset_loader::<NESRomAssetLoader>()
         .add_startup_system(setup.system())
+        .add_system(load_rom.system())
+        .add_system(read_input.system().label("input"))
+        .add_system(run_emulator.system().after("input"))
+        .add_system(scale_screen.system())
         .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut emulator: ResMut<Emulator>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    emulator.rom = asset_server.load(emulator.rom_path.as_path());
 
-    loop {
-        nessy.execute();
+    let texture = textures.add(Texture::new_fill(
+        Extent3d::new(SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32, 1),
+        TextureDimension::D2,
+        &[0, 0, 0, 0xFF],
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+
+    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+    commands.spawn_bundle(SpriteBundle {
+        material: materials.add(texture.clone().into()),
+        transform: Transform::from_scale(Vec3::new(INITIAL_SCALE, INITIAL_SCALE, 1.0)),
+        ..Default::default()
+    });
+    commands.insert_resource(Screen { texture });
+}
+
+/// Starts the console once the ROM is loaded
+fn load_rom(
+    mut emulator: ResMut<Emulator>,
+    asset_server: Res<AssetServer>,
+    roms: Res<Assets<NESRomAsset>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if emulator.running {
+        return;
+    }
+
+    if asset_server.get_load_state(&emulator.rom) == LoadState::Failed {
+        eprintln!("Couldn't load {}", emulator.rom_path.display());
+        exit.send(AppExit);
+        return;
+    }
+
+    let emulator = &mut *emulator;
+    if let Some(asset) = roms.get(&emulator.rom) {
+        if let Err(err) = emulator.nessy.load(&asset.rom) {
+            eprintln!("Couldn't load {}: {}", emulator.rom_path.display(), err);
+            exit.send(AppExit);
+            return;
+        }
+        emulator.running = true;
+    }
+}
+
+fn read_input(mut emulator: ResMut<Emulator>, keys: Res<Input<KeyCode>>) {
+    let controller = &mut emulator.nessy.bus.controllers[0];
+    for &(key, button) in KEY_BINDINGS.iter() {
+        controller.set_button(button, keys.pressed(key));
+    }
+}
+
+/// Runs one frame of the console per frame of the app, then shows it
+fn run_emulator(
+    mut emulator: ResMut<Emulator>,
+    screen: Res<Screen>,
+    mut textures: ResMut<Assets<Texture>>,
+) {
+    if !emulator.running {
+        return;
+    }
+
+    match emulator.nessy.run_frame() {
+        Ok(output) => {
+            if let Some(texture) = textures.get_mut(&screen.texture) {
+                texture.data.copy_from_slice(output.frame_buffer);
+            }
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            emulator.running = false;
+        }
     }
 }
 
-fn setup() {}
+/// Scales the picture by the largest integer that fits in the window, to keep pixels square
+fn scale_screen(windows: Res<Windows>, mut sprites: Query<&mut Transform, With<Sprite>>) {
+    let window = if let Some(window) = windows.get_primary() {
+        window
+    } else {
+        return;
+    };
+
+    let scale = (window.width() / SCREEN_WIDTH as f32)
+        .min(window.height() / SCREEN_HEIGHT as f32)
+        .floor()
+        .max(1.0);
+    for mut transform in sprites.iter_mut() {
+        transform.scale = Vec3::new(scale, scale, 1.0);
+    }
+}
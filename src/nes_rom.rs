@@ -1,119 +1,319 @@
-use self::mappers::Mapper;
-
-use super::*;
-
-pub mod mappers {
-
-    use super::*;
-
-    pub fn load_rom(memory: &mut cpu::Memory, ppu_memory: &mut ppu::Memory, nesfile: &RomFile) {
-        match nesfile {
-            RomFile::Ines(nesfile, data) => match nesfile.mapper {
-                Mapper::Nrom => {
-                    memory.memory[0x8000..0x8000 + 16384].copy_from_slice(&data[16..16 + 16384]);
-
-                    memory.memory[0xC000..=0xFFFF].copy_from_slice(
-                        &data[(16 + 16384 * (nesfile.num_prgrom - 1) as usize)
-                            ..16 + 16384 * (nesfile.num_prgrom) as usize],
-                    );
-
-                    if nesfile.num_chrrom > 0 {
-                        ppu_memory.memory[0x0000..0x1FFF].copy_from_slice(
-                            &data[(16 + 16384 * (nesfile.num_prgrom as usize) + 1)
-                                ..(16
-                                    + 16384 * (nesfile.num_prgrom as usize)
-                                    + (nesfile.num_chrrom as usize) * 8192)
-                                    as usize],
-                        )
-                    }
-                }
-                Mapper::MMC1 => {
-                    // TODO: handle PRG RAM stuff
-
-                    memory.memory[0x8000..=0xBFFF].copy_from_slice(&data[16..16 + 16384]);
-
-                    memory.memory[0xC000..=0xFFFF].copy_from_slice(
-                        &data[(16 + 16384 * (nesfile.num_prgrom - 1) as usize)
-                            ..16 + 16384 * (nesfile.num_prgrom) as usize],
-                    );
-
-                    if nesfile.num_chrrom > 0 {
-                        ppu_memory.memory[0x0000..0x1FFF].copy_from_slice(
-                            &data[(16 + 16384 * (nesfile.num_prgrom as usize) + 1)
-                                ..(16
-                                    + 16384 * (nesfile.num_prgrom as usize)
-                                    + (nesfile.num_chrrom as usize) * 8192)
-                                    as usize],
-                        )
-                    }
-                }
-                Mapper::Unknown => panic!("Unknown mapper"),
-            },
-            _ => unreachable!(),
-        }
-    }
-    #[repr(u32)]
-    #[derive(PartialEq, Clone, Copy)]
-    pub enum Mapper {
-        Nrom,
-        MMC1,
-        Unknown = u32::MAX,
-    }
-
-    impl From<u8> for Mapper {
-        fn from(from: u8) -> Self {
-            match from {
-                0 => Mapper::Nrom,
-                1 => Mapper::MMC1,
-                _ => Mapper::Unknown,
-            }
-        }
-    }
-}
+use crate::{
+    cartridge::{Mirroring, CHR_RAM_SIZE, PRG_RAM_SIZE},
+    error::EmulatorError,
+};
+
+/// Size of the iNES header
+const HEADER_SIZE: usize = 16;
+/// Size of the optional trainer, between the header and PRG ROM
+const TRAINER_SIZE: usize = 512;
+const PRG_ROM_BANK_SIZE: usize = 16384;
+const CHR_ROM_BANK_SIZE: usize = 8192;
 
 pub enum RomFile {
     Ines(Ines, Vec<u8>),
     Ines2(Ines2, Vec<u8>),
 }
 
-pub struct Ines2 {}
+/// CPU and PPU timing of a NES 2.0 file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timing {
+    Ntsc,
+    Pal,
+    /// The game runs on both NTSC and PAL consoles
+    MultipleRegion,
+    Dendy,
+}
+
+/// Console a NES 2.0 file runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleType {
+    Nes,
+    /// Vs. System arcade boards, with the type of their PPU and of their hardware
+    VsSystem {
+        ppu: u8,
+        hardware: u8,
+    },
+    Playchoice10,
+    /// Clones and other consoles, with the extended console type
+    Extended(u8),
+}
+
+/**
+NES 2.0 header, an iNES header with bits 2-3 of flags 7 set to 0b10
+
+PRG ROM size LSB    $04    In 16KB units
+CHR ROM size LSB    $05    In 8KB units
+Flags 6    $06    NNNNFTBM: mapper bits 0-3, four screen, trainer, battery, vertical mirroring
+Flags 7    $07    NNNN10CC: mapper bits 4-7, NES 2.0 identifier, console type
+Mapper MSB and submapper    $08    SSSSNNNN: submapper, mapper bits 8-11
+ROM sizes MSB    $09    CCCCPPPP: CHR ROM and PRG ROM sizes bits 8-11
+PRG RAM shifts    $0A    NNNNRRRR: PRG NVRAM and PRG RAM are 64 << shift bytes, 0 for none
+CHR RAM shifts    $0B    NNNNRRRR: same for CHR NVRAM and CHR RAM
+Timing    $0C    ......TT: NTSC, PAL, multiple region, Dendy
+System type    $0D    HHHHPPPP: Vs. System hardware and PPU types, or the extended console type
+Misc ROMs    $0E    ......RR: number of ROMs after CHR ROM
+Default expansion device    $0F    ..DDDDDD
+
+With $F as their MSB, ROM sizes are EEEEEEMM in their LSB: (MM * 2 + 1) << EEEEEE bytes.
+*/
+pub struct Ines2 {
+    pub prg_rom_size: usize,
+    pub chr_rom_size: usize,
+    pub mirroring: bool,
+    pub persistent_memory: bool,
+    pub has_trainer: bool,
+    pub four_screen_vram: bool,
+    pub console_type: ConsoleType,
+    pub mapper_number: u16,
+    pub submapper: u8,
+    pub prg_ram_shift: u8,
+    pub prg_nvram_shift: u8,
+    pub chr_ram_shift: u8,
+    pub chr_nvram_shift: u8,
+    pub timing: Timing,
+    pub misc_roms: u8,
+    pub default_expansion: u8,
+}
 
 pub struct Ines {
-    num_prgrom: u8,
-    num_chrrom: u8,
-    mirroring: bool,
-    persistent_memory: bool,
-    has_trainer: bool,
-    four_screen_vram: u8,
-    mapper_lsb: u8,
-    vs: bool,
-    playchoice: bool,
-    mapper_msb: u8,
-    prgram_size: u8,
-    tv_system: bool,
-
-    tv_system2: u8,
-    has_prg_ram: bool,
-    has_bus_conflict: bool,
-    padding: Vec<u8>,
-    mapper: Mapper,
+    pub num_prgrom: u8,
+    pub num_chrrom: u8,
+    pub mirroring: bool,
+    pub persistent_memory: bool,
+    pub has_trainer: bool,
+    pub four_screen_vram: u8,
+    pub mapper_lsb: u8,
+    pub vs: bool,
+    pub playchoice: bool,
+    pub mapper_msb: u8,
+    pub prgram_size: u8,
+    pub tv_system: bool,
+
+    pub tv_system2: u8,
+    pub has_prg_ram: bool,
+    pub has_bus_conflict: bool,
+    pub padding: Vec<u8>,
 }
 #[derive(Debug, PartialEq)]
 pub enum SupportedFormat {
-    ines,
-    unsupported,
+    Ines,
+    Ines2,
+    Unsupported,
+}
+
+impl Ines {
+    /// Mapper number, from the high nibbles of flags 6 and 7
+    pub fn mapper_number(&self) -> u16 {
+        ((self.mapper_msb << 4) | self.mapper_lsb) as u16
+    }
+}
+
+impl Ines2 {
+    fn new(rom: &[u8]) -> Self {
+        let flags6 = rom[6];
+        let flags7 = rom[7];
+
+        let console_type = match flags7 & 0x03 {
+            0 => ConsoleType::Nes,
+            1 => ConsoleType::VsSystem {
+                ppu: rom[13] & 0x0F,
+                hardware: rom[13] >> 4,
+            },
+            2 => ConsoleType::Playchoice10,
+            _ => ConsoleType::Extended(rom[13] & 0x0F),
+        };
+        let timing = match rom[12] & 0x03 {
+            0 => Timing::Ntsc,
+            1 => Timing::Pal,
+            2 => Timing::MultipleRegion,
+            _ => Timing::Dendy,
+        };
+
+        Self {
+            prg_rom_size: Ines2::rom_size(rom[4], rom[9] & 0x0F, PRG_ROM_BANK_SIZE),
+            chr_rom_size: Ines2::rom_size(rom[5], rom[9] >> 4, CHR_ROM_BANK_SIZE),
+            mirroring: flags6 & 0x01 == 0x01,
+            persistent_memory: flags6 & 0x02 == 0x02,
+            has_trainer: flags6 & 0x04 == 0x04,
+            four_screen_vram: flags6 & 0x08 == 0x08,
+            console_type,
+            mapper_number: (flags6 >> 4) as u16
+                | (flags7 & 0xF0) as u16
+                | ((rom[8] & 0x0F) as u16) << 8,
+            submapper: rom[8] >> 4,
+            prg_ram_shift: rom[10] & 0x0F,
+            prg_nvram_shift: rom[10] >> 4,
+            chr_ram_shift: rom[11] & 0x0F,
+            chr_nvram_shift: rom[11] >> 4,
+            timing,
+            misc_roms: rom[14] & 0x03,
+            default_expansion: rom[15] & 0x3F,
+        }
+    }
+
+    /// Size of a ROM in units of `unit`, or in exponent-multiplier notation when `msb` is $F
+    fn rom_size(lsb: u8, msb: u8, unit: usize) -> usize {
+        if msb == 0x0F {
+            let multiplier = (lsb & 0x03) as usize * 2 + 1;
+            1usize
+                .checked_shl((lsb >> 2) as u32)
+                .map_or(usize::MAX, |size| size.saturating_mul(multiplier))
+        } else {
+            ((msb as usize) << 8 | lsb as usize) * unit
+        }
+    }
+
+    /// Size of a RAM from its shift count
+    fn ram_size(shift: u8) -> usize {
+        if shift == 0 {
+            0
+        } else {
+            64 << shift
+        }
+    }
+
+    /// PRG RAM and PRG NVRAM, in bytes
+    pub fn prg_ram_size(&self) -> usize {
+        Ines2::ram_size(self.prg_ram_shift) + Ines2::ram_size(self.prg_nvram_shift)
+    }
+
+    /// CHR RAM and CHR NVRAM, in bytes
+    pub fn chr_ram_size(&self) -> usize {
+        Ines2::ram_size(self.chr_ram_shift) + Ines2::ram_size(self.chr_nvram_shift)
+    }
+
+    /// Size of the file described by the header, without the misc ROMs
+    fn file_size(&self) -> usize {
+        let trainer = if self.has_trainer { TRAINER_SIZE } else { 0 };
+        (HEADER_SIZE + trainer)
+            .saturating_add(self.prg_rom_size)
+            .saturating_add(self.chr_rom_size)
+    }
 }
 
 impl RomFile {
-    pub fn new(rom: &[u8]) -> Self {
-        let nes = &rom[0..4];
+    /// Mapper number, 12 bits with NES 2.0
+    pub fn mapper_number(&self) -> u16 {
+        match self {
+            RomFile::Ines(header, _) => header.mapper_number(),
+            RomFile::Ines2(header, _) => header.mapper_number,
+        }
+    }
+
+    /// Variant of the mapper, always 0 without NES 2.0
+    pub fn submapper(&self) -> u8 {
+        match self {
+            RomFile::Ines(..) => 0,
+            RomFile::Ines2(header, _) => header.submapper,
+        }
+    }
+
+    /// Nametable mirroring wired on the board
+    pub fn mirroring(&self) -> Mirroring {
+        let (vertical, four_screen) = match self {
+            RomFile::Ines(header, _) => (header.mirroring, header.four_screen_vram != 0),
+            RomFile::Ines2(header, _) => (header.mirroring, header.four_screen_vram),
+        };
+
+        if four_screen {
+            Mirroring::FourScreen
+        } else if vertical {
+            Mirroring::Vertical
+        } else {
+            Mirroring::Horizontal
+        }
+    }
+
+    /// Bus conflicts of the discrete boards, submapper 2 of mappers 2, 3 and 7 with NES 2.0
+    pub fn has_bus_conflict(&self) -> bool {
+        match self {
+            RomFile::Ines(header, _) => header.has_bus_conflict,
+            RomFile::Ines2(header, _) => {
+                matches!(header.mapper_number, 2 | 3 | 7) && header.submapper == 2
+            }
+        }
+    }
+
+    /// PRG RAM of the board in bytes, iNES headers give at least 8KB
+    pub fn prg_ram_size(&self) -> usize {
+        match self {
+            RomFile::Ines(header, _) => header.prgram_size as usize * PRG_RAM_SIZE,
+            RomFile::Ines2(header, _) => header.prg_ram_size(),
+        }
+    }
+
+    /// CHR RAM of the board in bytes, iNES headers give 8KB without CHR ROM
+    pub fn chr_ram_size(&self) -> usize {
+        match self {
+            RomFile::Ines(header, _) if header.num_chrrom == 0 => CHR_RAM_SIZE,
+            RomFile::Ines(..) => 0,
+            RomFile::Ines2(header, _) => header.chr_ram_size(),
+        }
+    }
+
+    fn has_trainer(&self) -> bool {
+        match self {
+            RomFile::Ines(header, _) => header.has_trainer,
+            RomFile::Ines2(header, _) => header.has_trainer,
+        }
+    }
+
+    fn prg_rom_size(&self) -> usize {
+        match self {
+            RomFile::Ines(header, _) => header.num_prgrom as usize * PRG_ROM_BANK_SIZE,
+            RomFile::Ines2(header, _) => header.prg_rom_size,
+        }
+    }
+
+    fn chr_rom_size(&self) -> usize {
+        match self {
+            RomFile::Ines(header, _) => header.num_chrrom as usize * CHR_ROM_BANK_SIZE,
+            RomFile::Ines2(header, _) => header.chr_rom_size,
+        }
+    }
+
+    fn data(&self) -> &[u8] {
+        match self {
+            RomFile::Ines(_, data) | RomFile::Ines2(_, data) => data,
+        }
+    }
+
+    /// The 512 bytes loaded at $7000 before the game starts, if any
+    pub fn trainer(&self) -> Option<&[u8]> {
+        if self.has_trainer() {
+            Some(&self.data()[HEADER_SIZE..HEADER_SIZE + TRAINER_SIZE])
+        } else {
+            None
+        }
+    }
+
+    pub fn prg_rom(&self) -> &[u8] {
+        let start = HEADER_SIZE + self.trainer().map_or(0, |trainer| trainer.len());
+        &self.data()[start..start + self.prg_rom_size()]
+    }
 
-        println!("{}{}{}", nes[0] as char, nes[1] as char, nes[2] as char);
+    /// Empty when the cartridge uses CHR RAM
+    pub fn chr_rom(&self) -> &[u8] {
+        let start =
+            HEADER_SIZE + self.trainer().map_or(0, |trainer| trainer.len()) + self.prg_rom().len();
+        &self.data()[start..start + self.chr_rom_size()]
+    }
+
+    /// Decodes the header of a ROM file, fails if it isn't an iNES or NES 2.0 file, if it has no PRG ROM
+    /// or if it's smaller than announced
+    pub fn new(rom: &[u8]) -> Result<Self, EmulatorError> {
+        if rom.len() < HEADER_SIZE {
+            return Err(EmulatorError::InvalidHeader);
+        }
 
         let format = RomFile::get_file_format(rom);
 
-        let file = if format == SupportedFormat::ines {
+        let file = if format == SupportedFormat::Ines {
             let num_prgrom = rom[4];
+            if num_prgrom == 0 {
+                return Err(EmulatorError::InvalidHeader);
+            }
             let num_chrrom = rom[5];
             let flags6 = rom[6];
 
@@ -121,7 +321,7 @@ impl RomFile {
             let persistent_memory = flags6 & 0x2 == 0x2;
             let has_trainer = flags6 & 0x4 == 0x4;
             let four_screen_vram = flags6 & 0x8 & 0x8;
-            let mapper_lsb = flags6 & 0xF0 >> 4;
+            let mapper_lsb = (flags6 & 0xF0) >> 4;
 
             let flags7 = rom[7];
 
@@ -142,7 +342,6 @@ impl RomFile {
             let padding = &rom[11..16];
             // TODO: there are checks to do in padding in some cases
             // TODO: See http://wiki.nesdev.com/w/index.php/INES before variant comparison
-            let mapper = (mapper_msb << 4) | mapper_lsb;
 
             let ines = Ines {
                 num_prgrom,
@@ -161,30 +360,32 @@ impl RomFile {
                 has_prg_ram,
                 has_bus_conflict,
                 padding: padding.to_vec(),
-                mapper: mapper.into(),
             };
 
-            println!("Format {:?}", format);
-            println!("Mapper number {}", mapper);
-            println!(
-                "Num PRG ROM {} ({}KB)",
-                num_prgrom,
-                num_prgrom as u32 * 16384
-            );
-            println!(
-                "Num CHR ROM {} ({}KB)",
-                num_chrrom,
-                num_chrrom as u32 * 8192
-            );
-            println!("Has trainer {}", has_trainer);
-            println!("Has PRG RAM {}", has_prg_ram);
+            let expected = HEADER_SIZE
+                + if has_trainer { TRAINER_SIZE } else { 0 }
+                + num_prgrom as usize * PRG_ROM_BANK_SIZE
+                + num_chrrom as usize * CHR_ROM_BANK_SIZE;
+            if rom.len() < expected {
+                return Err(EmulatorError::TruncatedRom {
+                    expected,
+                    actual: rom.len(),
+                });
+            }
 
             RomFile::Ines(ines, rom.to_vec())
+        } else if format == SupportedFormat::Ines2 {
+            let ines2 = Ines2::new(rom);
+            if ines2.prg_rom_size == 0 {
+                return Err(EmulatorError::InvalidHeader);
+            }
+
+            RomFile::Ines2(ines2, rom.to_vec())
         } else {
-            panic!("Unsupport file type");
+            return Err(EmulatorError::InvalidHeader);
         };
 
-        file
+        Ok(file)
     }
 
     fn get_file_format(header: &[u8]) -> SupportedFormat {
@@ -193,13 +394,130 @@ impl RomFile {
             && header[2] as char == 'S'
             && header[3] == 0x1A; // MS-DOS end of file
 
-        let nes2 = ines_format && (header[7] & 0x0C) == 0x08;
-        // TODO: check proper size of ROM image "size taking into account byte 9 does not exceed the actual size of the ROM image, then NES 2.0."
+        // NES 2.0 sets bits 2-3 of flags 7 to 0b10, and its sizes must fit in the file. Otherwise
+        // the header is read as iNES, byte 9 could be garbage
+        let nes2_format = ines_format
+            && header[7] & 0x0C == 0x08
+            && Ines2::new(header).file_size() <= header.len();
 
-        if ines_format {
-            SupportedFormat::ines
+        if nes2_format {
+            SupportedFormat::Ines2
+        } else if ines_format {
+            SupportedFormat::Ines
         } else {
-            SupportedFormat::unsupported
+            SupportedFormat::Unsupported
         }
     }
 }
+
+#[test]
+fn rom_file_errors_test() {
+    assert_eq!(RomFile::new(&[]).err(), Some(EmulatorError::InvalidHeader));
+    assert_eq!(
+        RomFile::new(b"NOT A NES ROM FILE").err(),
+        Some(EmulatorError::InvalidHeader)
+    );
+
+    let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 2, 1, 0xF0, 0xF0];
+    rom.resize(HEADER_SIZE + 1000, 0);
+    assert_eq!(
+        RomFile::new(&rom).err(),
+        Some(EmulatorError::TruncatedRom {
+            expected: HEADER_SIZE + 2 * PRG_ROM_BANK_SIZE + CHR_ROM_BANK_SIZE,
+            actual: HEADER_SIZE + 1000
+        })
+    );
+
+    rom.resize(HEADER_SIZE + 2 * PRG_ROM_BANK_SIZE + CHR_ROM_BANK_SIZE, 0);
+    let file = RomFile::new(&rom).unwrap();
+    assert_eq!(
+        crate::cartridge::Cartridge::new(&file).err(),
+        Some(EmulatorError::UnsupportedMapper(255))
+    );
+
+    // No PRG ROM, with iNES and NES 2.0 headers
+    let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 0, 1, 0x00, 0x00];
+    rom.resize(HEADER_SIZE + CHR_ROM_BANK_SIZE, 0);
+    assert_eq!(RomFile::new(&rom).err(), Some(EmulatorError::InvalidHeader));
+    rom[7] = 0x08;
+    assert_eq!(RomFile::new(&rom).err(), Some(EmulatorError::InvalidHeader));
+}
+
+#[test]
+fn rom_layout_test() {
+    // One PRG ROM bank, one CHR ROM bank, with a trainer
+    let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 1, 1, 0x04, 0x00];
+    rom.resize(HEADER_SIZE, 0);
+    rom.extend_from_slice(&[0x11; TRAINER_SIZE]);
+    rom.extend_from_slice(&[0x22; PRG_ROM_BANK_SIZE]);
+    rom.extend_from_slice(&[0x33; CHR_ROM_BANK_SIZE]);
+    let file = RomFile::new(&rom).unwrap();
+
+    assert_eq!(file.trainer().unwrap(), &[0x11; TRAINER_SIZE][..]);
+    assert_eq!(file.prg_rom(), &[0x22; PRG_ROM_BANK_SIZE][..]);
+    assert_eq!(file.chr_rom(), &[0x33; CHR_ROM_BANK_SIZE][..]);
+}
+
+#[test]
+fn nes2_header_test() {
+    // Mapper $155 submapper 2, 2MB of PRG ROM in the exponent notation, 3 * 8KB of CHR ROM,
+    // 8KB of PRG NVRAM, 2KB of PRG RAM, 32KB of CHR RAM, Vs. System, Dendy
+    let mut rom = vec![
+        0x4E, 0x45, 0x53, 0x1A, 0x54, 0x03, 0x53, 0x59, 0x21, 0x0F, 0x75, 0x09, 0x03, 0x21, 0x01,
+        0x2A,
+    ];
+    rom.resize(HEADER_SIZE + 0x200000 + 3 * CHR_ROM_BANK_SIZE, 0);
+    let file = RomFile::new(&rom).unwrap();
+
+    match &file {
+        RomFile::Ines2(header, _) => {
+            assert_eq!(header.prg_rom_size, 0x200000);
+            assert_eq!(header.chr_rom_size, 3 * CHR_ROM_BANK_SIZE);
+            assert!(header.persistent_memory);
+            assert_eq!(
+                header.console_type,
+                ConsoleType::VsSystem {
+                    ppu: 1,
+                    hardware: 2
+                }
+            );
+            assert_eq!(header.timing, Timing::Dendy);
+            assert_eq!(header.misc_roms, 1);
+            assert_eq!(header.default_expansion, 0x2A);
+        }
+        RomFile::Ines(..) => panic!("NES 2.0 header decoded as iNES"),
+    }
+    assert_eq!(file.mapper_number(), 0x155);
+    assert_eq!(file.submapper(), 2);
+    assert_eq!(file.mirroring(), Mirroring::Vertical);
+    assert_eq!(file.prg_ram_size(), 0x2000 + 0x800);
+    assert_eq!(file.chr_ram_size(), 0x8000);
+    assert_eq!(file.prg_rom().len(), 0x200000);
+    assert_eq!(file.chr_rom().len(), 3 * CHR_ROM_BANK_SIZE);
+
+    // Sizes larger than the file, the header is iNES with garbage in byte 9
+    rom.truncate(HEADER_SIZE + 0x200000);
+    let file = RomFile::new(&rom).unwrap();
+    assert!(matches!(file, RomFile::Ines(..)));
+    assert_eq!(file.mapper_number(), 0x55);
+    assert_eq!(file.submapper(), 0);
+}
+
+#[test]
+fn nes2_cartridge_test() {
+    // Mapper 185 submapper 5: the CHR ROM is only enabled by the value 1
+    let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 1, 1, 0x90, 0xB8, 0x50, 0x00, 0x00];
+    rom.resize(HEADER_SIZE, 0);
+    rom.extend_from_slice(&[0xFF; PRG_ROM_BANK_SIZE]);
+    rom.extend_from_slice(&[0x42; CHR_ROM_BANK_SIZE]);
+    let file = RomFile::new(&rom).unwrap();
+    let mut cartridge = crate::cartridge::Cartridge::new(&file).unwrap();
+
+    // No PRG RAM in the header
+    assert!(cartridge.memory.prg_ram.is_empty());
+    // 2 enables CHR ROM without a submapper
+    cartridge.cpu_write(0x8000, 0x02);
+    assert_ne!(cartridge.ppu_read(0x0000), 0x42);
+    cartridge.cpu_write(0x8000, 0x01);
+    assert_eq!(cartridge.ppu_read(0x0000), 0x42);
+}
@@ -1,217 +1,258 @@
-use crate::{cpu::{self, AddressingMode, Memory, StatusFlag, instructions::{match_instruction, Instruction, InstructionName, *}, utils::{RESET_VECTOR_ADDRESS, address_from_bytes, apply_addressing, get_cycles, get_operands, is_page_crossed, num_operands_from_addressing}}, nes_rom::{self, RomFile}, ppu::{self, Ctrl, Mask, Status}};
+use crate::{
+    bus::{Bus, NesBus},
+    cartridge::Cartridge,
+    cpu::{
+        self,
+        instructions::{match_instruction, Instruction, InstructionName, *},
+        utils::{
+            address_from_bytes, apply_addressing, fetch_address, get_cycles, get_operands,
+            is_page_crossed, num_operands_from_addressing, BREAK_VECTOR_ADDDRESS,
+            IRQ_VECTOR_ADDRESS, RESET_VECTOR_ADDRESS,
+        },
+        AddressingMode, CpuBus, Interrupt, Interrupts, StatusFlag,
+    },
+    error::EmulatorError,
+    nes_rom::RomFile,
+};
+
+/// What the console produced while running, borrowed until it runs again
+pub struct Output<'a> {
+    /// RGBA pixels of the last complete frame, see `crate::ppu::SCREEN_WIDTH` and `crate::ppu::SCREEN_HEIGHT`
+    pub frame_buffer: &'a [u8],
+    /// Audio samples at `crate::apu::SAMPLE_RATE` produced since the run started
+    pub samples: &'a [f32],
+}
 
 pub struct Nessy {
-    pub memory: Memory,
+    pub bus: NesBus,
     pub registers: cpu::Registers,
-    pub ppu_registers: ppu::Registers,
-    pub ppu_memory: ppu::Memory,
     pub reset_vector: u16,
     pub cycle: usize,
-    pub ppu_cycle: usize,
-    pub frames: usize,
+    pub interrupts: Interrupts,
+    pending_interrupt: Option<Interrupt>, // Interrupt polled during the last instruction, serviced before the next one
+}
+
+impl Default for Nessy {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Nessy {
     #[must_use]
     pub fn new() -> Self {
-        // Initialise memory
-        let mut memory = Memory::new();
+        // Initialise memory, PPU and I/O
+        let mut bus = NesBus::new();
         // Set up registers
         let mut registers = cpu::Registers::new();
 
-        // PPU
-        let ppu_registers = ppu::Registers::new();
-        let ppu_memory = ppu::Memory::new();
-
-        // Get the RESET vector to find start of the game
-        let reset_vector_low = memory.memory[RESET_VECTOR_ADDRESS as usize];
-        let reset_vector_high = memory.memory[(RESET_VECTOR_ADDRESS + 1) as usize];
-
-        let reset_vector = address_from_bytes(reset_vector_low, reset_vector_high);
+        // The RESET vector is only known once a ROM is loaded
+        let reset_vector = 0;
 
-        memory.stack_pointer = 0xFF; // Stack is on page 1 only so 0xFF is actually 0x01FF
+        registers.s = 0xFD; // Stack is on page 1 only so 0xFD is actually 0x01FD
 
-        // Memory
-        memory.memory[0x4017] = 0x00; // Frame IRQ enabled
-        memory.memory[0x4015] = 0x00; // All channels disabled
-        memory.memory[0x4000..0x400F].copy_from_slice(&[0x0; 15]);
-        memory.memory[0x4010..0x4013].copy_from_slice(&[0x0; 3]);
+        // APU
+        bus.write(0x4017, 0x00); // Frame IRQ enabled
+        bus.write(0x4015, 0x00); // All channels disabled
+        for addr in 0x4000..=0x400F {
+            bus.write(addr, 0x0);
+        }
+        for addr in 0x4010..=0x4013 {
+            bus.write(addr, 0x0);
+        }
 
         // Disable decimal mode
         registers.set_flag(StatusFlag::D, false);
 
+        // The reset sequence takes 7 cycles
         let cycle = 7;
-        let ppu_cycle = 21;
-        let frames = 0;
+        for _ in 0..(cycle * 3) {
+            bus.ppu.tick(&mut bus.cartridge);
+        }
 
         Self {
-            memory,
+            bus,
             registers,
-            ppu_registers,
-            ppu_memory,
             reset_vector,
 
             cycle,
-            ppu_cycle,
-            frames,
+            interrupts: Interrupts::new(),
+            pending_interrupt: None,
         }
     }
 
-    pub fn load(&mut self, nesfile: &RomFile) {
-        nes_rom::mappers::load_rom(&mut self.memory, &mut self.ppu_memory, &nesfile);
+    pub fn load(&mut self, nesfile: &RomFile) -> Result<(), EmulatorError> {
+        self.bus.cartridge = Cartridge::new(nesfile)?;
+
+        // Get the RESET vector to find start of the game
+        let reset_vector_low = self.bus.peek(RESET_VECTOR_ADDRESS as u16);
+        let reset_vector_high = self.bus.peek((RESET_VECTOR_ADDRESS + 1) as u16);
+        self.reset_vector = address_from_bytes(reset_vector_low, reset_vector_high);
 
         self.registers.pc = self.reset_vector;
         self.registers.status = 0x34;
+        Ok(())
     }
 
-    pub fn load_nestest(&mut self, nesfile: &RomFile) {
-        nes_rom::mappers::load_rom(&mut self.memory, &mut self.ppu_memory, &nesfile);
+    /// Presses the reset button
+    ///
+    /// The CPU goes through the interrupt sequence with its writes turned into reads,
+    /// so the stack pointer moves down by 3 and nothing is pushed.
+    pub fn reset(&mut self) {
+        let mut bus = CpuBus::new(&mut self.bus, &mut self.interrupts, &mut self.cycle);
+        for _ in 0..5 {
+            bus.idle();
+        }
+        let low = bus.read(RESET_VECTOR_ADDRESS as u16);
+        let high = bus.read((RESET_VECTOR_ADDRESS + 1) as u16);
+        self.reset_vector = address_from_bytes(low, high);
+
+        self.registers.pc = self.reset_vector;
+        self.registers.s = self.registers.s.wrapping_sub(3);
+        self.registers.set_flag(StatusFlag::I, true);
+        self.pending_interrupt = None;
+
+        self.bus.ppu.reset();
+        self.bus.apu.write_register(0x4015, 0x00);
+    }
+
+    pub fn load_nestest(&mut self, nesfile: &RomFile) -> Result<(), EmulatorError> {
+        self.bus.cartridge = Cartridge::new(nesfile)?;
 
         self.registers.pc = 0xC000;
         self.registers.status = 0x24;
+        Ok(())
     }
 
     #[must_use]
     pub fn get_opcode(&self) -> u8 {
-        self.memory.memory[self.registers.pc as usize]
+        self.bus.peek(self.registers.pc)
     }
 
-    pub fn execute(&mut self) {
-        let opcode = self.get_opcode();
+    /// Runs until the PPU finishes drawing the current frame
+    ///
+    /// Stops after the instruction during which the PPU leaves the last visible scanline, the
+    /// rest of that instruction runs in the post-render scanline where nothing is drawn, so the
+    /// frame buffer only holds the picture of this frame.
+    pub fn run_frame(&mut self) -> Result<Output<'_>, EmulatorError> {
+        self.bus.apu.clear_samples();
+
+        loop {
+            let drawing = (self.bus.ppu.scanline as usize) < crate::ppu::SCREEN_HEIGHT;
+            self.execute()?;
+            if drawing && self.bus.ppu.scanline as usize >= crate::ppu::SCREEN_HEIGHT {
+                break;
+            }
+        }
+
+        Ok(self.output())
+    }
+
+    /// Runs for at least `cycles` CPU cycles, stopping at the end of an instruction
+    pub fn run_cycles(&mut self, cycles: usize) -> Result<Output<'_>, EmulatorError> {
+        self.bus.apu.clear_samples();
+
+        let end = self.cycle + cycles;
+        while self.cycle < end {
+            self.execute()?;
+        }
+
+        Ok(self.output())
+    }
+
+    fn output(&self) -> Output<'_> {
+        Output {
+            frame_buffer: &self.bus.ppu.frame_buffer,
+            samples: self.bus.apu.samples(),
+        }
+    }
+
+    /// Executes one instruction, or services a pending interrupt
+    ///
+    /// Fails once the CPU is jammed, further calls keep failing until the console is reset.
+    /// The audio samples keep piling up in the APU, callers running instructions one at a time
+    /// drain them with `bus.apu.clear_samples()`, `run_frame` and `run_cycles` do it themselves.
+    pub fn execute(&mut self) -> Result<(), EmulatorError> {
+        let mut bus = CpuBus::new(&mut self.bus, &mut self.interrupts, &mut self.cycle);
+
+        if self.pending_interrupt.take().is_some() {
+            service_interrupt(&mut self.registers, &mut bus);
+            return Ok(());
+        }
+
+        let start_cycle = *bus.cycle;
+        let opcode = bus.read(self.registers.pc);
         let instruction = match_instruction(opcode);
 
         let (instruction, addressing_mode, _) = match instruction {
             Instruction::Official(instr, addr) => (instr, addr, true),
             Instruction::Unofficial(instr, addr) => (instr, addr, false),
-            Instruction::Unknown => {
-                panic!(
-                    "Unknown opcode {:#x}",
-                    self.memory.memory[(self.registers.pc - 1) as usize]
-                );
-            }
         };
 
         let num_operands = num_operands_from_addressing(&addressing_mode) as u16;
-        let ops = get_operands(&self.registers, &self.memory);
+        let (low_byte, high_byte) = get_operands(&self.registers, &bus);
 
-        let (low_byte, high_byte) = ops;
-        let addr = apply_addressing(
-            &self.memory,
+        self.registers.pc = self.registers.pc.wrapping_add(1); // READ instruction
+
+        let (addr, page_crossed) = fetch_address(
+            &mut bus,
             &self.registers,
             addressing_mode.clone(),
-            low_byte,
-            high_byte,
-        )
-        .unwrap_or(0);
-
-        // RAM MIRORRING AND
-        let mirror_addr = if addr < 0x2000 {
-            // System memory is mirrored
-            addr % 0x0800
-        } else if (0x2000..0x4000).contains(&addr) {
-            // PPU I/O rgisters are mirrored
-            if addr > 0x007 {
-                addr % 0x2008 + 0x2000
-            } else {
-                addr
-            }
-        } else {
-            addr
-        };
-
-        let j_addr = addr;
-        let addr = mirror_addr;
-
-        let page_crossed = match (instruction, addressing_mode.clone()) {
-            (InstructionName::INC, AddressingMode::AbsoluteIndirectWithX)
-            | (InstructionName::INC, AddressingMode::AbsoluteIndirectWithY)
-            | (InstructionName::ADC, AddressingMode::AbsoluteIndirectWithX)
-            | (InstructionName::ADC, AddressingMode::AbsoluteIndirectWithY)
-            | (InstructionName::LDA, AddressingMode::AbsoluteIndirectWithX)
-            | (InstructionName::LDA, AddressingMode::AbsoluteIndirectWithY)
-            | (InstructionName::LDY, AddressingMode::AbsoluteIndirectWithX)
-            | (InstructionName::LDY, AddressingMode::AbsoluteIndirectWithY)
-            | (InstructionName::LDX, AddressingMode::AbsoluteIndirectWithX)
-            | (InstructionName::LDX, AddressingMode::AbsoluteIndirectWithY)
-            | (InstructionName::NOP, AddressingMode::AbsoluteIndirectWithX)
-            | (InstructionName::NOP, AddressingMode::AbsoluteIndirectWithY) => {
-                is_page_crossed(address_from_bytes(low_byte, high_byte), addr)
-            }
-            (InstructionName::ADC, AddressingMode::ZeroPageIndirectIndexedWithY)
-            | (InstructionName::LDA, AddressingMode::ZeroPageIndirectIndexedWithY)
-            | (InstructionName::LDY, AddressingMode::ZeroPageIndirectIndexedWithY)
-            | (InstructionName::LDX, AddressingMode::ZeroPageIndirectIndexedWithY)
-            | (InstructionName::INC, AddressingMode::ZeroPageIndirectIndexedWithY)
-            | (InstructionName::LAX, AddressingMode::ZeroPageIndirectIndexedWithY) => {
-                let low = self.memory.memory[address_from_bytes(low_byte, 0x0) as usize];
-                let high =
-                    self.memory.memory[address_from_bytes(low_byte.wrapping_add(1), 0x0) as usize];
-
-                is_page_crossed(address_from_bytes(low, high) as u16, addr)
-            }
-            (_, AddressingMode::Relative) => is_page_crossed(
-                self.registers.pc + 2, /* Include pc++ and operand read */
-                (self.registers.pc as i16
-                    + 2
-                    + if addr >= 0x80 {
-                        (addr as i32 - (1 << 8)) as i16
-                    } else {
-                        addr as i16
-                    }) as u16,
-            ),
-            (_, AddressingMode::Absolute) => is_page_crossed(
-                self.registers.pc + 2, /* Include pc++ and operand read */
-                addr,
-            ),
-            _ => false,
-        };
-
-        self.registers.pc += 1; // READ instruction
+            instruction.operation(),
+        );
+        let addr = addr.unwrap_or(0);
+        let next_pc = self.registers.pc.wrapping_add(num_operands);
 
         let mut branched = false;
+        // CLI, SEI and PLP change the I flag after the interrupt lines are polled
+        let irq_disabled = self.registers.is_flag_set(StatusFlag::I);
 
         match instruction {
             InstructionName::SEI => {
                 sei(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
+            }
+            InstructionName::CLI => {
+                cli(&mut self.registers);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::CLD => {
                 cld(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::LDA => {
                 let data = if addressing_mode == AddressingMode::Immediate {
                     addr as u8
                 } else {
-                    self.memory.memory[addr as usize]
+                    bus.read(addr)
                 };
 
                 lda(&mut self.registers, data);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::BRK => {
-                brk(&mut self.registers, &mut self.memory);
+                brk(&mut self.registers, &mut bus, BREAK_VECTOR_ADDDRESS as u16);
             }
             InstructionName::STA => {
-                sta(&mut self.registers, &mut self.memory, addr);
-                self.registers.pc += num_operands;
+                sta(&mut self.registers, &mut bus, addr);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::INC => {
-                inc(&mut self.registers, &mut self.memory, addr);
-                self.registers.pc += num_operands;
+                inc(&mut self.registers, &mut bus, addr);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::LDX => {
                 let data = if addressing_mode == AddressingMode::Immediate {
                     addr as u8
                 } else {
-                    self.memory.memory[addr as usize]
+                    bus.read(addr)
                 };
                 ldx(&mut self.registers, data.into());
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::TXS => {
-                txs(&mut self.registers, &mut self.memory);
-                self.registers.pc += num_operands;
+                txs(&mut self.registers);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::AND => {
                 if addressing_mode == AddressingMode::Accumulator {
@@ -220,16 +261,16 @@ impl Nessy {
                     let data = if addressing_mode == AddressingMode::Immediate {
                         addr as u8
                     } else {
-                        self.memory.memory[addr as usize]
+                        bus.read(addr)
                     };
                     and(&mut self.registers, data);
                 }
 
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::BEQ => {
                 if !beq(&mut self.registers, addr) {
-                    self.registers.pc += num_operands;
+                    self.registers.pc = self.registers.pc.wrapping_add(num_operands);
                 } else {
                     branched = true;
                 }
@@ -238,103 +279,109 @@ impl Nessy {
                 let data = if addressing_mode == AddressingMode::Immediate {
                     addr as u8
                 } else {
-                    self.memory.memory[addr as usize]
+                    bus.read(addr)
                 };
                 cpx(&mut self.registers, data);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::DEY => {
                 dey(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::BPL => {
                 if !bpl(&mut self.registers, addr) {
-                    self.registers.pc += num_operands;
+                    self.registers.pc = self.registers.pc.wrapping_add(num_operands);
                 } else {
                     branched = true;
                 }
             }
             InstructionName::PLA => {
-                pla(&mut self.registers, &mut self.memory);
-                self.registers.pc += num_operands;
+                pla(&mut self.registers, &mut bus);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::TAY => {
                 tay(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::CPY => {
                 let data = if addressing_mode == AddressingMode::Immediate {
                     addr as u8
                 } else {
-                    self.memory.memory[addr as usize]
+                    bus.read(addr)
                 };
                 cpy(&mut self.registers, data);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::BNE => {
                 if !bne(&mut self.registers, addr) {
-                    self.registers.pc += num_operands;
+                    self.registers.pc = self.registers.pc.wrapping_add(num_operands);
                 } else {
                     branched = true;
                 }
             }
             InstructionName::RTS => {
-                rts(&mut self.registers, &mut self.memory);
+                rts(&mut self.registers, &mut bus);
             }
             InstructionName::JMP => {
-                jmp(&mut self.registers, j_addr);
+                jmp(&mut self.registers, addr);
             }
             InstructionName::STX => {
-                stx(&mut self.registers, &mut self.memory, addr);
-                self.registers.pc += num_operands;
+                stx(&mut self.registers, &mut bus, addr);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::JSR => {
-                jsr(&mut self.registers, &mut self.memory, j_addr);
+                jsr(&mut self.registers, &mut bus, addr);
             }
             InstructionName::NOP => {
+                // Unofficial NOPs with an operand still read it
+                if addressing_mode != AddressingMode::Implied
+                    && addressing_mode != AddressingMode::Immediate
+                {
+                    let _ = bus.read(addr);
+                }
                 nop();
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::SEC => {
                 sec(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::BCS => {
                 if !bcs(&mut self.registers, addr) {
-                    self.registers.pc += num_operands;
+                    self.registers.pc = self.registers.pc.wrapping_add(num_operands);
                 } else {
                     branched = true;
                 }
             }
             InstructionName::CLC => {
                 clc(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::BCC => {
                 if !bcc(&mut self.registers, addr) {
-                    self.registers.pc += num_operands;
+                    self.registers.pc = self.registers.pc.wrapping_add(num_operands);
                 } else {
                     branched = true;
                 }
             }
             InstructionName::PHP => {
-                php(&mut self.registers, &mut self.memory);
-                self.registers.pc += num_operands;
+                php(&mut self.registers, &mut bus);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::BIT => {
-                bit(&mut self.registers, &mut self.memory, addr);
-                self.registers.pc += num_operands;
+                bit(&mut self.registers, &mut bus, addr);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::BVS => {
                 if !bvs(&mut self.registers, addr) {
-                    self.registers.pc += num_operands;
+                    self.registers.pc = self.registers.pc.wrapping_add(num_operands);
                 } else {
                     branched = true;
                 }
             }
             InstructionName::BVC => {
                 if !bvc(&mut self.registers, addr) {
-                    self.registers.pc += num_operands;
+                    self.registers.pc = self.registers.pc.wrapping_add(num_operands);
                 } else {
                     branched = true;
                 }
@@ -343,58 +390,58 @@ impl Nessy {
                 let data = if addressing_mode == AddressingMode::Immediate {
                     addr as u8
                 } else {
-                    self.memory.memory[addr as usize]
+                    bus.read(addr)
                 };
                 ldy(&mut self.registers, data);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::ASL => {
                 if addressing_mode == AddressingMode::Accumulator {
                     asl_acc(&mut self.registers);
                 } else {
-                    let data = self.memory.memory[addr as usize];
-                    asl(&mut self.registers, &mut self.memory, addr, data);
+                    let data = bus.read(addr);
+                    asl(&mut self.registers, &mut bus, addr, data);
                 }
 
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::RTI => {
-                rti(&mut self.registers, &mut self.memory);
-                self.registers.pc += num_operands;
+                rti(&mut self.registers, &mut bus);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::SBC => {
                 let data = if addressing_mode == AddressingMode::Immediate {
                     addr as u8
                 } else {
-                    self.memory.memory[addr as usize]
+                    bus.read(addr)
                 };
                 sbc(&mut self.registers, data);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::SED => {
                 sed(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::CMP => {
                 let data = if addressing_mode == AddressingMode::Immediate {
                     addr as u8
                 } else {
-                    self.memory.memory[addr as usize]
+                    bus.read(addr)
                 };
                 cmp(&mut self.registers, data);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::PHA => {
-                pha(&mut self.registers, &mut self.memory);
-                self.registers.pc += num_operands;
+                pha(&mut self.registers, &mut bus);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::PLP => {
-                plp(&mut self.registers, &mut self.memory);
-                self.registers.pc += num_operands;
+                plp(&mut self.registers, &mut bus);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::BMI => {
                 if !bmi(&mut self.registers, addr) {
-                    self.registers.pc += num_operands;
+                    self.registers.pc = self.registers.pc.wrapping_add(num_operands);
                 } else {
                     branched = true;
                 }
@@ -403,94 +450,94 @@ impl Nessy {
                 let data = if addressing_mode == AddressingMode::Immediate {
                     addr as u8
                 } else {
-                    self.memory.memory[addr as usize]
+                    bus.read(addr)
                 };
                 ora(&mut self.registers, data);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::CLV => {
                 clv(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::EOR => {
                 let data = if addressing_mode == AddressingMode::Immediate {
                     addr as u8
                 } else {
-                    self.memory.memory[addr as usize]
+                    bus.read(addr)
                 };
                 eor(&mut self.registers, data);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::ADC => {
                 let data = if addressing_mode == AddressingMode::Immediate {
                     addr as u8
                 } else {
-                    self.memory.memory[addr as usize]
+                    bus.read(addr)
                 };
                 adc(&mut self.registers, data);
 
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::STY => {
-                sty(&mut self.registers, &mut self.memory, addr);
-                self.registers.pc += num_operands;
+                sty(&mut self.registers, &mut bus, addr);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::INY => {
                 iny(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::INX => {
                 inx(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::TAX => {
                 tax(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::TYA => {
                 tya(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::TXA => {
                 txa(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::TSX => {
-                tsx(&mut self.registers, &mut self.memory);
-                self.registers.pc += num_operands;
+                tsx(&mut self.registers);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::DEX => {
                 dex(&mut self.registers);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::LSR => {
                 if addressing_mode == AddressingMode::Accumulator {
                     lsr_acc(&mut self.registers);
                 } else {
-                    lsr(&mut self.registers, &mut self.memory, addr);
+                    lsr(&mut self.registers, &mut bus, addr);
                 }
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::ROR => {
                 if addressing_mode == AddressingMode::Accumulator {
                     ror_acc(&mut self.registers);
                 } else {
-                    ror(&mut self.registers, &mut self.memory, addr);
+                    ror(&mut self.registers, &mut bus, addr);
                 }
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::ROL => {
                 if addressing_mode == AddressingMode::Accumulator {
                     rol_acc(&mut self.registers);
                 } else {
-                    let data = self.memory.memory[addr as usize];
-                    rol(&mut self.registers, &mut self.memory, addr, data);
+                    let data = bus.read(addr);
+                    rol(&mut self.registers, &mut bus, addr, data);
                 }
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::DEC => {
-                dec(&mut self.registers, &mut self.memory, addr);
-                self.registers.pc += num_operands;
+                dec(&mut self.registers, &mut bus, addr);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
 
             // UNOFFICIAL Instructions
@@ -498,96 +545,135 @@ impl Nessy {
                 let data = if addressing_mode == AddressingMode::Immediate {
                     addr as u8
                 } else {
-                    self.memory.memory[addr as usize]
+                    bus.read(addr)
                 };
 
                 lda(&mut self.registers, data);
                 ldx(&mut self.registers, data as u16);
-                self.registers.pc += num_operands;
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::SAX => {
-                self.memory.memory[addr as usize] =
-                    ((self.registers.a & self.registers.x) as i16) as u8;
-                self.registers.pc += num_operands;
+                bus.write(addr, self.registers.a & self.registers.x);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::DCP => {
-                dec(&mut self.registers, &mut self.memory, addr);
-                cmp(&mut self.registers, self.memory.memory[addr as usize]);
-                self.registers.pc += num_operands;
+                let data = dec(&mut self.registers, &mut bus, addr);
+                cmp(&mut self.registers, data);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::ISB => {
-                inc(&mut self.registers, &mut self.memory, addr);
-                sbc(&mut self.registers, self.memory.memory[addr as usize]);
-                self.registers.pc += num_operands;
+                let data = inc(&mut self.registers, &mut bus, addr);
+                sbc(&mut self.registers, data);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::SLO => {
-                let data = self.memory.memory[addr as usize];
-                asl(&mut self.registers, &mut self.memory, addr, data);
-                ora(&mut self.registers, self.memory.memory[addr as usize]);
-                self.registers.pc += num_operands;
+                let data = bus.read(addr);
+                let data = asl(&mut self.registers, &mut bus, addr, data);
+                ora(&mut self.registers, data);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::RLA => {
-                let data = self.memory.memory[addr as usize];
-                rol(&mut self.registers, &mut self.memory, addr, data);
-                and(&mut self.registers, self.memory.memory[addr as usize]);
-                self.registers.pc += num_operands;
+                let data = bus.read(addr);
+                let data = rol(&mut self.registers, &mut bus, addr, data);
+                and(&mut self.registers, data);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::SRE => {
-                lsr(&mut self.registers, &mut self.memory, addr);
-                eor(&mut self.registers, self.memory.memory[addr as usize]);
-                self.registers.pc += num_operands;
+                let data = lsr(&mut self.registers, &mut bus, addr);
+                eor(&mut self.registers, data);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
             }
             InstructionName::RRA => {
-                ror(&mut self.registers, &mut self.memory, addr);
-                adc(&mut self.registers, self.memory.memory[addr as usize]);
-                self.registers.pc += num_operands;
+                let data = ror(&mut self.registers, &mut bus, addr);
+                adc(&mut self.registers, data);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
+            }
+            InstructionName::ANC => {
+                anc(&mut self.registers, addr as u8);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
+            }
+            InstructionName::ALR => {
+                alr(&mut self.registers, addr as u8);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
+            }
+            InstructionName::ARR => {
+                arr(&mut self.registers, addr as u8);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
+            }
+            InstructionName::AXS => {
+                axs(&mut self.registers, addr as u8);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
+            }
+            InstructionName::XAA => {
+                xaa(&mut self.registers, addr as u8);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
+            }
+            InstructionName::LAS => {
+                let data = bus.read(addr);
+                las(&mut self.registers, data);
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
+            }
+            InstructionName::SHX
+            | InstructionName::SHY
+            | InstructionName::TAS
+            | InstructionName::AHX => {
+                // Address before indexing, its high byte ends up in the stored value
+                let base = if addressing_mode == AddressingMode::ZeroPageIndirectIndexedWithY {
+                    address_from_bytes(
+                        bus.peek(low_byte as u16),
+                        bus.peek(low_byte.wrapping_add(1) as u16),
+                    )
+                } else {
+                    address_from_bytes(low_byte, high_byte)
+                };
+
+                match instruction {
+                    InstructionName::SHX => shx(&mut self.registers, &mut bus, base, addr),
+                    InstructionName::SHY => shy(&mut self.registers, &mut bus, base, addr),
+                    InstructionName::TAS => tas(&mut self.registers, &mut bus, base, addr),
+                    _ => ahx(&mut self.registers, &mut bus, base, addr),
+                }
+                self.registers.pc = self.registers.pc.wrapping_add(num_operands);
+            }
+            InstructionName::JAM => {
+                // The CPU locks up, only a reset gets it out of there
+                self.registers.pc = self.registers.pc.wrapping_sub(1);
+                return Err(EmulatorError::CpuJammed {
+                    pc: self.registers.pc,
+                });
             }
         }
 
-        let new_cycles = get_cycles(instruction, addressing_mode, page_crossed, branched);
-        self.cycle += new_cycles as usize;
-
-        // PPU
-
-        for _ in 0..(new_cycles * 3) {
-            // let get_oam_byte = |n: i32, m: i32| 4 * n + m;
-
-            if mirror_addr == 0x2000 {
-                // PPUCTRL register
-                self.ppu_registers.ctrl = Ctrl::new_from(self.memory.memory[0x2000]);
-            } else if mirror_addr == 0x2001 {
-                // PPUMASK register
-                self.ppu_registers.mask = Mask::new_from(self.memory.memory[0x2001]);
-            } else if mirror_addr == 0x2002 {
-                // PPUSTATUS register
-                self.ppu_registers.status = Status::new_from(self.memory.memory[0x2002]);
-            }
-            // } else if mirror_addr == 0x2003 {
-            //     // OAMADDR register
-            //     let oamaddr = self.memory.memory[0x2003];
-            // } else if mirror_addr == 0x2004 {
-            //     // OAMDATA register
-            //     let oamdata = self.memory.memory[0x2004];
-            // } else if mirror_addr == 0x2005 {
-            //     // PPUSCROLL register
-            //     let ppuscroll = self.memory.memory[0x2005];
-            // } else if mirror_addr == 0x2006 {
-            //     // PPUADDR regsiter
-            //     let ppuaddr = self.memory.memory[0x2006];
-            // } else if mirror_addr == 0x2007 {
-            //     // PPUDATA register
-            //     let ppudata = self.memory.memory[0x2007];
-            // } else if mirror_addr == 0x4014 {
-            //     // OAMDATA register
-            //     let oamdata = self.memory.memory[0x4014];
-            // }
-
-            self.ppu_cycle += 1;
-            if self.ppu_cycle > 340 {
-                self.frames += 1;
-            }
-            self.ppu_cycle %= 341;
+        let page_crossed = if addressing_mode == AddressingMode::Relative {
+            branched && is_page_crossed(next_pc, self.registers.pc)
+        } else {
+            page_crossed
+        };
+        if branched {
+            // Dummy reads while the offset is added to PC, then while its high byte is fixed
+            let _ = bus.read(next_pc);
+            if page_crossed {
+                let _ = bus.read((next_pc & 0xFF00) | (self.registers.pc & 0x00FF));
+            }
+        }
+
+        // Every cycle is a bus access, the count has to match the cycle table
+        debug_assert_eq!(
+            *bus.cycle - start_cycle,
+            get_cycles(instruction, addressing_mode, page_crossed, branched) as usize
+        );
+
+        // The CPU is halted while OAM DMA runs
+        if let Some(page) = bus.bus.take_oam_dma() {
+            oam_dma(&mut bus, page);
         }
+
+        let irq_disabled = match instruction {
+            InstructionName::CLI | InstructionName::SEI | InstructionName::PLP => irq_disabled,
+            _ => self.registers.is_flag_set(StatusFlag::I),
+        };
+        self.pending_interrupt = bus.interrupts.poll(irq_disabled);
+        Ok(())
     }
 
     pub fn get_nestest_output(&self) -> String {
@@ -597,17 +683,14 @@ impl Nessy {
         let (instruction, addressing_mode, is_official_instruction) = match instruction {
             Instruction::Official(instr, addr) => (instr, addr, true),
             Instruction::Unofficial(instr, addr) => (instr, addr, false),
-            Instruction::Unknown => {
-                unreachable!()
-            }
         };
 
         let num_operands = num_operands_from_addressing(&addressing_mode) as u16;
-        let ops = get_operands(&self.registers, &self.memory);
+        let ops = get_operands(&self.registers, &self.bus);
 
         let (low_byte, high_byte) = ops;
         let addr = apply_addressing(
-            &self.memory,
+            &self.bus,
             &self.registers,
             addressing_mode.clone(),
             low_byte,
@@ -615,21 +698,6 @@ impl Nessy {
         )
         .unwrap_or(0);
 
-        // RAM MIRORRING AND
-        let mirror_addr = if addr < 0x2000 {
-            // System memory is mirrored
-            addr % 0x0800
-        } else if (0x2000..0x4000).contains(&addr) {
-            // PPU I/O rgisters are mirrored
-            if addr > 0x007 {
-                addr % 0x2008 + 0x2000
-            } else {
-                addr
-            }
-        } else {
-            addr
-        };
-
         let op1 = if num_operands >= 1 {
             format!("{:02X}", ops.0)
         } else {
@@ -670,22 +738,19 @@ impl Nessy {
                 | InstructionName::BNE
                 | InstructionName::BPL
                 | InstructionName::BVC => format!("${:04X}", addr),
-                _ => format!(
-                    "${:04X} = {:02X}",
-                    addr, self.memory.memory[mirror_addr as usize]
-                ),
+                _ => format!("${:04X} = {:02X}", addr, self.bus.peek(addr)),
             },
             (AddressingMode::AbsoluteIndirectWithX, _) => format!(
                 "${:04X},X @ {:04X} = {:02X}",
                 address_from_bytes(ops.0, ops.1),
                 address_from_bytes(ops.0, ops.1).wrapping_add(self.registers.x.into()),
-                self.memory.memory[mirror_addr as usize]
+                self.bus.peek(addr)
             ),
             (AddressingMode::AbsoluteIndirectWithY, _) => format!(
                 "${:04X},Y @ {:04X} = {:02X}",
                 address_from_bytes(ops.0, ops.1),
                 address_from_bytes(ops.0, ops.1).wrapping_add(self.registers.y.into()),
-                self.memory.memory[mirror_addr as usize]
+                self.bus.peek(addr)
             ),
             (AddressingMode::Immediate, _) => format!("#${:02X}", addr),
             (AddressingMode::Accumulator, _) => "A".to_string(),
@@ -695,36 +760,33 @@ impl Nessy {
                 ops.0,
                 ops.0.wrapping_add(self.registers.x),
                 addr,
-                self.memory.memory[mirror_addr as usize]
+                self.bus.peek(addr)
             ),
             (AddressingMode::ZeroPageIndirectIndexedWithY, _) => format!(
                 "(${:02X}),Y = {:04X} @ {:04X} = {:02X}",
                 ops.0,
                 address_from_bytes(
-                    self.memory.memory[ops.0 as usize],
-                    self.memory.memory[ops.0.wrapping_add(1) as usize]
+                    self.bus.peek(ops.0 as u16),
+                    self.bus.peek(ops.0.wrapping_add(1) as u16)
                 ),
                 addr,
-                self.memory.memory[mirror_addr as usize]
+                self.bus.peek(addr)
             ),
             (AddressingMode::AbsoluteIndirect, _) => {
                 format!("(${:04X}) = {:04X}", address_from_bytes(ops.0, ops.1), addr)
             }
-            (AddressingMode::ZeroPage, _) => format!(
-                "${:02X} = {:02X}",
-                addr, self.memory.memory[mirror_addr as usize]
-            ),
+            (AddressingMode::ZeroPage, _) => format!("${:02X} = {:02X}", addr, self.bus.peek(addr)),
             (AddressingMode::ZeroPageIndexedWithX, _) => format!(
                 "${:02X},X @ {:02X} = {:02X}",
                 ops.0,
                 ops.0.wrapping_add(self.registers.x),
-                self.memory.memory[mirror_addr as usize]
+                self.bus.peek(addr)
             ),
             (AddressingMode::ZeroPageIndexedWithY, _) => format!(
                 "${:02X},Y @ {:02X} = {:02X}",
                 ops.0,
                 ops.0.wrapping_add(self.registers.y),
-                self.memory.memory[mirror_addr as usize]
+                self.bus.peek(addr)
             ),
             _ => "".to_string(),
         };
@@ -737,10 +799,191 @@ impl Nessy {
             op2,
             instr,
             addressing_stuff,
-            self.registers.a, self.registers.x, self.registers.y, self.registers.status, self.memory.stack_pointer,
-            self.frames,
-            self.ppu_cycle,
+            self.registers.a, self.registers.x, self.registers.y, self.registers.status, self.registers.s,
+            self.bus.ppu.scanline,
+            self.bus.ppu.dot,
             self.cycle,
         )
     }
 }
+
+/// Runs the 7 cycles interrupt sequence
+///
+/// A pending NMI takes the NMI vector, including one occurring while an IRQ pushes its state.
+fn service_interrupt(registers: &mut cpu::Registers, bus: &mut CpuBus<NesBus>) {
+    // Dummy reads, the opcode fetched is replaced by a BRK
+    let _ = bus.read(registers.pc);
+    let _ = bus.read(registers.pc);
+
+    cpu::instructions::interrupt(registers, bus, IRQ_VECTOR_ADDRESS as u16);
+}
+
+/// Copies a whole CPU page into OAM through $2004
+///
+/// Takes 513 cycles, plus one to align on a read cycle when started on an odd cycle.
+fn oam_dma(bus: &mut CpuBus<NesBus>, page: u8) {
+    bus.idle();
+    if *bus.cycle % 2 == 1 {
+        bus.idle();
+    }
+
+    let page = (page as u16) << 8;
+    for i in 0..0x100 {
+        let value = bus.read(page | i);
+        bus.write(0x2004, value);
+    }
+}
+
+#[test]
+fn nmi_test() {
+    let mut nessy = Nessy::new();
+
+    // JMP $8000 at $8000, NMI handler at $9000
+    let prg_rom = &mut nessy.bus.cartridge.memory.prg_rom;
+    prg_rom[0x0000..0x0003].copy_from_slice(&[0x4C, 0x00, 0x80]);
+    prg_rom[0x7FFA..0x7FFC].copy_from_slice(&[0x00, 0x90]);
+    nessy.registers.pc = 0x8000;
+    nessy.registers.set_flag(StatusFlag::I, true); // NMI can't be masked
+
+    nessy.bus.write(0x2000, 0x80);
+    while nessy.registers.pc != 0x9000 {
+        nessy.execute().unwrap();
+        assert!(nessy.cycle < 30000, "NMI never happened");
+    }
+    assert_eq!(nessy.bus.ppu.scanline, 241);
+
+    // Return address and status pushed with B clear
+    assert_eq!(nessy.bus.peek(0x01FD), 0x80);
+    assert_eq!(nessy.bus.peek(0x01FB) & 0b00010000, 0);
+}
+
+#[test]
+fn brk_nmi_hijack_test() {
+    let mut nessy = Nessy::new();
+
+    // BRK at $8000, NOP in the NMI handler at $9000, BRK handler at $A000
+    let prg_rom = &mut nessy.bus.cartridge.memory.prg_rom;
+    prg_rom[0x0000] = 0x00;
+    prg_rom[0x1000] = 0xEA;
+    prg_rom[0x7FFA..0x7FFC].copy_from_slice(&[0x00, 0x90]);
+    prg_rom[0x7FFE..0x8000].copy_from_slice(&[0x00, 0xA0]);
+    nessy.registers.pc = 0x8000;
+    nessy.bus.write(0x2000, 0x80);
+
+    // Vblank starts 12 dots later, on the 4th cycle of BRK while it pushes the PC
+    nessy.bus.ppu.scanline = 240;
+    nessy.bus.ppu.dot = 330;
+
+    nessy.execute().unwrap();
+    assert_eq!(nessy.registers.pc, 0x9000);
+    // BRK still pushed its return address and the status with B set
+    assert_eq!(nessy.bus.peek(0x01FC), 0x02);
+    assert_ne!(nessy.bus.peek(0x01FB) & 0b00010000, 0);
+
+    // The NMI was serviced by the BRK, the handler runs
+    nessy.execute().unwrap();
+    assert_eq!(nessy.registers.pc, 0x9001);
+}
+
+#[test]
+fn dummy_read_test() {
+    let mut nessy = Nessy::new();
+
+    // LDA $20F2,X crosses a page, the dummy read hits $2002 and clears vblank
+    let prg_rom = &mut nessy.bus.cartridge.memory.prg_rom;
+    prg_rom[0x0000..0x0003].copy_from_slice(&[0xBD, 0xF2, 0x20]);
+    nessy.registers.pc = 0x8000;
+    nessy.registers.x = 0x10;
+    nessy.bus.ppu.registers.status = crate::ppu::Status::new_from(0x80);
+
+    let cycle = nessy.cycle;
+    nessy.execute().unwrap();
+    assert_eq!(nessy.cycle - cycle, 5);
+    assert_eq!(nessy.bus.ppu.registers.status.bits() & 0x80, 0);
+}
+
+#[test]
+fn jam_test() {
+    let mut nessy = Nessy::new();
+
+    nessy.bus.cartridge.memory.prg_rom[0x0000] = 0x02;
+    nessy.registers.pc = 0x8000;
+
+    for _ in 0..2 {
+        assert_eq!(
+            nessy.execute(),
+            Err(EmulatorError::CpuJammed { pc: 0x8000 })
+        );
+    }
+}
+
+#[test]
+fn pc_wrap_test() {
+    let mut nessy = Nessy::new();
+
+    // LDA #$42 at $FFFF, its operand is at $0000 in RAM
+    nessy.bus.cartridge.memory.prg_rom[0x7FFF] = 0xA9;
+    nessy.bus.write(0x0000, 0x42);
+    nessy.registers.pc = 0xFFFF;
+    nessy.execute().unwrap();
+    assert_eq!(nessy.registers.a, 0x42);
+    assert_eq!(nessy.registers.pc, 0x0001);
+
+    // JAM at $0000
+    nessy.bus.write(0x0000, 0x02);
+    nessy.registers.pc = 0x0000;
+    assert_eq!(
+        nessy.execute(),
+        Err(EmulatorError::CpuJammed { pc: 0x0000 })
+    );
+}
+
+#[test]
+fn run_frame_test() {
+    let mut nessy = Nessy::new();
+
+    // JMP $8000 at $8000, the picture is the backdrop colour
+    let prg_rom = &mut nessy.bus.cartridge.memory.prg_rom;
+    prg_rom[0x0000..0x0003].copy_from_slice(&[0x4C, 0x00, 0x80]);
+    nessy.registers.pc = 0x8000;
+    nessy.registers.set_flag(StatusFlag::I, true);
+    nessy.bus.ppu.memory.palette[0x00] = 0x30;
+
+    nessy.run_frame().unwrap();
+    let cycle = nessy.cycle;
+    let output = nessy.run_frame().unwrap();
+
+    let width = crate::ppu::SCREEN_WIDTH;
+    let height = crate::ppu::SCREEN_HEIGHT;
+    assert_eq!(output.frame_buffer.len(), width * height * 4);
+    assert_eq!(&output.frame_buffer[0..4], &[236, 238, 236, 0xFF]);
+    // 44100 Hz at 60 frames per second
+    assert!((730..=740).contains(&output.samples.len()));
+
+    // A frame is 89342 dots, 29780.67 CPU cycles
+    let cycles = nessy.cycle - cycle;
+    assert!((29778..=29784).contains(&cycles));
+    // Stopped in the post-render scanline, before the next frame draws anything
+    assert_eq!(nessy.bus.ppu.scanline, 240);
+
+    let cycle = nessy.cycle;
+    nessy.run_cycles(1000).unwrap();
+    assert!((1000..1003).contains(&(nessy.cycle - cycle)));
+}
+
+#[test]
+fn reset_test() {
+    let mut nessy = Nessy::new();
+
+    nessy.bus.cartridge.memory.prg_rom[0x7FFC..0x7FFE].copy_from_slice(&[0x34, 0x82]);
+    nessy.registers.pc = 0x9000;
+    nessy.bus.write(0x2000, 0x80);
+
+    let cycle = nessy.cycle;
+    nessy.reset();
+    assert_eq!(nessy.cycle - cycle, 7);
+    assert_eq!(nessy.registers.pc, 0x8234);
+    assert_eq!(nessy.registers.s, 0xFA);
+    assert!(nessy.registers.is_flag_set(StatusFlag::I));
+    assert!(!nessy.bus.ppu.registers.ctrl.nmi_enable);
+}
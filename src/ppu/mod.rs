@@ -1,22 +1,618 @@
 /*!  Emulate a Ricoh 2C02 microntroller used for PPU */
 
+pub mod palette;
+
+use self::palette::PALETTE;
+use crate::cartridge::Cartridge;
+
+/// Width of the picture output by the PPU, in pixels
+pub const SCREEN_WIDTH: usize = 256;
+/// Height of the picture output by the PPU, in pixels
+pub const SCREEN_HEIGHT: usize = 240;
+
+/// Scanline during which the next frame's first tiles are fetched
+const PRE_RENDER_SCANLINE: u16 = 261;
+/// Sprites are drawn a scanline after their OAM Y coordinate, only 8 of them per scanline
+const MAX_SPRITES_PER_SCANLINE: usize = 8;
+
+/**
+Memory inside the PPU, the pattern tables are on the cartridge
+
+Nametable RAM (CIRAM), 2KB laid out in $2000 - $2FFF by the cartridge mirroring,
+followed by the 2KB that four-screen cartridges add for the other two nametables
+Palette RAM, 32 bytes in $3F00 - $3FFF, mirrored every 32 bytes
+Object Attribute Memory, 64 sprites of 4 bytes in their own address space
+*/
 pub struct Memory {
-    pub memory: Vec<u8>,
-    pub oam: Vec<u8>, // Object Attribute Memory
+    pub nametables: Vec<u8>,
+    pub palette: Vec<u8>,
+    pub oam: Vec<u8>,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Memory {
     pub fn new() -> Self {
+        Self {
+            nametables: vec![0; 0x1000],
+            palette: vec![0; 32],
+            oam: vec![0; 256],
+        }
+    }
+
+    /// Maps a palette address to its location in `palette`
+    ///
+    /// The backdrop entries $3F10/$3F14/$3F18/$3F1C mirror $3F00/$3F04/$3F08/$3F0C.
+    pub fn palette_index(addr: u16) -> usize {
+        let index = addr & 0x1F;
+        let index = if index & 0b10011 == 0b10000 {
+            index & 0x0F
+        } else {
+            index
+        };
+
+        index as usize
+    }
+}
+
+/**
+The PPU, as seen from the CPU through its eight memory mapped registers
+
+PPUCTRL    $2000 (write)
+PPUMASK    $2001 (write)
+PPUSTATUS    $2002 (read)
+OAMADDR    $2003 (write)
+OAMDATA    $2004 (read/write)
+PPUSCROLL    $2005 (write twice)
+PPUADDR    $2006 (write twice)
+PPUDATA    $2007 (read/write)
+*/
+pub struct Ppu {
+    pub registers: Registers,
+    pub memory: Memory,
+    oam_addr: u8,
+    vram_addr: u16,      // v: current VRAM address
+    temp_vram_addr: u16, // t: temporary VRAM address, top left onscreen tile
+    fine_x: u8,
+    write_toggle: bool, // w: first or second write of $2005/$2006
+    read_buffer: u8,
+    io_latch: u8, // Last value put on the PPU data bus, returned by write-only registers
+    pub scanline: u16,
+    pub dot: u16,
+    pub frame: u64,                // Number of frames completed since power up
+    pub frame_buffer: Vec<u8>,     // RGBA pixels, SCREEN_WIDTH * SCREEN_HEIGHT * 4
+    background: BackgroundFetcher, // Tiles fetched ahead and the shift registers drawing them
+    sprites: SpriteUnit,           // Sprites found for the next scanline
+}
+
+/// State of the background tile fetches
+#[derive(Default)]
+struct BackgroundFetcher {
+    tile_latch: u8,
+    attribute_latch: u8,
+    pattern_low_latch: u8,
+    pattern_high_latch: u8,
+    pattern_low: u16, // The high byte is the tile being drawn, the low byte the next one
+    pattern_high: u16,
+    attribute_low: u16, // Palette bits, expanded to one bit per pixel like the pattern
+    attribute_high: u16,
+}
 
-        let mut memory = Vec::new();
-        memory.resize_with(0x10000, || 0);
+/// Up to eight sprites found during the sprite evaluation of the previous scanline
+#[derive(Default)]
+struct SpriteUnit {
+    count: usize,
+    indices: [u8; MAX_SPRITES_PER_SCANLINE], // Position in OAM, for sprite 0 hits
+    x: [u8; MAX_SPRITES_PER_SCANLINE],
+    attributes: [u8; MAX_SPRITES_PER_SCANLINE],
+    pattern_low: [u8; MAX_SPRITES_PER_SCANLINE],
+    pattern_high: [u8; MAX_SPRITES_PER_SCANLINE],
+    // Found during the evaluation, fetched from dot 257 then drawn on the next scanline
+    next_count: usize,
+    next: [[u8; 4]; MAX_SPRITES_PER_SCANLINE],
+    next_indices: [u8; MAX_SPRITES_PER_SCANLINE],
+}
 
-        let mut oam = Vec::new();
-        oam.resize_with(256, || 0);
+impl Default for Ppu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
+impl Ppu {
+    pub fn new() -> Self {
         Self {
-            memory,
-            oam,
+            registers: Registers::new(),
+            memory: Memory::new(),
+            oam_addr: 0,
+            vram_addr: 0,
+            temp_vram_addr: 0,
+            fine_x: 0,
+            write_toggle: false,
+            read_buffer: 0,
+            io_latch: 0,
+            scanline: 0,
+            dot: 0,
+            frame: 0,
+            frame_buffer: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT * 4],
+            background: BackgroundFetcher::default(),
+            sprites: SpriteUnit::default(),
+        }
+    }
+
+    /// Reset button, clears PPUCTRL, PPUMASK and the write toggle
+    pub fn reset(&mut self) {
+        self.registers.ctrl = Ctrl::new();
+        self.registers.mask = Mask::new();
+        self.write_toggle = false;
+        self.read_buffer = 0;
+    }
+
+    /// Advances the PPU by one dot
+    ///
+    /// A frame is 262 scanlines of 341 dots: 240 visible scanlines, one idle scanline,
+    /// vblank from scanline 241 to 260 and the pre-render scanline 261.
+    /// With rendering enabled the pre-render scanline of odd frames is one dot shorter.
+    pub fn tick(&mut self, cartridge: &mut Cartridge) {
+        self.dot += 1;
+        if self.scanline == PRE_RENDER_SCANLINE
+            && self.dot == 340
+            && self.frame % 2 == 1
+            && self.is_rendering_enabled()
+        {
+            self.dot = 341;
+        }
+        if self.dot > 340 {
+            self.dot = 0;
+            self.scanline += 1;
+            if self.scanline > PRE_RENDER_SCANLINE {
+                self.scanline = 0;
+                self.frame += 1;
+            }
+        }
+
+        if self.scanline == 241 && self.dot == 1 {
+            self.registers.status.vblank = true;
+        } else if self.scanline == PRE_RENDER_SCANLINE && self.dot == 1 {
+            self.registers.status.vblank = false;
+            self.registers.status.sprite_0_hit = false;
+            self.registers.status.sprite_overflow = false;
+        }
+
+        let visible = (self.scanline as usize) < SCREEN_HEIGHT;
+        if !(visible || self.scanline == PRE_RENDER_SCANLINE) {
+            return;
+        }
+
+        if self.is_rendering_enabled() {
+            self.render_dot(cartridge, visible);
+        }
+
+        if visible && (1..=256).contains(&self.dot) {
+            self.draw_pixel();
+        }
+    }
+
+    /// Whether background or sprite rendering is enabled in PPUMASK
+    pub fn is_rendering_enabled(&self) -> bool {
+        self.registers.mask.background_enable || self.registers.mask.sprite_enable
+    }
+
+    /// Memory fetches and scrolling done on a dot of a visible or pre-render scanline
+    fn render_dot(&mut self, cartridge: &mut Cartridge, visible: bool) {
+        let dot = self.dot;
+
+        if (2..=257).contains(&dot) || (322..=337).contains(&dot) {
+            let background = &mut self.background;
+            background.pattern_low <<= 1;
+            background.pattern_high <<= 1;
+            background.attribute_low <<= 1;
+            background.attribute_high <<= 1;
+        }
+
+        if (1..=256).contains(&dot) || (321..=336).contains(&dot) {
+            match dot % 8 {
+                1 => {
+                    self.reload_background();
+                    self.background.tile_latch =
+                        self.vram_read(cartridge, 0x2000 | (self.vram_addr & 0x0FFF));
+                }
+                3 => {
+                    let v = self.vram_addr;
+                    let addr = 0x23C0 | (v & 0x0C00) | ((v >> 4) & 0x38) | ((v >> 2) & 0x07);
+                    let shift = ((v >> 4) & 0x04) | (v & 0x02);
+                    self.background.attribute_latch =
+                        (self.vram_read(cartridge, addr) >> shift) & 0b11;
+                }
+                5 => {
+                    let addr = self.background_pattern_addr();
+                    self.background.pattern_low_latch = self.vram_read(cartridge, addr);
+                }
+                7 => {
+                    let addr = self.background_pattern_addr() + 8;
+                    self.background.pattern_high_latch = self.vram_read(cartridge, addr);
+                }
+                0 => self.increment_coarse_x(),
+                _ => {}
+            }
+        }
+
+        match dot {
+            256 => self.increment_y(),
+            257 => {
+                self.reload_background();
+                // Copy the horizontal position from t
+                self.vram_addr = (self.vram_addr & !0x041F) | (self.temp_vram_addr & 0x041F);
+                if visible {
+                    self.evaluate_sprites();
+                } else {
+                    self.sprites.next_count = 0;
+                }
+            }
+            // Unused nametable fetches
+            338 | 340 => {
+                let _ = self.vram_read(cartridge, 0x2000 | (self.vram_addr & 0x0FFF));
+            }
+            _ => {}
+        }
+
+        if (257..=320).contains(&dot) {
+            let slot = (dot - 257) as usize / 8;
+            match (dot - 257) % 8 {
+                // Garbage nametable fetches, mappers counting the PPU fetches rely on them
+                0 | 2 => {
+                    let _ = self.vram_read(cartridge, 0x2000 | (self.vram_addr & 0x0FFF));
+                }
+                4 => self.sprites.pattern_low[slot] = self.sprite_pattern(cartridge, slot, 0),
+                6 => self.sprites.pattern_high[slot] = self.sprite_pattern(cartridge, slot, 8),
+                7 if slot == MAX_SPRITES_PER_SCANLINE - 1 => {
+                    self.sprites.count = self.sprites.next_count;
+                }
+                _ => {}
+            }
+        }
+
+        if self.scanline == PRE_RENDER_SCANLINE && (280..=304).contains(&dot) {
+            // Copy the vertical position from t
+            self.vram_addr = (self.vram_addr & !0x7BE0) | (self.temp_vram_addr & 0x7BE0);
+        }
+    }
+
+    /// Moves the latched tile in the low byte of the shift registers
+    fn reload_background(&mut self) {
+        let background = &mut self.background;
+        background.pattern_low =
+            (background.pattern_low & 0xFF00) | background.pattern_low_latch as u16;
+        background.pattern_high =
+            (background.pattern_high & 0xFF00) | background.pattern_high_latch as u16;
+
+        let expand = |bit: u8| if bit == 1 { 0xFF } else { 0x00 };
+        background.attribute_low =
+            (background.attribute_low & 0xFF00) | expand(background.attribute_latch & 1);
+        background.attribute_high =
+            (background.attribute_high & 0xFF00) | expand(background.attribute_latch >> 1);
+    }
+
+    /// Address of the low plane of the latched tile, for the fine Y in v
+    fn background_pattern_addr(&self) -> u16 {
+        let table = if self.registers.ctrl.background_tile_select {
+            0x1000
+        } else {
+            0x0000
+        };
+        let fine_y = (self.vram_addr >> 12) & 0b111;
+        table | (self.background.tile_latch as u16) << 4 | fine_y
+    }
+
+    fn increment_coarse_x(&mut self) {
+        if self.vram_addr & 0x001F == 31 {
+            // Wrap to the next horizontal nametable
+            self.vram_addr = (self.vram_addr & !0x001F) ^ 0x0400;
+        } else {
+            self.vram_addr += 1;
+        }
+    }
+
+    fn increment_y(&mut self) {
+        if self.vram_addr & 0x7000 != 0x7000 {
+            self.vram_addr += 0x1000;
+            return;
+        }
+
+        self.vram_addr &= !0x7000;
+        let mut coarse_y = (self.vram_addr & 0x03E0) >> 5;
+        if coarse_y == 29 {
+            // Wrap to the next vertical nametable
+            coarse_y = 0;
+            self.vram_addr ^= 0x0800;
+        } else if coarse_y == 31 {
+            // Out of the nametable, in the attributes, wraps without switching nametable
+            coarse_y = 0;
+        } else {
+            coarse_y += 1;
+        }
+        self.vram_addr = (self.vram_addr & !0x03E0) | (coarse_y << 5);
+    }
+
+    fn sprite_height(&self) -> u16 {
+        if self.registers.ctrl.sprite_height != 0 {
+            16
+        } else {
+            8
+        }
+    }
+
+    /// Finds the first eight sprites on the current scanline, they are drawn on the next one
+    ///
+    /// NOTE: The overflow flag is set without the buggy diagonal OAM scan of the real PPU
+    fn evaluate_sprites(&mut self) {
+        let height = self.sprite_height();
+        let mut count = 0;
+
+        for index in 0..64 {
+            let sprite = &self.memory.oam[index * 4..index * 4 + 4];
+            let row = self.scanline.wrapping_sub(sprite[0] as u16);
+            if row >= height {
+                continue;
+            }
+
+            if count == MAX_SPRITES_PER_SCANLINE {
+                self.registers.status.sprite_overflow = true;
+                break;
+            }
+            self.sprites.next[count].copy_from_slice(sprite);
+            self.sprites.next_indices[count] = index as u8;
+            count += 1;
+        }
+
+        self.sprites.next_count = count;
+    }
+
+    /// Fetches a plane of the row of `slot` drawn on the next scanline
+    ///
+    /// Empty slots still fetch tile $FF, mappers watching the pattern fetches rely on it.
+    fn sprite_pattern(&mut self, cartridge: &mut Cartridge, slot: usize, plane: u16) -> u8 {
+        let height = self.sprite_height();
+        let [y, tile, attributes, _] = if slot < self.sprites.next_count {
+            self.sprites.next[slot]
+        } else {
+            [0xFF, 0xFF, 0xFF, 0xFF]
+        };
+
+        let mut row = self.scanline.wrapping_sub(y as u16) & (height - 1);
+        if attributes & 0x80 == 0x80 {
+            row = height - 1 - row;
+        }
+
+        let addr = if height == 16 {
+            let table = (tile as u16 & 1) << 12;
+            let tile = (tile as u16 & 0xFE) + (row >> 3);
+            table | tile << 4 | (row & 0b111)
+        } else {
+            let table = if self.registers.ctrl.sprite_tile_select {
+                0x1000
+            } else {
+                0x0000
+            };
+            table | (tile as u16) << 4 | row
+        };
+
+        let value = self.vram_read(cartridge, addr + plane);
+        if slot >= self.sprites.next_count {
+            return 0;
+        }
+
+        if plane == 0 {
+            self.sprites.x[slot] = self.sprites.next[slot][3];
+            self.sprites.attributes[slot] = attributes;
+            self.sprites.indices[slot] = self.sprites.next_indices[slot];
+        }
+
+        if attributes & 0x40 == 0x40 {
+            value.reverse_bits()
+        } else {
+            value
+        }
+    }
+
+    /// Outputs the pixel for the current dot in the frame buffer
+    fn draw_pixel(&mut self) {
+        let x = (self.dot - 1) as usize;
+        let y = self.scanline as usize;
+        let mask = &self.registers.mask;
+
+        let background = if mask.background_enable && (x >= 8 || mask.background_left_column_enable)
+        {
+            let bit = 15 - self.fine_x as u16;
+            let background = &self.background;
+            let pixel =
+                ((background.pattern_high >> bit) & 1) << 1 | ((background.pattern_low >> bit) & 1);
+            let palette = ((background.attribute_high >> bit) & 1) << 1
+                | ((background.attribute_low >> bit) & 1);
+            (pixel as u8, palette as u8)
+        } else {
+            (0, 0)
+        };
+
+        let mut sprite = None;
+        if mask.sprite_enable && (x >= 8 || mask.sprite_left_column_enable) {
+            for slot in 0..self.sprites.count {
+                let column = x.wrapping_sub(self.sprites.x[slot] as usize);
+                if column >= 8 {
+                    continue;
+                }
+
+                let bit = 7 - column;
+                let pixel = ((self.sprites.pattern_high[slot] >> bit) & 1) << 1
+                    | ((self.sprites.pattern_low[slot] >> bit) & 1);
+                if pixel != 0 {
+                    sprite = Some((slot, pixel));
+                    break;
+                }
+            }
+        }
+
+        let palette_addr = match (background, sprite) {
+            ((0, _), None) => 0x3F00,
+            ((pixel, palette), None) => 0x3F00 | (palette as u16) << 2 | pixel as u16,
+            (background, Some((slot, pixel))) => {
+                let attributes = self.sprites.attributes[slot];
+                if self.sprites.indices[slot] == 0 && background.0 != 0 && x != 255 {
+                    self.registers.status.sprite_0_hit = true;
+                }
+
+                let behind_background = attributes & 0x20 == 0x20;
+                if behind_background && background.0 != 0 {
+                    0x3F00 | (background.1 as u16) << 2 | background.0 as u16
+                } else {
+                    0x3F10 | ((attributes & 0b11) as u16) << 2 | pixel as u16
+                }
+            }
+        };
+
+        let mut colour = self.memory.palette[Memory::palette_index(palette_addr)] & 0x3F;
+        if self.registers.mask.greyscale {
+            colour &= 0x30;
+        }
+
+        let [r, g, b] = PALETTE[colour as usize];
+        let offset = (y * SCREEN_WIDTH + x) * 4;
+        self.frame_buffer[offset..offset + 4].copy_from_slice(&[r, g, b, 0xFF]);
+    }
+
+    /// State of the NMI output, active while in vblank with NMI enabled in PPUCTRL
+    pub fn nmi_output(&self) -> bool {
+        self.registers.status.vblank && self.registers.ctrl.nmi_enable
+    }
+
+    /// CPU read of a PPU register, `addr` is already mirrored to $2000-$2007
+    pub fn read_register(&mut self, cartridge: &mut Cartridge, addr: u16) -> u8 {
+        match addr {
+            0x2002 => {
+                let value = self.registers.status.bits() | (self.io_latch & 0b11111);
+                self.registers.status.vblank = false;
+                self.write_toggle = false;
+                self.io_latch = value;
+            }
+            0x2004 => {
+                self.io_latch = self.memory.oam[self.oam_addr as usize];
+            }
+            0x2007 => {
+                let addr = self.vram_addr & 0x3FFF;
+                self.io_latch = if addr >= 0x3F00 {
+                    // Palette reads are not buffered, but the buffer is filled with the nametable "below"
+                    self.read_buffer = self.vram_read(cartridge, addr - 0x1000);
+                    (self.vram_read(cartridge, addr) & 0b111111) | (self.io_latch & 0b11000000)
+                } else {
+                    let value = self.read_buffer;
+                    self.read_buffer = self.vram_read(cartridge, addr);
+                    value
+                };
+                self.increment_vram_addr(cartridge);
+            }
+            _ => {}
+        }
+
+        self.io_latch
+    }
+
+    /// Reads a PPU register without any side effect
+    pub fn peek_register(&self, addr: u16) -> u8 {
+        match addr {
+            0x2002 => self.registers.status.bits() | (self.io_latch & 0b11111),
+            0x2004 => self.memory.oam[self.oam_addr as usize],
+            0x2007 => self.read_buffer,
+            _ => self.io_latch,
+        }
+    }
+
+    /// CPU write to a PPU register, `addr` is already mirrored to $2000-$2007
+    pub fn write_register(&mut self, cartridge: &mut Cartridge, addr: u16, value: u8) {
+        self.io_latch = value;
+
+        match addr {
+            0x2000 => {
+                self.registers.ctrl = Ctrl::new_from(value);
+                self.temp_vram_addr =
+                    (self.temp_vram_addr & 0xF3FF) | ((value as u16 & 0b11) << 10);
+            }
+            0x2001 => self.registers.mask = Mask::new_from(value),
+            0x2003 => self.oam_addr = value,
+            0x2004 => self.write_oam_data(value),
+            0x2005 => {
+                if !self.write_toggle {
+                    self.temp_vram_addr = (self.temp_vram_addr & !0x001F) | (value as u16 >> 3);
+                    self.fine_x = value & 0b111;
+                } else {
+                    self.temp_vram_addr = (self.temp_vram_addr & !0x73E0)
+                        | ((value as u16 & 0b111) << 12)
+                        | ((value as u16 & 0b11111000) << 2);
+                }
+                self.write_toggle = !self.write_toggle;
+            }
+            0x2006 => {
+                if !self.write_toggle {
+                    self.temp_vram_addr =
+                        (self.temp_vram_addr & 0x00FF) | ((value as u16 & 0b111111) << 8);
+                } else {
+                    self.temp_vram_addr = (self.temp_vram_addr & 0xFF00) | value as u16;
+                    self.vram_addr = self.temp_vram_addr;
+                    cartridge.ppu_address(self.vram_addr);
+                }
+                self.write_toggle = !self.write_toggle;
+            }
+            0x2007 => {
+                self.vram_write(cartridge, self.vram_addr & 0x3FFF, value);
+                self.increment_vram_addr(cartridge);
+            }
+            _ => {}
+        }
+    }
+
+    /// Writes a byte to OAM at OAMADDR, then increments OAMADDR. Used by $2004 and OAM DMA.
+    pub fn write_oam_data(&mut self, value: u8) {
+        self.memory.oam[self.oam_addr as usize] = value;
+        self.oam_addr = self.oam_addr.wrapping_add(1);
+    }
+
+    /// Moves v to the next address after a $2007 access, the PPU bus holds v until the next fetch
+    fn increment_vram_addr(&mut self, cartridge: &mut Cartridge) {
+        let increment = if self.registers.ctrl.increment_mode {
+            32
+        } else {
+            1
+        };
+        self.vram_addr = self.vram_addr.wrapping_add(increment) & 0x7FFF;
+        cartridge.ppu_address(self.vram_addr & 0x3FFF);
+    }
+
+    /// Reads the PPU address space, pattern tables are on the cartridge
+    fn vram_read(&mut self, cartridge: &mut Cartridge, addr: u16) -> u8 {
+        cartridge.ppu_address(addr & 0x3FFF);
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => cartridge.ppu_read(addr),
+            0x2000..=0x3EFF => match cartridge.nametable_read(addr) {
+                Some(value) => value,
+                None => self.memory.nametables[cartridge.mirroring().nametable_offset(addr)],
+            },
+            _ => self.memory.palette[Memory::palette_index(addr)],
+        }
+    }
+
+    fn vram_write(&mut self, cartridge: &mut Cartridge, addr: u16, value: u8) {
+        cartridge.ppu_address(addr & 0x3FFF);
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => cartridge.ppu_write(addr, value),
+            0x2000..=0x3EFF => {
+                if !cartridge.nametable_write(addr, value) {
+                    self.memory.nametables[cartridge.mirroring().nametable_offset(addr)] = value
+                }
+            }
+            _ => self.memory.palette[Memory::palette_index(addr)] = value,
         }
     }
 }
@@ -27,6 +623,12 @@ pub struct Registers {
     pub status: Status,
 }
 
+impl Default for Registers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Registers {
     pub fn new() -> Self {
         Self {
@@ -38,12 +640,18 @@ impl Registers {
 }
 
 pub struct Mask {
-    color_emphasis: u8,
-    sprite_enable: bool,
-    background_enable: bool,
-    sprite_left_column_enable: bool,
-    background_left_column_enable: bool,
-    greyscale: bool,
+    pub color_emphasis: u8,
+    pub sprite_enable: bool,
+    pub background_enable: bool,
+    pub sprite_left_column_enable: bool,
+    pub background_left_column_enable: bool,
+    pub greyscale: bool,
+}
+
+impl Default for Mask {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Mask {
@@ -72,13 +680,19 @@ impl Mask {
 
 // Represents the state of the PPU Control Register (0x2000)
 pub struct Ctrl {
-    nmi_enable: bool,
-    ppu_master_slave: bool, // Not used by NES
-    sprite_height: u8,
-    sprite_tile_select: bool,
-    background_tile_select: bool,
-    increment_mode: bool,
-    nametable_select: u8,
+    pub nmi_enable: bool,
+    pub ppu_master_slave: bool, // Not used by NES
+    pub sprite_height: u8,
+    pub sprite_tile_select: bool,
+    pub background_tile_select: bool,
+    pub increment_mode: bool,
+    pub nametable_select: u8,
+}
+
+impl Default for Ctrl {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Ctrl {
@@ -108,9 +722,15 @@ impl Ctrl {
 }
 
 pub struct Status {
-    vblank: bool,
-    sprite_0_hit: bool,
-    sprite_overflow: bool,
+    pub vblank: bool,
+    pub sprite_0_hit: bool,
+    pub sprite_overflow: bool,
+}
+
+impl Default for Status {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Status {
@@ -129,4 +749,90 @@ impl Status {
             sprite_overflow,
         }
     }
+
+    /// Returns the three status bits as they appear in PPUSTATUS
+    pub fn bits(&self) -> u8 {
+        (self.vblank as u8) << 7
+            | (self.sprite_0_hit as u8) << 6
+            | (self.sprite_overflow as u8) << 5
+    }
+}
+
+#[test]
+fn ppu_registers_test() {
+    let mut ppu = Ppu::new();
+    let mut cartridge = Cartridge::default();
+
+    // PPUADDR then PPUDATA writes, with +1 increment
+    ppu.write_register(&mut cartridge, 0x2006, 0x21);
+    ppu.write_register(&mut cartridge, 0x2006, 0x08);
+    ppu.write_register(&mut cartridge, 0x2007, 0x42);
+    ppu.write_register(&mut cartridge, 0x2007, 0x43);
+    assert_eq!(ppu.memory.nametables[0x108], 0x42);
+    assert_eq!(ppu.memory.nametables[0x109], 0x43);
+
+    // PPUDATA reads are buffered
+    ppu.write_register(&mut cartridge, 0x2006, 0x21);
+    ppu.write_register(&mut cartridge, 0x2006, 0x08);
+    let _ = ppu.read_register(&mut cartridge, 0x2007);
+    assert_eq!(ppu.read_register(&mut cartridge, 0x2007), 0x42);
+    assert_eq!(ppu.read_register(&mut cartridge, 0x2007), 0x43);
+
+    // Reading PPUSTATUS clears vblank and the write toggle
+    ppu.registers.status.vblank = true;
+    ppu.write_register(&mut cartridge, 0x2006, 0x3F);
+    assert_eq!(ppu.read_register(&mut cartridge, 0x2002) & 0x80, 0x80);
+    assert_eq!(ppu.read_register(&mut cartridge, 0x2002) & 0x80, 0x00);
+    ppu.write_register(&mut cartridge, 0x2006, 0x3F);
+    ppu.write_register(&mut cartridge, 0x2006, 0x10);
+    ppu.write_register(&mut cartridge, 0x2007, 0x0F);
+    assert_eq!(ppu.memory.palette[0x00], 0x0F);
+}
+
+#[test]
+fn vblank_nmi_test() {
+    let mut ppu = Ppu::new();
+    let mut cartridge = Cartridge::default();
+
+    while !(ppu.scanline == 241 && ppu.dot == 1) {
+        assert!(!ppu.registers.status.vblank);
+        ppu.tick(&mut cartridge);
+    }
+    assert!(ppu.registers.status.vblank);
+    assert!(!ppu.nmi_output());
+
+    // Enabling NMI during vblank raises the NMI output right away
+    ppu.write_register(&mut cartridge, 0x2000, 0x80);
+    assert!(ppu.nmi_output());
+
+    while !(ppu.scanline == 261 && ppu.dot == 1) {
+        ppu.tick(&mut cartridge);
+    }
+    assert!(!ppu.nmi_output());
+}
+
+#[test]
+fn background_rendering_test() {
+    let mut ppu = Ppu::new();
+    let mut cartridge = Cartridge::default();
+
+    // Tile 1 is a vertical bar on its leftmost column, drawn at the top left with palette 1
+    for row in 0..8 {
+        cartridge.memory.chr[0x0010 + row] = 0x80;
+    }
+    ppu.memory.nametables[0x000] = 1;
+    ppu.memory.nametables[0x3C0] = 0b01;
+    ppu.memory.palette[0x00] = 0x0F;
+    ppu.memory.palette[0x05] = 0x30;
+    ppu.write_register(&mut cartridge, 0x2001, 0b00001010);
+
+    while ppu.frame < 2 {
+        ppu.tick(&mut cartridge);
+    }
+
+    let pixel = |x: usize, y: usize| &ppu.frame_buffer[(y * SCREEN_WIDTH + x) * 4..][..3];
+    assert_eq!(pixel(0, 0), &PALETTE[0x30]);
+    assert_eq!(pixel(0, 7), &PALETTE[0x30]);
+    assert_eq!(pixel(1, 0), &PALETTE[0x0F]);
+    assert_eq!(pixel(0, 8), &PALETTE[0x0F]);
 }
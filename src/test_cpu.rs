@@ -1,53 +1,196 @@
 #[cfg(test)]
 mod cpu {
-    use crate::{nes_rom, nessy::Nessy};
+    use crate::blargg::{self, Status};
+
+    /// About 10 seconds of emulated time, the longest ROMs need less than that
+    const MAX_CYCLES: usize = 18_000_000;
+    /// The multi-ROM tests run every single ROM one after the other, all_instrs takes about 40 seconds
+    const MULTI_ROM_MAX_CYCLES: usize = 90_000_000;
+
+    fn assert_passes(rom: &[u8]) {
+        assert_passes_within(rom, MAX_CYCLES);
+    }
+
+    fn assert_passes_within(rom: &[u8], max_cycles: usize) {
+        let result = blargg::run(rom, max_cycles).unwrap();
+        assert_eq!(result.status, Status::Passed, "{}", result.text);
+    }
 
     #[test]
     fn instr_test_v5_offical() {
-        let mut nessy = Nessy::new();
-        let nestest = include_bytes!("../test_roms/instr_test-v5/official_only.nes");
+        assert_passes_within(
+            include_bytes!("../test_roms/instr_test-v5/official_only.nes"),
+            MULTI_ROM_MAX_CYCLES,
+        );
+    }
 
-        // Load ROM and decode header
-        let rom = nestest;
-        let nesfile = nes_rom::RomFile::new(rom);
+    #[test]
+    fn instr_test_v5_all_instrs() {
+        assert_passes_within(
+            include_bytes!("../test_roms/instr_test-v5/all_instrs.nes"),
+            MULTI_ROM_MAX_CYCLES,
+        );
+    }
 
-        nessy.load(&nesfile);
+    #[test]
+    fn instr_test_v5_basics() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/01-basics.nes"
+        ));
+    }
 
-        loop {
-            nessy.execute();
-        }
+    #[test]
+    fn instr_test_v5_implied() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/02-implied.nes"
+        ));
     }
 
     #[test]
-    fn instr_misc() {
-        let mut nessy = Nessy::new();
-        let nestest = include_bytes!("../test_roms/instr_misc/instr_misc.nes");
+    fn instr_test_v5_immediate() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/03-immediate.nes"
+        ));
+    }
+
+    #[test]
+    fn instr_test_v5_zero_page() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/04-zero_page.nes"
+        ));
+    }
+
+    #[test]
+    fn instr_test_v5_zp_xy() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/05-zp_xy.nes"
+        ));
+    }
+
+    #[test]
+    fn instr_test_v5_absolute() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/06-absolute.nes"
+        ));
+    }
+
+    #[test]
+    fn instr_test_v5_abs_xy() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/07-abs_xy.nes"
+        ));
+    }
+
+    #[test]
+    fn instr_test_v5_ind_x() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/08-ind_x.nes"
+        ));
+    }
+
+    #[test]
+    fn instr_test_v5_ind_y() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/09-ind_y.nes"
+        ));
+    }
+
+    #[test]
+    fn instr_test_v5_branches() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/10-branches.nes"
+        ));
+    }
 
-        // Load ROM and decode header
-        let rom = nestest;
-        let nesfile = nes_rom::RomFile::new(rom);
+    #[test]
+    fn instr_test_v5_stack() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/11-stack.nes"
+        ));
+    }
 
-        nessy.load(&nesfile);
+    #[test]
+    fn instr_test_v5_jmp_jsr() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/12-jmp_jsr.nes"
+        ));
+    }
 
-        loop {
-            nessy.execute();
-        }
+    #[test]
+    fn instr_test_v5_rts() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/13-rts.nes"
+        ));
     }
 
-    // #[test]
-    // fn cpu_exec_space() {
-    //     let mut nessy = Nessy::new();
-    //     let nestest =
-    //         include_bytes!("../test_roms/cpu_exec_space/test_cpu_exec_space_ppuio.nes");
+    #[test]
+    fn instr_test_v5_rti() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/14-rti.nes"
+        ));
+    }
 
-    //     // Load ROM and decode header
-    //     let rom = nestest;
-    //     let nesfile = nes_rom::RomFile::new(rom);
+    #[test]
+    fn instr_test_v5_brk() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/15-brk.nes"
+        ));
+    }
+
+    #[test]
+    fn instr_test_v5_special() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_test-v5/rom_singles/16-special.nes"
+        ));
+    }
 
-    //     nessy.load(&nesfile);
+    #[test]
+    fn instr_misc() {
+        assert_passes_within(
+            include_bytes!("../test_roms/instr_misc/instr_misc.nes"),
+            MULTI_ROM_MAX_CYCLES,
+        );
+    }
 
-    //     loop {
-    //         nessy.execute();
-    //     }
-    // }
+    #[test]
+    fn instr_misc_abs_x_wrap() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_misc/rom_singles/01-abs_x_wrap.nes"
+        ));
+    }
+
+    #[test]
+    fn instr_misc_branch_wrap() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_misc/rom_singles/02-branch_wrap.nes"
+        ));
+    }
+
+    #[test]
+    fn instr_misc_dummy_reads() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_misc/rom_singles/03-dummy_reads.nes"
+        ));
+    }
+
+    #[test]
+    fn instr_misc_dummy_reads_apu() {
+        assert_passes(include_bytes!(
+            "../test_roms/instr_misc/rom_singles/04-dummy_reads_apu.nes"
+        ));
+    }
+
+    #[test]
+    fn cpu_exec_space_ppuio() {
+        assert_passes(include_bytes!(
+            "../test_roms/cpu_exec_space/test_cpu_exec_space_ppuio.nes"
+        ));
+    }
+
+    #[test]
+    fn cpu_exec_space_apu() {
+        assert_passes(include_bytes!(
+            "../test_roms/cpu_exec_space/test_cpu_exec_space_apu.nes"
+        ));
+    }
 }
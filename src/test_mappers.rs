@@ -0,0 +1,165 @@
+#[cfg(test)]
+mod mappers {
+    use crate::{
+        blargg::{self, Status},
+        cartridge::Cartridge,
+        nes_rom::RomFile,
+        nessy::Nessy,
+        ppu::{palette::PALETTE, Ppu, SCREEN_HEIGHT, SCREEN_WIDTH},
+    };
+    use std::fs;
+
+    /// About 10 seconds of emulated time, the longest ROMs need less than that
+    const MAX_CYCLES: usize = 18_000_000;
+
+    /// The mapper test ROMs aren't in the repository, put them in `test_roms` and run the
+    /// ignored tests. The unit tests of the mappers are what `cargo test` checks without them
+    fn read_rom(path: &str) -> Vec<u8> {
+        let path = format!("{}/test_roms/{}", env!("CARGO_MANIFEST_DIR"), path);
+        fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path, e))
+    }
+
+    fn assert_passes(path: &str) {
+        let result = blargg::run(&read_rom(path), MAX_CYCLES).unwrap();
+        assert_eq!(result.status, Status::Passed, "{}", result.text);
+    }
+
+    /// Compares the picture after `frames` frames with the PPM image next to the ROM
+    ///
+    /// The reference images are checked by eye and kept with the ROMs. When the picture differs
+    /// or there's no reference, it is written next to it as `.actual.ppm` to look at.
+    fn assert_screenshot(path: &str, frames: usize) {
+        let nesfile = RomFile::new(&read_rom(path)).unwrap();
+        let mut nessy = Nessy::new();
+        nessy.load(&nesfile).unwrap();
+        for _ in 1..frames {
+            nessy.run_frame().unwrap();
+        }
+
+        let mut image = format!("P6\n{} {}\n255\n", SCREEN_WIDTH, SCREEN_HEIGHT).into_bytes();
+        for pixel in nessy.run_frame().unwrap().frame_buffer.chunks(4) {
+            image.extend_from_slice(&pixel[..3]);
+        }
+
+        let screenshot = format!(
+            "{}/test_roms/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            path.trim_end_matches(".nes")
+        );
+        let expected = fs::read(format!("{}.ppm", screenshot));
+        if expected.as_ref().ok() != Some(&image) {
+            let actual = format!("{}.actual.ppm", screenshot);
+            fs::write(&actual, &image).unwrap();
+            match expected {
+                Ok(_) => panic!(
+                    "the picture differs from {}.ppm, see {}",
+                    screenshot, actual
+                ),
+                Err(e) => panic!("{}.ppm: {}, the picture is in {}", screenshot, e, actual),
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "needs blargg's mmc3_test in test_roms"]
+    fn mmc3_clocking() {
+        assert_passes("mmc3_test/1-clocking.nes");
+    }
+
+    #[test]
+    #[ignore = "needs blargg's mmc3_test in test_roms"]
+    fn mmc3_details() {
+        assert_passes("mmc3_test/2-details.nes");
+    }
+
+    #[test]
+    #[ignore = "needs blargg's mmc3_test in test_roms"]
+    fn mmc3_a12_clocking() {
+        assert_passes("mmc3_test/3-A12_clocking.nes");
+    }
+
+    #[test]
+    #[ignore = "needs blargg's mmc3_test in test_roms"]
+    fn mmc3_scanline_timing() {
+        assert_passes("mmc3_test/4-scanline_timing.nes");
+    }
+
+    #[test]
+    #[ignore = "needs blargg's mmc3_test in test_roms"]
+    fn mmc3_mmc3() {
+        assert_passes("mmc3_test/5-MMC3.nes");
+    }
+
+    /// The NEC revision is only selected by NES 2.0 headers, the ROM is given one
+    #[test]
+    #[ignore = "needs blargg's mmc3_test in test_roms"]
+    fn mmc3_alt() {
+        let mut rom = read_rom("mmc3_test/6-MMC3_alt.nes");
+        rom[7] = (rom[7] & 0xF0) | 0x08;
+        rom[8] = 0x40; // Mapper 4, submapper 4
+        rom[9] = 0x00;
+        rom[10] = 0x07; // 8KB of PRG RAM
+        rom[11] = if rom[5] == 0 { 0x07 } else { 0x00 }; // 8KB of CHR RAM without CHR ROM
+        for byte in &mut rom[12..16] {
+            *byte = 0;
+        }
+
+        let result = blargg::run(&rom, MAX_CYCLES).unwrap();
+        assert_eq!(result.status, Status::Passed, "{}", result.text);
+    }
+
+    /// Tile $FD on row 15 switches the background to the other CHR bank for the rest of the
+    /// screen, tile $FE at the end of row 29 switches it back for the next frame
+    #[test]
+    fn mmc2_latch_frame() {
+        // 128KB of PRG ROM and 128KB of CHR ROM on mapper 9
+        let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 8, 16, 0x90, 0x00];
+        rom.resize(16 + 8 * 0x4000, 0);
+        let mut chr = vec![0; 16 * 0x2000];
+        // Tile 0 is colour 1 in the 4KB bank 2 and colour 2 in bank 1, tiles $FD and $FE are blank
+        for row in 0..8 {
+            chr[2 * 0x1000 + row] = 0xFF;
+            chr[0x1000 + 8 + row] = 0xFF;
+        }
+        rom.extend_from_slice(&chr);
+
+        let mut cartridge = Cartridge::new(&RomFile::new(&rom).unwrap()).unwrap();
+        cartridge.cpu_write(0xB000, 1); // $FD bank of the first pattern table
+        cartridge.cpu_write(0xC000, 2); // $FE bank
+        let mut ppu = Ppu::new();
+        ppu.memory.nametables[15 * 32] = 0xFD;
+        ppu.memory.nametables[29 * 32 + 31] = 0xFE;
+        ppu.memory.palette[0x00] = 0x0F;
+        ppu.memory.palette[0x01] = 0x16;
+        ppu.memory.palette[0x02] = 0x2A;
+        ppu.write_register(&mut cartridge, 0x2001, 0b00001010);
+
+        while ppu.frame < 2 {
+            ppu.tick(&mut cartridge);
+        }
+
+        let pixel = |x: usize, y: usize| &ppu.frame_buffer[(y * SCREEN_WIDTH + x) * 4..][..3];
+        assert_eq!(pixel(128, 0), &PALETTE[0x16]);
+        assert_eq!(pixel(128, 119), &PALETTE[0x16]);
+        assert_eq!(pixel(4, 120), &PALETTE[0x0F]);
+        assert_eq!(pixel(128, 120), &PALETTE[0x2A]);
+        assert_eq!(pixel(128, 232), &PALETTE[0x2A]);
+        assert_eq!(pixel(128, 239), &PALETTE[0x16]);
+    }
+
+    /// The ring switches CHR banks in the middle of the screen with the MMC2 latches
+    #[test]
+    #[ignore = "needs Punch-Out!! in test_roms"]
+    fn mmc2_punch_out_ring() {
+        // The attract mode fight after the title screen
+        assert_screenshot("punch_out/punch_out.nes", 1800);
+    }
+
+    /// The levels and the status bar are drawn with the MMC5 banks and scanline IRQ
+    #[test]
+    #[ignore = "needs Castlevania III in test_roms"]
+    fn mmc5_castlevania_3() {
+        // The first level of the attract mode demo
+        assert_screenshot("castlevania_3/castlevania_3.nes", 2400);
+    }
+}
@@ -9,9 +9,9 @@ mod nestest {
 
         // Load ROM and decode header
         let rom = nestest;
-        let nesfile = nes_rom::RomFile::new(rom);
+        let nesfile = nes_rom::RomFile::new(rom).unwrap();
 
-        nessy.load_nestest(&nesfile);
+        nessy.load_nestest(&nesfile).unwrap();
 
         let reference = include_str!("../test_roms/nestest.log");
         let mut reference_lines = reference.lines();
@@ -49,7 +49,7 @@ mod nestest {
 
             // print!("\u{001b}[0m");
 
-            nessy.execute();
+            nessy.execute().unwrap();
         }
     }
 }